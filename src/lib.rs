@@ -7,11 +7,32 @@
 //! - `schwab`: Contains modules for Schwab API authentication and market data access.
 //! - `util`: Provides utility functions used across the library.
 
+#[cfg(not(any(feature = "rustls-tls", feature = "native-tls")))]
+compile_error!("enable a TLS backend with either the `rustls-tls` or the `native-tls` feature");
+
 pub mod util;
 
 /// Provides modules for interacting with the Schwab API, including authentication and market data.
 pub mod schwab;
 
-pub use schwab::schwab_api::SchwabApi;
-pub use schwab::schwab_auth::SchwabAuth;
-pub use schwab::schwab_streamer::{SchwabStreamer, SchwabStreamerStartConfig};
+pub use schwab::cache::{FileCache, InMemoryCache, ResponseCache, cache_key};
+pub use schwab::endpoints::SchwabEndpoints;
+pub use schwab::error::{ApiErrorDetail, SchwabApiError, SchwabError};
+pub use schwab::models::parsed::Parsed;
+pub use schwab::pnl::{LotMatching, RealizedPnl, SymbolPnl, realized_pnl, realized_pnl_with};
+pub use schwab::message_stream::MessageStream;
+pub use schwab::quote_book::QuoteBook;
+pub use schwab::requests::{ChainsRequest, OrderRequest, PriceHistoryRequest};
+pub use schwab::retry::RetryConfig;
+pub use schwab::schwab_api::{AccountId, SchwabApi, SchwabApiBuilder};
+pub use schwab::schwab_auth::{
+    AuthorizeCallbackConfig, RefreshShutdown, SchwabAuth, Scope, StoredTokenInfo,
+};
+pub use schwab::token_store::{FileTokenStore, InMemoryTokenStore, TokenStore};
+pub use schwab::transport::HttpTransport;
+#[cfg(any(test, feature = "test-util"))]
+pub use schwab::transport::{MockTransport, RecordedRequest};
+pub use schwab::schwab_streamer::{
+    Backpressure, QosLevel, ReconnectPolicy, SchwabStreamer, SchwabStreamerStartConfig,
+    SubscriptionSnapshot,
+};
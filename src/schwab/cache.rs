@@ -0,0 +1,277 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use async_trait::async_trait;
+use reqwest::Url;
+use tokio::sync::Mutex;
+
+use crate::schwab::error::SchwabError;
+
+/// A pluggable store for market data responses.
+///
+/// When a cache is configured, `SchwabApi` looks up every market data GET request by its
+/// [`cache_key`] before sending it, and answers it from the stored JSON body on a hit. Successful
+/// responses are written back to the cache. Trader endpoints (accounts, orders, transactions) are
+/// never cached.
+///
+/// Errors returned by either method are logged and treated as a miss, so a failing cache never
+/// fails a request.
+#[async_trait]
+pub trait ResponseCache: fmt::Debug + Send + Sync {
+    /// Returns the body stored under `key`, or `None` if there is no fresh entry for it.
+    async fn get(&self, key: &str) -> Result<Option<String>, SchwabError>;
+
+    /// Stores `body` under `key`, replacing any previous entry.
+    async fn put(&self, key: &str, body: &str) -> Result<(), SchwabError>;
+}
+
+/// Returns the canonical cache key of a request URL.
+///
+/// The key is the URL with its query parameters sorted and its fragment removed, so requests
+/// that differ only in parameter order share a key. Use it to pre-seed a cache with recorded
+/// responses.
+pub fn cache_key(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    pairs.sort();
+
+    let mut canonical = url.clone();
+    canonical.set_fragment(None);
+    if pairs.is_empty() {
+        canonical.set_query(None);
+    } else {
+        canonical.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    canonical.to_string()
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    body: String,
+    stored_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    entries: HashMap<String, CacheEntry>,
+    clock: u64,
+}
+
+/// A `ResponseCache` that keeps up to `capacity` responses in memory, evicting the least
+/// recently used one when full.
+///
+/// Clones share the same underlying storage.
+#[derive(Debug, Clone)]
+pub struct InMemoryCache {
+    state: Arc<Mutex<LruState>>,
+    capacity: usize,
+    ttl: Option<Duration>,
+}
+
+impl InMemoryCache {
+    /// Creates an empty cache holding at most `capacity` responses, each for at most `ttl`.
+    /// A `ttl` of `None` keeps entries until they are evicted.
+    pub fn new(capacity: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(LruState::default())),
+            capacity: capacity.max(1),
+            ttl,
+        }
+    }
+
+    /// Removes every entry from the cache.
+    pub async fn clear(&self) {
+        self.state.lock().await.entries.clear();
+    }
+}
+
+#[async_trait]
+impl ResponseCache for InMemoryCache {
+    async fn get(&self, key: &str) -> Result<Option<String>, SchwabError> {
+        let mut state = self.state.lock().await;
+        state.clock += 1;
+        let clock = state.clock;
+
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Ok(None);
+        };
+        if self.ttl.is_some_and(|ttl| entry.stored_at.elapsed() > ttl) {
+            state.entries.remove(key);
+            return Ok(None);
+        }
+        entry.last_used = clock;
+        Ok(Some(entry.body.clone()))
+    }
+
+    async fn put(&self, key: &str, body: &str) -> Result<(), SchwabError> {
+        let mut state = self.state.lock().await;
+        state.clock += 1;
+        let clock = state.clock;
+
+        if !state.entries.contains_key(key) && state.entries.len() >= self.capacity {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+        state.entries.insert(
+            key.to_string(),
+            CacheEntry {
+                body: body.to_string(),
+                stored_at: Instant::now(),
+                last_used: clock,
+            },
+        );
+        Ok(())
+    }
+}
+
+/// A `ResponseCache` that stores every response as a JSON file in a directory.
+///
+/// Each key maps to the file returned by [`FileCache::path_for`], which holds the raw response
+/// body. Entries older than the TTL, judged by the file's modification time, are ignored.
+/// Responses recorded elsewhere can be replayed by writing them to those paths.
+#[derive(Debug, Clone)]
+pub struct FileCache {
+    dir: PathBuf,
+    ttl: Option<Duration>,
+}
+
+impl FileCache {
+    /// Creates a cache in `dir`, which is created on the first write if it does not exist.
+    /// A `ttl` of `None` keeps entries forever.
+    pub fn new(dir: impl Into<PathBuf>, ttl: Option<Duration>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl,
+        }
+    }
+
+    /// Returns the file the response for `key` is stored in.
+    pub fn path_for(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(key)))
+    }
+}
+
+#[async_trait]
+impl ResponseCache for FileCache {
+    async fn get(&self, key: &str) -> Result<Option<String>, SchwabError> {
+        let path = self.path_for(key);
+        let metadata = match tokio::fs::metadata(&path).await {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(ttl) = self.ttl {
+            let age = SystemTime::now()
+                .duration_since(metadata.modified()?)
+                .unwrap_or_default();
+            if age > ttl {
+                return Ok(None);
+            }
+        }
+        Ok(Some(tokio::fs::read_to_string(&path).await?))
+    }
+
+    async fn put(&self, key: &str, body: &str) -> Result<(), SchwabError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(self.path_for(key), body).await?;
+        Ok(())
+    }
+}
+
+/// The 64-bit FNV-1a hash of `key`, stable across runs and platforms unlike `DefaultHasher`.
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    async fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("brokerage-api-{}-{}", std::process::id(), name));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        dir
+    }
+
+    #[test]
+    fn cache_key_sorts_parameters_and_drops_the_fragment() {
+        let key = |url: &str| cache_key(&Url::parse(url).unwrap());
+
+        assert_eq!(
+            key("https://api.schwabapi.com/marketdata/v1/quotes?symbols=AAPL&fields=quote#top"),
+            "https://api.schwabapi.com/marketdata/v1/quotes?fields=quote&symbols=AAPL"
+        );
+        assert_eq!(
+            key("https://api.schwabapi.com/marketdata/v1/quotes?fields=quote&symbols=AAPL"),
+            key("https://api.schwabapi.com/marketdata/v1/quotes?symbols=AAPL&fields=quote")
+        );
+        assert_eq!(
+            key("https://api.schwabapi.com/marketdata/v1/markets?#fragment"),
+            "https://api.schwabapi.com/marketdata/v1/markets"
+        );
+    }
+
+    #[tokio::test]
+    async fn in_memory_cache_evicts_the_least_recently_used_entry() {
+        let cache = InMemoryCache::new(2, None);
+        cache.put("a", "1").await.unwrap();
+        cache.put("b", "2").await.unwrap();
+        cache.get("a").await.unwrap();
+
+        cache.put("c", "3").await.unwrap();
+
+        assert_eq!(cache.get("a").await.unwrap().as_deref(), Some("1"));
+        assert_eq!(cache.get("b").await.unwrap(), None);
+        assert_eq!(cache.get("c").await.unwrap().as_deref(), Some("3"));
+    }
+
+    #[tokio::test]
+    async fn in_memory_cache_expires_entries_after_the_ttl() {
+        let cache = InMemoryCache::new(8, Some(Duration::from_millis(20)));
+        cache.put("a", "1").await.unwrap();
+        assert_eq!(cache.get("a").await.unwrap().as_deref(), Some("1"));
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+
+        assert_eq!(cache.get("a").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn file_cache_judges_the_ttl_by_modification_time() {
+        let dir = temp_dir("file-cache").await;
+        let fresh = FileCache::new(&dir, Some(Duration::from_secs(60)));
+        let forever = FileCache::new(&dir, None);
+        assert_eq!(fresh.get("quotes").await.unwrap(), None);
+
+        fresh.put("quotes", "{}").await.unwrap();
+        assert_eq!(fresh.get("quotes").await.unwrap().as_deref(), Some("{}"));
+
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        File::options()
+            .write(true)
+            .open(fresh.path_for("quotes"))
+            .unwrap()
+            .set_modified(an_hour_ago)
+            .unwrap();
+        assert_eq!(fresh.get("quotes").await.unwrap(), None);
+        assert_eq!(forever.get("quotes").await.unwrap().as_deref(), Some("{}"));
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+use reqwest::Url;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+use tracing::debug;
+
+use crate::schwab::error::SchwabError;
+
+const SUCCESS_PAGE: &str = "<html><body><h1>Authorization complete</h1><p>You can close this window and return to the application.</p></body></html>";
+const FAILURE_PAGE: &str = "<html><body><h1>Authorization failed</h1><p>Return to the application for details.</p></body></html>";
+
+/// Binds the loopback listener that receives the OAuth redirect.
+pub(crate) async fn bind(port: u16) -> Result<TcpListener, SchwabError> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    debug!("Waiting for OAuth redirect on 127.0.0.1:{}", port);
+    Ok(listener)
+}
+
+/// Accepts connections on `listener` until a request carrying the OAuth `code` query parameter
+/// arrives, then returns the decoded code.
+///
+/// Requests without a `code` (such as the browser asking for `/favicon.ico`) are answered and
+/// ignored. An `error` query parameter from the authorization server ends the wait with
+/// `SchwabError::AuthorizationDenied`, and no redirect within `wait` with `SchwabError::Timeout`.
+/// The listener is dropped, closing the port, as soon as this returns or `wait` elapses.
+pub(crate) async fn wait_for_auth_code(
+    listener: TcpListener,
+    wait: Duration,
+) -> Result<String, SchwabError> {
+    timeout(wait, async {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            if let Some(result) = handle_connection(stream).await? {
+                return result;
+            }
+        }
+    })
+    .await
+    .map_err(|_| SchwabError::Timeout(wait))?
+}
+
+/// Reads a single HTTP request and answers it. Returns `None` if the request did not carry an
+/// authorization result.
+async fn handle_connection(
+    mut stream: TcpStream,
+) -> Result<Option<Result<String, SchwabError>>, SchwabError> {
+    // The request line and headers of a browser redirect comfortably fit in one buffer.
+    let mut buffer = vec![0u8; 8192];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+
+    let target = request
+        .lines()
+        .next()
+        .and_then(|request_line| request_line.split_whitespace().nth(1))
+        .unwrap_or("/");
+    let url = Url::parse(&format!("http://127.0.0.1{}", target)).ok();
+    let param = |name: &str| {
+        url.as_ref().and_then(|url| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        })
+    };
+
+    let result = if let Some(code) = param("code") {
+        Some(Ok(code))
+    } else {
+        param("error").map(|error| {
+            Err(SchwabError::AuthorizationDenied {
+                error,
+                description: param("error_description"),
+            })
+        })
+    };
+
+    let (status_line, body) = match &result {
+        Some(Ok(_)) => ("HTTP/1.1 200 OK", SUCCESS_PAGE),
+        Some(Err(_)) => ("HTTP/1.1 400 Bad Request", FAILURE_PAGE),
+        None => ("HTTP/1.1 404 Not Found", ""),
+    };
+    let response = format!(
+        "{}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status_line,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Waits for an authorization code while a browser-style GET for `target` arrives.
+    async fn redirect(target: &str) -> Result<String, SchwabError> {
+        let listener = bind(0).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let wait = tokio::spawn(wait_for_auth_code(listener, Duration::from_secs(5)));
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n", target);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        wait.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn redirect_with_a_code_returns_the_decoded_code() {
+        let code = redirect("/?code=C0.b2F1dGg%40&session=abc").await;
+
+        assert_eq!(code.unwrap(), "C0.b2F1dGg@");
+    }
+
+    #[tokio::test]
+    async fn redirect_with_an_error_is_reported_as_denied() {
+        let denied = redirect("/?error=access_denied&error_description=User%20cancelled").await;
+
+        assert!(matches!(
+            denied,
+            Err(SchwabError::AuthorizationDenied { error, description })
+                if error == "access_denied" && description.as_deref() == Some("User cancelled")
+        ));
+    }
+
+    #[tokio::test]
+    async fn no_redirect_within_the_wait_is_a_timeout() {
+        let listener = bind(0).await.unwrap();
+
+        let result = wait_for_auth_code(listener, Duration::from_millis(50)).await;
+
+        assert!(matches!(
+            result,
+            Err(SchwabError::Timeout(wait)) if wait == Duration::from_millis(50)
+        ));
+    }
+}
@@ -1,15 +1,62 @@
+use std::time::Duration;
+
+use reqwest::Client;
+
 /// The file name where authentication tokens are stored.
 pub(crate) const TOKENS_FILE: &str = "tokens.json";
 /// The base URL for the Schwab Market Data API.
 pub(crate) const SCHWAB_MARKET_DATA_API_URL: &str = "https://api.schwabapi.com/marketdata/v1";
 /// The base URL for Schwab API authorization.
-pub(crate) const SCHWAB_AUTH_URL: &str = "https://api.schwabapi.com/v1/oauth/authorize?response_type=code";
+pub(crate) const SCHWAB_AUTH_URL: &str = "https://api.schwabapi.com/v1/oauth/authorize";
 /// The URL for exchanging authorization codes or refresh tokens for access tokens.
 pub(crate) const SCHWAB_TOKEN_URL: &str = "https://api.schwabapi.com/v1/oauth/token";
 /// The redirect URI used during the OAuth 2.0 authorization flow.
 pub(crate) const REDIRECT_URI: &str = "https://127.0.0.1";
-/// The redirect URI used during the OAuth 2.0 authorization flow.
-/// TODO:
+/// The base URL for the Schwab Trader API.
 pub(crate) const SCHWAB_TRADER_API_URL: &str = "https://api.schwabapi.com/trader/v1";
-/// TODO:
+/// The WebSocket URL for the Schwab streamer.
 pub(crate) const SCHWAB_STREAMER_API_URL: &str = "wss://streamer-api.schwab.com/ws";
+/// The default number of symbols sent in a single `/quotes` request.
+pub(crate) const DEFAULT_QUOTE_CHUNK_SIZE: usize = 250;
+/// The default maximum number of `/quotes` chunk requests in flight at once.
+pub(crate) const DEFAULT_QUOTE_CHUNK_CONCURRENCY: usize = 4;
+/// The maximum number of per-expiration `/chains` requests in flight at once.
+pub(crate) const CHAIN_EXPIRATION_CONCURRENCY: usize = 4;
+/// The widest date range Schwab accepts in a single transactions request, in days.
+pub(crate) const MAX_TRANSACTION_RANGE_DAYS: i64 = 60;
+/// How many days of order history Schwab keeps, and so how far before a day
+/// `SchwabApi::executions` looks for orders that may have filled on it.
+pub(crate) const MAX_ORDER_LOOKBACK_DAYS: i64 = 60;
+/// The widest date range of minute candles Schwab returns, in days.
+pub(crate) const MAX_MINUTE_HISTORY_DAYS: i64 = 48;
+/// The default timeout for a single HTTP request to the Schwab API.
+pub(crate) const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often `SchwabApi::place_order_and_await_fill` polls the status of a new order.
+pub(crate) const ORDER_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// The `User-Agent` sent with API requests unless overridden through the default headers.
+pub(crate) const DEFAULT_USER_AGENT: &str = concat!("brokerage-api/", env!("CARGO_PKG_VERSION"));
+
+/// Builds the HTTP client used when the caller doesn't supply one.
+///
+/// With both TLS features enabled, `native-tls` takes precedence. `tokio-tungstenite` makes
+/// the same choice for the streamer WebSocket, so both connections always share a backend.
+pub(crate) fn default_http_client() -> Client {
+    let builder = Client::builder();
+    #[cfg(feature = "native-tls")]
+    let builder = builder.tls_backend_native();
+    #[cfg(all(feature = "rustls-tls", not(feature = "native-tls")))]
+    let builder = builder.tls_backend_rustls();
+    builder
+        .build()
+        .expect("the TLS backend could not be initialized")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_http_client_builds_with_the_enabled_backend() {
+        default_http_client();
+    }
+}
@@ -0,0 +1,54 @@
+use crate::schwab::common::{
+    SCHWAB_AUTH_URL, SCHWAB_MARKET_DATA_API_URL, SCHWAB_STREAMER_API_URL, SCHWAB_TOKEN_URL,
+    SCHWAB_TRADER_API_URL,
+};
+
+/// The URLs the client sends requests to.
+///
+/// Defaults to Schwab's production endpoints. Override them to run against a mock server or a
+/// recording proxy, then pass the result to `SchwabApiBuilder::endpoints` or
+/// `SchwabAuth::with_endpoints`. `SchwabStreamer` connects to the `streamer` URL of the
+/// `SchwabApi` it is created from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchwabEndpoints {
+    /// The base URL of the Market Data API, e.g. `https://api.schwabapi.com/marketdata/v1`.
+    pub market_data: String,
+    /// The base URL of the Trader API, e.g. `https://api.schwabapi.com/trader/v1`.
+    pub trader: String,
+    /// The OAuth authorization page the user logs in on.
+    pub authorize: String,
+    /// The OAuth endpoint authorization codes and refresh tokens are exchanged at.
+    pub token: String,
+    /// The WebSocket URL of the streamer.
+    pub streamer: String,
+}
+
+impl Default for SchwabEndpoints {
+    fn default() -> Self {
+        Self {
+            market_data: SCHWAB_MARKET_DATA_API_URL.to_string(),
+            trader: SCHWAB_TRADER_API_URL.to_string(),
+            authorize: SCHWAB_AUTH_URL.to_string(),
+            token: SCHWAB_TOKEN_URL.to_string(),
+            streamer: SCHWAB_STREAMER_API_URL.to_string(),
+        }
+    }
+}
+
+impl SchwabEndpoints {
+    /// Returns endpoints that keep Schwab's URL paths but use `base_url` as the host, e.g.
+    /// `http://127.0.0.1:1234` for a local mock server.
+    ///
+    /// The streamer URL is not derived from `base_url`, since it uses a different scheme; set
+    /// `streamer` separately to mock it as well.
+    pub fn with_base_url(base_url: &str) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        Self {
+            market_data: format!("{}/marketdata/v1", base_url),
+            trader: format!("{}/trader/v1", base_url),
+            authorize: format!("{}/v1/oauth/authorize", base_url),
+            token: format!("{}/v1/oauth/token", base_url),
+            ..Self::default()
+        }
+    }
+}
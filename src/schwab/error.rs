@@ -0,0 +1,241 @@
+use std::{fmt, path::PathBuf, time::Duration};
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+use tokio_tungstenite::tungstenite;
+
+use crate::schwab::schwab_auth::Scope;
+
+/// The error type returned by every fallible operation in this crate.
+#[derive(Debug, Error)]
+pub enum SchwabError {
+    /// The HTTP request could not be sent or its body could not be read.
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// Schwab rejected the credentials or tokens (HTTP 401/403, or a failed token exchange).
+    #[error("authentication failed with status {status}: {body}")]
+    Auth { status: StatusCode, body: String },
+
+    /// The OAuth redirect reported that authorization was refused, e.g. because the user
+    /// declined. `error` and `description` are the redirect's `error` and `error_description`
+    /// parameters.
+    #[error(
+        "authorization was denied: {error}{}",
+        description.as_ref().map_or(String::new(), |description| format!(" ({})", description))
+    )]
+    AuthorizationDenied {
+        error: String,
+        description: Option<String>,
+    },
+
+    /// Schwab returned a non-success status other than an authentication or rate-limit failure.
+    ///
+    /// `error` holds the parsed error body when Schwab sent one in its usual format; `body` is
+    /// always the raw response body.
+    #[error(
+        "Schwab API returned status {status}: {}",
+        error.as_ref().map_or(body.clone(), ToString::to_string)
+    )]
+    Api {
+        status: StatusCode,
+        body: String,
+        error: Option<SchwabApiError>,
+    },
+
+    /// A successful response could not be deserialized into the expected type. `raw` holds the
+    /// response body exactly as Schwab returned it.
+    #[error("failed to deserialize response: {source}")]
+    Deserialize {
+        raw: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// A successful response parsed, but lacked the data the call returns, e.g. an empty market
+    /// hours map.
+    #[error("unexpected response: {0}")]
+    UnexpectedResponse(String),
+
+    /// Schwab rejected the request with HTTP 429. `retry_after` is taken from the `Retry-After`
+    /// header when present.
+    #[error("rate limited by Schwab (retry after {retry_after:?})")]
+    RateLimited { retry_after: Option<Duration> },
+
+    /// A request did not complete within the configured timeout, or no authorization input
+    /// arrived within the time allowed for it.
+    #[error("request timed out after {0:?}")]
+    Timeout(Duration),
+
+    /// The access token is no longer accepted, even after a refresh.
+    #[error("access token expired and could not be refreshed")]
+    TokenExpired,
+
+    /// Schwab rejected the refresh token, typically because it is older than seven days. No
+    /// retry can succeed; run `SchwabAuth::authorize` again to obtain a new token pair. `body` is
+    /// Schwab's response.
+    #[error("refresh token expired or revoked; re-authorization is required: {body}")]
+    RefreshTokenExpired { body: String },
+
+    /// A `TokenStore` failed to load or save tokens.
+    #[error("token store error: {0}")]
+    TokenStore(String),
+
+    /// A local I/O operation failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A value could not be serialized to or deserialized from JSON.
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The streamer WebSocket failed.
+    #[error("WebSocket error: {0}")]
+    WebSocket(Box<tungstenite::Error>),
+
+    /// The streamer protocol was violated or the streamer is not in the required state.
+    #[error("streamer error: {0}")]
+    Streamer(String),
+
+    /// The streamer LOGIN was rejected because another session for the account is still connected.
+    #[error("streamer session already connected: {0}")]
+    DuplicateSession(String),
+
+    /// The stored tokens were issued with a scope that does not allow the attempted call.
+    ///
+    /// Tokens keep the scope they were authorized with; authorize again with the required scope.
+    #[error(
+        "tokens were issued with scope `{granted}`, but this call requires `{required}`; re-run authorization with the required scope"
+    )]
+    InsufficientScope { required: Scope, granted: String },
+
+    /// The token file exists but does not contain valid tokens, e.g. after a crash or a full disk
+    /// truncated it. Run the authorization flow again to write a fresh file.
+    #[error(
+        "token file {} is corrupt or unreadable ({source}); re-run SchwabAuth::authorize to create a new one",
+        path.display()
+    )]
+    CorruptTokenFile {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// The client is missing required configuration.
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    /// An argument was rejected before any request was sent.
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    /// The library reached a state it should not, e.g. a request it built could not be cloned.
+    /// Please report it.
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+impl From<tungstenite::Error> for SchwabError {
+    fn from(error: tungstenite::Error) -> Self {
+        SchwabError::WebSocket(Box::new(error))
+    }
+}
+
+/// The JSON body Schwab sends with a rejected request.
+///
+/// The Market Data API sends a list of error objects, while the Trader API sends a `message` and
+/// a list of plain strings; both are parsed into `errors`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct SchwabApiError {
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub errors: Vec<ApiErrorDetail>,
+}
+
+impl SchwabApiError {
+    /// Parses an error response body, returning `None` if it carries no error details.
+    pub(crate) fn parse(body: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(body)
+            .ok()
+            .filter(|error| error.message.is_some() || !error.errors.is_empty())
+    }
+}
+
+impl fmt::Display for SchwabApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts: Vec<String> = self.message.iter().cloned().collect();
+        parts.extend(self.errors.iter().map(ToString::to_string));
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// A single entry of a [`SchwabApiError`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiErrorDetail {
+    pub id: Option<String>,
+    /// The HTTP status, as a string.
+    pub status: Option<String>,
+    pub title: Option<String>,
+    pub detail: Option<String>,
+    /// The request parameters the error refers to, if any.
+    pub parameters: Vec<String>,
+}
+
+impl fmt::Display for ApiErrorDetail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.title, &self.detail) {
+            (Some(title), Some(detail)) => write!(f, "{}: {}", title, detail)?,
+            (Some(text), None) | (None, Some(text)) => write!(f, "{}", text)?,
+            (None, None) => write!(f, "unknown error")?,
+        }
+        if !self.parameters.is_empty() {
+            write!(f, " ({})", self.parameters.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for ApiErrorDetail {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Source {
+            #[serde(default)]
+            parameter: Vec<String>,
+        }
+
+        #[derive(Deserialize)]
+        struct Object {
+            id: Option<String>,
+            status: Option<String>,
+            title: Option<String>,
+            detail: Option<String>,
+            source: Option<Source>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Object(Object),
+            Message(String),
+        }
+
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::Object(object) => ApiErrorDetail {
+                id: object.id,
+                status: object.status,
+                title: object.title,
+                detail: object.detail,
+                parameters: object.source.map(|s| s.parameter).unwrap_or_default(),
+            },
+            Raw::Message(message) => ApiErrorDetail {
+                detail: Some(message),
+                ..Default::default()
+            },
+        })
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{Stream, StreamExt, future};
+use tokio::sync::mpsc;
+
+use crate::schwab::models::streamer::{
+    AccountActivityResponse, ChartEquityResponse, LevelOneEquitiesResponse,
+    LevelOneOptionsResponse, StreamerMessage,
+};
+
+/// The messages of a streamer as a `futures::Stream`, to be composed with `StreamExt`
+/// combinators instead of polling the channel receiver by hand.
+///
+/// Obtained from `SchwabStreamer::message_stream`, or from any receiver returned by
+/// `SchwabStreamer::start_with_config` through `MessageStream::from`. The stream ends when the
+/// streamer stops.
+#[derive(Debug)]
+pub struct MessageStream {
+    receiver: mpsc::Receiver<StreamerMessage>,
+}
+
+impl MessageStream {
+    /// Returns the underlying channel receiver.
+    pub fn into_inner(self) -> mpsc::Receiver<StreamerMessage> {
+        self.receiver
+    }
+
+    /// Keeps only Level 1 equity updates.
+    pub fn equities(self) -> impl Stream<Item = LevelOneEquitiesResponse> + Send {
+        self.filter_map(|message| {
+            future::ready(match message {
+                StreamerMessage::LevelOneEquity(update) => Some(update),
+                _ => None,
+            })
+        })
+    }
+
+    /// Keeps only Level 1 option updates.
+    pub fn options(self) -> impl Stream<Item = LevelOneOptionsResponse> + Send {
+        self.filter_map(|message| {
+            future::ready(match message {
+                StreamerMessage::LevelOneOption(update) => Some(update),
+                _ => None,
+            })
+        })
+    }
+
+    /// Keeps only one-minute equity chart bars.
+    pub fn chart_equities(self) -> impl Stream<Item = ChartEquityResponse> + Send {
+        self.filter_map(|message| {
+            future::ready(match message {
+                StreamerMessage::ChartEquity(bar) => Some(bar),
+                _ => None,
+            })
+        })
+    }
+
+    /// Keeps only order and fill notifications.
+    pub fn account_activity(self) -> impl Stream<Item = AccountActivityResponse> + Send {
+        self.filter_map(|message| {
+            future::ready(match message {
+                StreamerMessage::AccountActivity(activity) => Some(activity),
+                _ => None,
+            })
+        })
+    }
+}
+
+impl From<mpsc::Receiver<StreamerMessage>> for MessageStream {
+    fn from(receiver: mpsc::Receiver<StreamerMessage>) -> Self {
+        Self { receiver }
+    }
+}
+
+impl Stream for MessageStream {
+    type Item = StreamerMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
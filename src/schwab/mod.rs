@@ -2,11 +2,38 @@
 //!
 //! It includes sub-modules for:
 //! - `schwab_api`: Core API client for market data and trading operations.
+//! - `requests`: Builders for endpoints that take many optional parameters.
 //! - `schwab_auth`: Handles the authentication and token management process.
+//! - `retry`: Configures retries with exponential backoff for rate-limited and failed requests.
+//! - `rate_limit`: A client-side token-bucket limiter for outbound requests.
+//! - `message_stream`: Exposes streamer messages as a `futures::Stream`.
+//! - `quote_book`: Maintains the latest streamed quote per symbol.
+//! - `pnl`: Computes realized profit and loss from account transactions.
+//! - `cache`: Pluggable caches that answer repeated market data requests without a network call.
+//! - `transport`: The `HttpTransport` trait requests are sent through, with a mock for tests
+//!   behind the `test-util` feature.
+//! - `token_store`: Pluggable storage backends for OAuth tokens.
+//! - `error`: Defines the `SchwabError` type returned by every fallible operation.
+//! - `callback_server`: A loopback listener that captures the OAuth redirect.
+//! - `endpoints`: The overridable base URLs of the Schwab API.
 //! - `common`: Defines common constants and utilities for the Schwab API integration.
 
 pub mod schwab_api;
 pub mod schwab_streamer;
+pub mod quote_book;
+pub mod message_stream;
+pub mod pnl;
+pub mod requests;
 pub mod schwab_auth;
+pub mod token_store;
+pub mod cache;
+pub mod transport;
+pub mod retry;
+mod rate_limit;
 pub mod models;
+pub mod error;
+pub mod endpoints;
 mod common;
+mod callback_server;
+#[cfg(test)]
+mod test_support;
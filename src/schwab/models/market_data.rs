@@ -1,138 +1,1110 @@
-use serde::Deserialize;
-use std::collections::HashMap;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    ops::Deref,
+};
+
+use super::numbers::{f64_or_nan, f64_or_nan_string, option_f64_or_nan, present};
+use crate::util::{
+    DayCount, epoch_ms_to_utc, epoch_ms_to_utc_opt, parse_expiration_date, year_fraction,
+};
 
 /// The top-level response for a quotes request is a map from symbol to quote data.
 pub type QuotesResponse = HashMap<String, Quote>;
 
+/// The symbols a quotes request could not resolve, reported by Schwab under the `errors` key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QuoteErrors {
+    pub invalid_symbols: Vec<String>,
+    pub invalid_cusips: Vec<String>,
+    pub invalid_ssids: Vec<i64>,
+}
+
+impl QuoteErrors {
+    /// Returns `true` if no symbol was rejected.
+    pub fn is_empty(&self) -> bool {
+        self.invalid_symbols.is_empty()
+            && self.invalid_cusips.is_empty()
+            && self.invalid_ssids.is_empty()
+    }
+
+    /// Appends the rejections of another request.
+    pub fn extend(&mut self, other: QuoteErrors) {
+        self.invalid_symbols.extend(other.invalid_symbols);
+        self.invalid_cusips.extend(other.invalid_cusips);
+        self.invalid_ssids.extend(other.invalid_ssids);
+    }
+}
+
+/// A single `/quotes` response: the quotes keyed by symbol, plus any rejected symbols.
 #[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
+pub(crate) struct QuotesPage {
+    #[serde(default)]
+    pub errors: QuoteErrors,
+    #[serde(flatten)]
+    pub quotes: QuotesResponse,
+}
+
+/// Whether the data in a [`Quote`] is real-time or delayed.
+///
+/// The kind is taken from the top-level `realtime` flag Schwab attaches to every entry of a
+/// `/quotes` response, which reflects the market data entitlements of the account:
+///
+/// - **Equities and ETFs:** `realtime` is `true` for NBBO quotes. Accounts without real-time
+///   entitlements receive non-fee-liable quotes (`quoteType: "NFL"`) with `realtime: false`.
+/// - **Options:** `realtime` is `false` when the account lacks OPRA entitlements.
+/// - **Futures and futures options:** `realtime` is `false` unless the account is entitled to the
+///   relevant exchange's real-time feed; otherwise data is typically delayed by 10-15 minutes.
+/// - **Indices, mutual funds and forex:** reported through the same flag.
+///
+/// If Schwab omits the flag the quote is treated as [`DataKind::Delayed`], so that a missing
+/// field can never be mistaken for real-time data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DataKind {
+    /// Real-time data.
+    Realtime,
+    /// Delayed data.
+    #[default]
+    Delayed,
+}
+
+impl Serialize for DataKind {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bool(*self == DataKind::Realtime)
+    }
+}
+
+impl<'de> Deserialize<'de> for DataKind {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let realtime = Option::<bool>::deserialize(deserializer)?;
+        Ok(match realtime {
+            Some(true) => DataKind::Realtime,
+            _ => DataKind::Delayed,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawQuote", into = "RawQuote")]
 pub struct Quote {
-    pub asset_type: String,
-    pub asset_main_type: String,
+    pub asset_type: AssetType,
+    pub asset_main_type: AssetMainType,
     pub cusip: Option<String>,
     pub symbol: String,
     pub description: String,
-    pub quote: Option<EquityQuote>,
+    /// Whether this quote is real-time or delayed, derived from Schwab's `realtime` flag.
+    pub data_kind: DataKind,
+    /// The `quote` object, in the shape matching `asset_main_type`.
+    pub quote: Option<QuoteData>,
     pub fundamental: Option<FundamentalData>,
     pub extended: Option<ExtendedQuote>,
     pub reference: Option<ReferenceData>,
     pub regular: Option<RegularMarketData>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// A [`Quote`] as sent by Schwab, before its `quote` object is parsed according to the asset type.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawQuote {
+    asset_type: AssetType,
+    asset_main_type: AssetMainType,
+    cusip: Option<String>,
+    symbol: String,
+    description: String,
+    #[serde(rename = "realtime", default)]
+    data_kind: DataKind,
+    quote: Option<serde_json::Value>,
+    fundamental: Option<FundamentalData>,
+    extended: Option<ExtendedQuote>,
+    reference: Option<ReferenceData>,
+    regular: Option<RegularMarketData>,
+}
+
+impl From<Quote> for RawQuote {
+    fn from(quote: Quote) -> Self {
+        RawQuote {
+            asset_type: quote.asset_type,
+            asset_main_type: quote.asset_main_type,
+            cusip: quote.cusip,
+            symbol: quote.symbol,
+            description: quote.description,
+            data_kind: quote.data_kind,
+            quote: quote
+                .quote
+                .and_then(|quote| serde_json::to_value(quote).ok()),
+            fundamental: quote.fundamental,
+            extended: quote.extended,
+            reference: quote.reference,
+            regular: quote.regular,
+        }
+    }
+}
+
+impl TryFrom<RawQuote> for Quote {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawQuote) -> Result<Self, Self::Error> {
+        let quote = raw
+            .quote
+            .map(|quote| QuoteData::parse(&raw.asset_main_type, quote))
+            .transpose()?;
+
+        Ok(Quote {
+            asset_type: raw.asset_type,
+            asset_main_type: raw.asset_main_type,
+            cusip: raw.cusip,
+            symbol: raw.symbol,
+            description: raw.description,
+            data_kind: raw.data_kind,
+            quote,
+            fundamental: raw.fundamental,
+            extended: raw.extended,
+            reference: raw.reference,
+            regular: raw.regular,
+        })
+    }
+}
+
+/// The `quote` object of a [`Quote`], whose fields depend on the asset type.
+///
+/// Serializes to the plain `quote` object, without the variant name.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum QuoteData {
+    /// Equities, ETFs and mutual funds (`EQUITY` and `MUTUAL_FUND`).
+    Equity(EquityQuote),
+    /// Options (`OPTION`).
+    Option(Box<OptionQuote>),
+    /// Indices such as `$SPX` (`INDEX`).
+    Index(IndexQuote),
+    /// Currency pairs (`FOREX`).
+    Forex(ForexQuote),
+    /// Futures (`FUTURE`).
+    Future(FutureQuote),
+    /// Options on futures (`FUTURE_OPTION`).
+    FutureOption(FutureOptionQuote),
+    /// Any other asset type, such as bonds, left as raw JSON.
+    Other(serde_json::Value),
+}
+
+impl QuoteData {
+    /// Parses a `quote` object according to the quote's `assetMainType`.
+    fn parse(
+        asset_main_type: &AssetMainType,
+        quote: serde_json::Value,
+    ) -> Result<Self, serde_json::Error> {
+        Ok(match asset_main_type {
+            AssetMainType::Equity | AssetMainType::MutualFund => {
+                QuoteData::Equity(serde_json::from_value(quote)?)
+            }
+            AssetMainType::Option => QuoteData::Option(serde_json::from_value(quote)?),
+            AssetMainType::Index => QuoteData::Index(serde_json::from_value(quote)?),
+            AssetMainType::Forex => QuoteData::Forex(serde_json::from_value(quote)?),
+            AssetMainType::Future => QuoteData::Future(serde_json::from_value(quote)?),
+            AssetMainType::FutureOption => QuoteData::FutureOption(serde_json::from_value(quote)?),
+            AssetMainType::Bond | AssetMainType::Unknown(_) => QuoteData::Other(quote),
+        })
+    }
+
+    /// Returns the last traded price, if the asset type reports one.
+    pub fn last_price(&self) -> Option<f64> {
+        match self {
+            QuoteData::Equity(quote) => quote.last_price,
+            QuoteData::Option(quote) => quote.last_price,
+            QuoteData::Index(quote) => quote.last_price,
+            QuoteData::Forex(quote) => quote.last_price,
+            QuoteData::Future(quote) => quote.last_price,
+            QuoteData::FutureOption(quote) => quote.last_price,
+            QuoteData::Other(quote) => quote.get("lastPrice").and_then(serde_json::Value::as_f64),
+        }
+    }
+
+    /// Returns the parsed security status, if the asset type reports one.
+    pub fn status(&self) -> Option<SecurityStatus> {
+        match self {
+            QuoteData::Equity(quote) => Some(quote.security_status.clone()),
+            QuoteData::Option(quote) => quote.security_status.clone(),
+            QuoteData::Index(quote) => quote.security_status.clone(),
+            QuoteData::Forex(quote) => quote.security_status.clone(),
+            QuoteData::Future(quote) => quote.security_status.clone(),
+            QuoteData::FutureOption(quote) => quote.security_status.clone(),
+            QuoteData::Other(quote) => quote
+                .get("securityStatus")
+                .and_then(|s| s.as_str())
+                .map(SecurityStatus::from),
+        }
+    }
+}
+
+impl Quote {
+    /// Returns the `quote` object of an equity, ETF or mutual fund quote.
+    pub fn equity_quote(&self) -> Option<&EquityQuote> {
+        match self.quote.as_ref()? {
+            QuoteData::Equity(quote) => Some(quote),
+            _ => None,
+        }
+    }
+
+    /// Returns the `quote` object of an option quote.
+    pub fn option_quote(&self) -> Option<&OptionQuote> {
+        match self.quote.as_ref()? {
+            QuoteData::Option(quote) => Some(quote),
+            _ => None,
+        }
+    }
+
+    /// Returns the `quote` object of a futures quote.
+    pub fn future_quote(&self) -> Option<&FutureQuote> {
+        match self.quote.as_ref()? {
+            QuoteData::Future(quote) => Some(quote),
+            _ => None,
+        }
+    }
+
+    /// Returns the `quote` object of a futures option quote.
+    pub fn future_option_quote(&self) -> Option<&FutureOptionQuote> {
+        match self.quote.as_ref()? {
+            QuoteData::FutureOption(quote) => Some(quote),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this quote carries real-time data.
+    pub fn is_realtime(&self) -> bool {
+        self.data_kind == DataKind::Realtime
+    }
+
+    /// Returns the net asset value of a mutual fund or ETF, or `None` for other assets.
+    ///
+    /// This is the official NAV and is distinct from `last_price`, the last trade.
+    pub fn nav(&self) -> Option<f64> {
+        self.equity_quote()
+            .and_then(|quote| quote.nav)
+            .filter(|&nav| nav != 0.0)
+    }
+
+    /// Returns the time the NAV returned by [`Quote::nav`] is as of, where Schwab reports it.
+    ///
+    /// Mutual funds and ETFs both report it through `tradeTime`. For an ETF this is the time of
+    /// the official NAV, while `last_price` and `last_trade_time` follow intraday trading.
+    pub fn nav_as_of(&self) -> Option<DateTime<Utc>> {
+        self.nav()?;
+        self.equity_quote()
+            .and_then(|quote| quote.trade_time)
+            .and_then(DateTime::from_timestamp_millis)
+    }
+
+    /// Returns the last price traded in the pre- or post-market session.
+    ///
+    /// Schwab reports `0` when there has been no extended-hours trade, which is returned as `None`.
+    pub fn extended_hours_last(&self) -> Option<f64> {
+        self.extended
+            .as_ref()
+            .and_then(|extended| extended.last_price)
+            .filter(|&price| price != 0.0)
+    }
+}
+
+/// A [`Quote`] that is known to carry real-time data.
+///
+/// Obtained through `RealtimeQuote::try_from(quote)`, which hands back a [`DelayedQuote`] when the
+/// data is delayed. Trading logic that must never act on delayed prices can require this type in
+/// its signatures instead of checking [`Quote::data_kind`] at runtime.
+#[derive(Debug, Clone)]
+pub struct RealtimeQuote(Quote);
+
+/// A [`Quote`] that is known to carry delayed data.
+#[derive(Debug, Clone)]
+pub struct DelayedQuote(Quote);
+
+impl RealtimeQuote {
+    /// Returns the underlying [`Quote`].
+    pub fn into_inner(self) -> Quote {
+        self.0
+    }
+}
+
+impl DelayedQuote {
+    /// Returns the underlying [`Quote`].
+    pub fn into_inner(self) -> Quote {
+        self.0
+    }
+}
+
+impl TryFrom<Quote> for RealtimeQuote {
+    type Error = DelayedQuote;
+
+    fn try_from(quote: Quote) -> Result<Self, Self::Error> {
+        match quote.data_kind {
+            DataKind::Realtime => Ok(RealtimeQuote(quote)),
+            DataKind::Delayed => Err(DelayedQuote(quote)),
+        }
+    }
+}
+
+impl Deref for RealtimeQuote {
+    type Target = Quote;
+
+    fn deref(&self) -> &Quote {
+        &self.0
+    }
+}
+
+impl Deref for DelayedQuote {
+    type Target = Quote;
+
+    fn deref(&self) -> &Quote {
+        &self.0
+    }
+}
+
+/// The `quote` object of an equity, ETF or mutual fund [`Quote`].
+///
+/// Mutual funds only report the 52-week range, the close, the net change, the status, the volume,
+/// the NAV and the NAV time, so the other fields are `None` for them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EquityQuote {
     #[serde(rename = "52WeekHigh")]
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub fifty_two_week_high: f64,
     #[serde(rename = "52WeekLow")]
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub fifty_two_week_low: f64,
-    pub ask_mic_id: String,
-    pub ask_price: f64,
-    pub ask_size: i64,
-    pub bid_mic_id: String,
-    pub bid_price: f64,
-    pub bid_size: i64,
+    pub ask_mic_id: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub ask_price: Option<f64>,
+    pub ask_size: Option<i64>,
+    pub bid_mic_id: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub bid_price: Option<f64>,
+    pub bid_size: Option<i64>,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub close_price: f64,
-    pub high_price: f64,
-    pub last_mic_id: String,
-    pub last_price: f64,
-    pub last_size: i64,
-    pub low_price: f64,
-    pub mark: f64,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub high_price: Option<f64>,
+    pub last_mic_id: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub last_price: Option<f64>,
+    pub last_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub low_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub mark: Option<f64>,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub net_change: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub net_percent_change: f64,
-    pub open_price: f64,
-    pub quote_time_in_long: i64,
-    pub security_status: String,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub open_price: Option<f64>,
+    pub quote_time_in_long: Option<i64>,
+    pub security_status: SecurityStatus,
     pub total_volume: i64,
-    pub trade_time_in_long: i64,
+    pub trade_time_in_long: Option<i64>,
+    /// The net asset value for mutual funds and ETFs.
+    #[serde(rename = "nAV", alias = "nav")]
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub nav: Option<f64>,
+    /// For mutual funds and ETFs, the time the current NAV was struck, in milliseconds since the
+    /// epoch.
+    pub trade_time: Option<i64>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// The `quote` object of an option [`Quote`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionQuote {
+    #[serde(rename = "52WeekHigh")]
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub fifty_two_week_high: Option<f64>,
+    #[serde(rename = "52WeekLow")]
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub fifty_two_week_low: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub ask_price: Option<f64>,
+    pub ask_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub bid_price: Option<f64>,
+    pub bid_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub close_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub high_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub last_price: Option<f64>,
+    pub last_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub low_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub mark: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub mark_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub mark_percent_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub net_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub net_percent_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub open_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub open_interest: Option<f64>,
+    pub quote_time: Option<i64>,
+    pub trade_time: Option<i64>,
+    pub total_volume: Option<i64>,
+    pub security_status: Option<SecurityStatus>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub delta: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub gamma: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub theta: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub vega: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub rho: Option<f64>,
+    /// The implied volatility, in percent.
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub volatility: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub implied_yield: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub money_intrinsic_value: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub theoretical_option_value: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub time_value: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub underlying_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub ind_ask_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub ind_bid_price: Option<f64>,
+    pub ind_quote_time: Option<i64>,
+}
+
+/// The `quote` object of an index [`Quote`]. Indices are not traded, so there is no bid or ask.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexQuote {
+    #[serde(rename = "52WeekHigh")]
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub fifty_two_week_high: Option<f64>,
+    #[serde(rename = "52WeekLow")]
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub fifty_two_week_low: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub close_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub high_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub last_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub low_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub net_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub net_percent_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub open_price: Option<f64>,
+    pub security_status: Option<SecurityStatus>,
+    pub total_volume: Option<i64>,
+    pub trade_time: Option<i64>,
+}
+
+/// The `quote` object of a forex [`Quote`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForexQuote {
+    #[serde(rename = "52WeekHigh")]
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub fifty_two_week_high: Option<f64>,
+    #[serde(rename = "52WeekLow")]
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub fifty_two_week_low: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub ask_price: Option<f64>,
+    pub ask_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub bid_price: Option<f64>,
+    pub bid_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub close_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub high_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub last_price: Option<f64>,
+    pub last_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub low_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub mark: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub net_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub net_percent_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub open_price: Option<f64>,
+    pub quote_time: Option<i64>,
+    pub security_status: Option<SecurityStatus>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub tick: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub tick_amount: Option<f64>,
+    pub total_volume: Option<i64>,
+    pub trade_time: Option<i64>,
+}
+
+/// The `quote` object of a futures [`Quote`], such as `/ES`.
+///
+/// The settlement price and contract details are reported in the quote's `reference` object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FutureQuote {
+    #[serde(rename = "askMICId")]
+    pub ask_mic_id: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub ask_price: Option<f64>,
+    pub ask_size: Option<i64>,
+    pub ask_time: Option<i64>,
+    #[serde(rename = "bidMICId")]
+    pub bid_mic_id: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub bid_price: Option<f64>,
+    pub bid_size: Option<i64>,
+    pub bid_time: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub close_price: Option<f64>,
+    /// The change from the previous settlement, as a fraction rather than in percent.
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub future_percent_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub high_price: Option<f64>,
+    #[serde(rename = "lastMICId")]
+    pub last_mic_id: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub last_price: Option<f64>,
+    pub last_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub low_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub mark: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub net_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub open_interest: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub open_price: Option<f64>,
+    pub quote_time: Option<i64>,
+    /// Whether the contract is currently quoted in an active session.
+    pub quoted_in_session: Option<bool>,
+    pub security_status: Option<SecurityStatus>,
+    /// The time of the last settlement, in milliseconds since the epoch.
+    pub settle_time: Option<i64>,
+    /// The minimum price increment.
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub tick: Option<f64>,
+    /// The dollar value of one `tick`.
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub tick_amount: Option<f64>,
+    pub total_volume: Option<i64>,
+    pub trade_time: Option<i64>,
+}
+
+/// The `quote` object of a futures option [`Quote`], such as `./OZCZ24C565`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FutureOptionQuote {
+    #[serde(rename = "askMICId")]
+    pub ask_mic_id: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub ask_price: Option<f64>,
+    pub ask_size: Option<i64>,
+    #[serde(rename = "bidMICId")]
+    pub bid_mic_id: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub bid_price: Option<f64>,
+    pub bid_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub close_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub high_price: Option<f64>,
+    #[serde(rename = "lastMICId")]
+    pub last_mic_id: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub last_price: Option<f64>,
+    pub last_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub low_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub mark: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub mark_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub net_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub net_percent_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub open_interest: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub open_price: Option<f64>,
+    pub quote_time: Option<i64>,
+    pub security_status: Option<SecurityStatus>,
+    /// The settlement price. Schwab misspells the key as `settlemetPrice`.
+    #[serde(rename = "settlemetPrice", alias = "settlementPrice")]
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub settlement_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub tick: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub tick_amount: Option<f64>,
+    pub total_volume: Option<i64>,
+    pub trade_time: Option<i64>,
+}
+
+impl EquityQuote {
+    /// Returns `quote_time_in_long` as a UTC time, or `None` for mutual funds.
+    pub fn quote_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc_opt(self.quote_time_in_long)
+    }
+
+    /// Returns `trade_time_in_long`, the time of the last trade, as a UTC time, or `None` for
+    /// mutual funds.
+    pub fn last_trade_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc_opt(self.trade_time_in_long)
+    }
+
+    /// Returns a copy of `security_status`.
+    pub fn status(&self) -> SecurityStatus {
+        self.security_status.clone()
+    }
+}
+
+/// Implements `Serialize` and `Deserialize` for an enum with an `Unknown(String)` fallback
+/// through its `as_str` method and `From<&str>` impl, so unrecognized values survive a round trip.
+macro_rules! serde_via_str {
+    ($name:ident) => {
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                Ok($name::from(value.as_str()))
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
+
+/// Defines `as_str`, `From<&str>` and the serde impls of a string enum from its variants and the
+/// exact values Schwab sends for them. Anything else becomes `Unknown`.
+macro_rules! string_enum {
+    ($name:ident { $($variant:ident => $value:literal),+ $(,)? }) => {
+        impl $name {
+            /// Returns the value as sent by Schwab.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $value,)+
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($value => $name::$variant,)+
+                    _ => $name::Unknown(value.to_string()),
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::Unknown(String::new())
+            }
+        }
+
+        serde_via_str!($name);
+    };
+}
+
+/// The broad asset class of a [`Quote`], which decides the shape of its `quote` object.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AssetMainType {
+    Bond,
+    Equity,
+    Forex,
+    Future,
+    FutureOption,
+    Index,
+    MutualFund,
+    Option,
+    /// A type this library does not recognize, as sent by Schwab.
+    Unknown(String),
+}
+
+string_enum!(AssetMainType {
+    Bond => "BOND",
+    Equity => "EQUITY",
+    Forex => "FOREX",
+    Future => "FUTURE",
+    FutureOption => "FUTURE_OPTION",
+    Index => "INDEX",
+    MutualFund => "MUTUAL_FUND",
+    Option => "OPTION",
+});
+
+/// The asset type of a quote, instrument, position or transaction.
+///
+/// Quotes and instruments use the market data names, such as `EQUITY` or `FUTURE_OPTION`, while
+/// accounts also report `COLLECTIVE_INVESTMENT`, `CASH_EQUIVALENT` and `CURRENCY`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AssetType {
+    Bond,
+    CashEquivalent,
+    CollectiveInvestment,
+    Currency,
+    Equity,
+    FixedIncome,
+    Forex,
+    Future,
+    FutureOption,
+    Index,
+    MutualFund,
+    Option,
+    Product,
+    /// A type this library does not recognize, or an empty type.
+    Unknown(String),
+}
+
+string_enum!(AssetType {
+    Bond => "BOND",
+    CashEquivalent => "CASH_EQUIVALENT",
+    CollectiveInvestment => "COLLECTIVE_INVESTMENT",
+    Currency => "CURRENCY",
+    Equity => "EQUITY",
+    FixedIncome => "FIXED_INCOME",
+    Forex => "FOREX",
+    Future => "FUTURE",
+    FutureOption => "FUTURE_OPTION",
+    Index => "INDEX",
+    MutualFund => "MUTUAL_FUND",
+    Option => "OPTION",
+    Product => "PRODUCT",
+});
+
+/// Whether a [`Mover`] moved up or down.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MoverDirection {
+    Up,
+    Down,
+    /// A direction this library does not recognize, or an empty direction when the endpoint did
+    /// not send one.
+    Unknown(String),
+}
+
+string_enum!(MoverDirection {
+    Up => "up",
+    Down => "down",
+});
+
+/// The trading status of a security, as reported in the `security_status` of every quote type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SecurityStatus {
+    /// The security is trading normally.
+    Normal,
+    /// Trading in the security has been halted by the exchange or a regulator.
+    Halted,
+    /// The security's market is closed.
+    Closed,
+    /// A status this library does not recognize, or an empty status.
+    Unknown(String),
+}
+
+impl SecurityStatus {
+    /// Returns `true` if orders for the security can be expected to execute.
+    pub fn is_tradable(&self) -> bool {
+        *self == SecurityStatus::Normal
+    }
+
+    /// Returns the status as sent by Schwab.
+    pub fn as_str(&self) -> &str {
+        match self {
+            SecurityStatus::Normal => "Normal",
+            SecurityStatus::Halted => "Halted",
+            SecurityStatus::Closed => "Closed",
+            SecurityStatus::Unknown(status) => status,
+        }
+    }
+}
+
+impl Default for SecurityStatus {
+    fn default() -> Self {
+        SecurityStatus::Unknown(String::new())
+    }
+}
+
+serde_via_str!(SecurityStatus);
+
+impl From<&str> for SecurityStatus {
+    fn from(status: &str) -> Self {
+        match status.to_ascii_lowercase().as_str() {
+            "normal" => SecurityStatus::Normal,
+            "halted" => SecurityStatus::Halted,
+            "closed" => SecurityStatus::Closed,
+            _ => SecurityStatus::Unknown(status.to_string()),
+        }
+    }
+}
+
+/// The overnight gap between the previous close and today's open for a single symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GapInfo {
+    /// The previous session's closing price.
+    pub previous_close: f64,
+    /// Today's opening price, or `None` if the symbol has not opened yet.
+    pub open: Option<f64>,
+    /// `open - previous_close`, or `None` if the symbol has not opened yet.
+    pub gap_amount: Option<f64>,
+    /// The gap as a percentage of the previous close, or `None` if the symbol has not opened yet
+    /// or has no previous close.
+    pub gap_percent: Option<f64>,
+}
+
+impl GapInfo {
+    /// Computes the gap from an equity quote.
+    ///
+    /// Schwab reports `openPrice` as `0` before the first trade of the session, which is treated as
+    /// "not opened yet", as is a quote without an `openPrice`, such as a mutual fund's.
+    pub fn from_quote(quote: &EquityQuote) -> Self {
+        let previous_close = quote.close_price;
+        let open = quote.open_price.filter(|&open| open != 0.0);
+        let gap_amount = open.map(|open| open - previous_close);
+        let gap_percent = gap_amount
+            .filter(|_| previous_close != 0.0)
+            .map(|gap| gap / previous_close * 100.0);
+
+        Self {
+            previous_close,
+            open,
+            gap_amount,
+            gap_percent,
+        }
+    }
+}
+
+/// Quotes and today's equity market hours, fetched together by `SchwabApi::snapshot`.
+#[derive(Debug, Clone)]
+pub struct MarketSnapshot {
+    /// The quotes of the requested symbols, keyed by symbol.
+    pub quotes: QuotesResponse,
+    /// Today's hours of the equity market.
+    pub equity_hours: MarketHours,
+}
+
+/// The `fundamental` object of an equity, ETF or mutual fund [`Quote`].
+///
+/// Schwab leaves out the fields that do not apply to the security, such as the dividend dates of
+/// a stock that pays no dividend, so every field is optional.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FundamentalData {
-    pub avg10_day_volume: i64,
-    pub avg1_year_volume: i64,
-    pub declaration_date: String,
-    pub div_amount: f64,
-    pub div_ex_date: String,
-    pub div_freq: i32,
-    pub div_pay_date: String,
-    pub div_yield: f64,
-    pub eps: f64,
-    pub exchange: String,
-    pub high52: f64,
-    pub last_earnings_date: String,
-    pub low52: f64,
-    pub market_cap: f64,
-    pub market_cap_float: f64,
-    pub pe_ratio: f64,
-    pub peg_ratio: f64,
-    pub pb_ratio: f64,
-    pub pr_ratio: f64,
-    pub pcf_ratio: f64,
-    pub gross_margin_ttm: f64,
-    pub net_profit_margin_ttm: f64,
-    pub operating_margin_ttm: f64,
-    pub return_on_equity: f64,
-    pub return_on_assets: f64,
-    pub return_on_investment: f64,
-    pub quick_ratio: f64,
-    pub current_ratio: f64,
-    pub interest_coverage: f64,
-    pub total_debt_to_capital: f64,
-    pub lt_debt_to_equity: f64,
-    pub total_debt_to_equity: f64,
-    pub revenue_per_share_ttm: f64,
-    pub book_value_per_share: f64,
-    pub short_int_to_float: f64,
-    pub short_int_day_to_cover: f64,
-    pub shares_outstanding: i64,
-    pub beta: f64,
-    pub volatility: f64,
+    pub avg10_day_volume: Option<i64>,
+    pub avg1_year_volume: Option<i64>,
+    pub declaration_date: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub div_amount: Option<f64>,
+    pub div_ex_date: Option<String>,
+    pub div_freq: Option<i32>,
+    pub div_pay_date: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub div_yield: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub eps: Option<f64>,
+    pub exchange: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub high52: Option<f64>,
+    pub last_earnings_date: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub low52: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub market_cap: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub market_cap_float: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub pe_ratio: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub peg_ratio: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub pb_ratio: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub pr_ratio: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub pcf_ratio: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub gross_margin_ttm: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub net_profit_margin_ttm: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub operating_margin_ttm: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub return_on_equity: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub return_on_assets: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub return_on_investment: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub quick_ratio: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub current_ratio: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub interest_coverage: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub total_debt_to_capital: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub lt_debt_to_equity: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub total_debt_to_equity: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub revenue_per_share_ttm: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub book_value_per_share: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub short_int_to_float: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub short_int_day_to_cover: Option<f64>,
+    pub shares_outstanding: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub beta: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub volatility: Option<f64>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExtendedQuote {
-    // Define fields for the "extended" object if needed
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub ask_price: Option<f64>,
+    pub ask_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub bid_price: Option<f64>,
+    pub bid_size: Option<i64>,
+    /// The last price traded in the pre- or post-market session.
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub last_price: Option<f64>,
+    pub last_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub mark: Option<f64>,
+    pub quote_time: Option<i64>,
+    pub total_volume: Option<i64>,
+    pub trade_time: Option<i64>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReferenceData {
-    // Define fields for the "reference" object if needed
+    pub cusip: Option<String>,
+    pub description: Option<String>,
+    pub exchange: Option<String>,
+    pub exchange_name: Option<String>,
+    /// For futures, the currently active (front-month) contract, e.g. `/ESZ24` for `/ES`.
+    pub future_active_symbol: Option<String>,
+    pub future_expiration_date: Option<i64>,
+    pub future_is_active: Option<bool>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub future_multiplier: Option<f64>,
+    /// The display format of futures prices, e.g. `D,D` for decimals.
+    pub future_price_format: Option<String>,
+    /// The last settlement price of a futures contract.
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub future_settlement_price: Option<f64>,
+    pub future_trading_hours: Option<String>,
+    pub product: Option<String>,
+    /// For options, `P` or `C`.
+    pub contract_type: Option<String>,
+    pub underlying: Option<String>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub strike_price: Option<f64>,
+    pub days_to_expiration: Option<i64>,
+    pub expiration_day: Option<u32>,
+    pub expiration_month: Option<u32>,
+    pub expiration_year: Option<i32>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub multiplier: Option<f64>,
+    pub exercise_type: Option<String>,
+    pub settlement_type: Option<String>,
+    pub is_hard_to_borrow: Option<bool>,
+    pub is_shortable: Option<bool>,
+    /// The annualized borrow fee rate for hard-to-borrow securities, in percent.
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub htb_rate: Option<f64>,
+    /// The quantity available to borrow for hard-to-borrow securities.
+    pub htb_quantity: Option<i64>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// The `regular` object of a [`Quote`]: the last trade of the regular session, unaffected by
+/// extended-hours trading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RegularMarketData {
-    // Define fields for the "regular" object if needed
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub regular_market_last_price: Option<f64>,
+    pub regular_market_last_size: Option<i64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub regular_market_net_change: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub regular_market_percent_change: Option<f64>,
+    /// The time of the last regular-session trade, in milliseconds since the epoch.
+    pub regular_market_trade_time: Option<i64>,
 }
 
 /// A type alias for the complex nested map of expiration dates to strikes to contracts.
 pub type ExpirationMap = HashMap<String, HashMap<String, Vec<OptionContract>>>;
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum PutCall {
     Put,
     Call,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChainsResponse {
     pub symbol: String,
     pub status: String,
     pub underlying: Option<UnderlyingInfo>,
     pub strategy: String,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub interval: f64,
     pub is_delayed: bool,
     pub is_index: bool,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub interest_rate: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub underlying_price: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub volatility: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub days_to_expiration: f64,
     pub number_of_contracts: i64,
     #[serde(rename = "callExpDateMap")]
@@ -141,36 +1113,134 @@ pub struct ChainsResponse {
     pub put_exp_date_map: ExpirationMap,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl ChainsResponse {
+    /// Adds the contracts of `other`, a response for another part of the same chain, to this one.
+    ///
+    /// Contracts already present under the same expiration and strike are skipped by symbol, so
+    /// overlapping responses can be merged safely. `number_of_contracts` is recomputed; every
+    /// other field keeps the value from `self`.
+    pub fn merge(&mut self, other: ChainsResponse) {
+        merge_expiration_map(&mut self.call_exp_date_map, other.call_exp_date_map);
+        merge_expiration_map(&mut self.put_exp_date_map, other.put_exp_date_map);
+        if self.underlying.is_none() {
+            self.underlying = other.underlying;
+        }
+
+        self.number_of_contracts = [&self.call_exp_date_map, &self.put_exp_date_map]
+            .into_iter()
+            .flat_map(|map| map.values())
+            .flat_map(|strikes| strikes.values())
+            .map(|contracts| contracts.len() as i64)
+            .sum();
+    }
+
+    /// Returns every contract in the chain, calls first, then puts.
+    ///
+    /// Contracts are not ordered by expiration or strike.
+    pub fn all_contracts(&self) -> impl Iterator<Item = &OptionContract> {
+        [&self.call_exp_date_map, &self.put_exp_date_map]
+            .into_iter()
+            .flat_map(|map| map.values())
+            .flat_map(|strikes| strikes.values())
+            .flatten()
+    }
+
+    /// Returns the earliest expiration date in the chain, e.g. `2024-01-19`.
+    ///
+    /// Schwab keys expirations as `<date>:<days to expiration>`; only the date is returned.
+    pub fn nearest_expiration(&self) -> Option<&str> {
+        self.call_exp_date_map
+            .keys()
+            .chain(self.put_exp_date_map.keys())
+            .map(|key| expiration_key_date(key))
+            .min()
+    }
+
+    /// Returns the calls and puts that expire on `date`, calls first.
+    ///
+    /// `date` is either the date alone, e.g. `2024-01-19`, or a full expiration key such as
+    /// `2024-01-19:5`.
+    pub fn contracts_for_expiration<'a>(
+        &'a self,
+        date: &'a str,
+    ) -> impl Iterator<Item = &'a OptionContract> {
+        let date = expiration_key_date(date);
+        [&self.call_exp_date_map, &self.put_exp_date_map]
+            .into_iter()
+            .flat_map(move |map| {
+                map.iter()
+                    .filter(move |(key, _)| expiration_key_date(key) == date)
+                    .flat_map(|(_, strikes)| strikes.values())
+            })
+            .flatten()
+    }
+}
+
+/// Returns the date part of an [`ExpirationMap`] key, which has the form `<date>:<days>`.
+fn expiration_key_date(key: &str) -> &str {
+    key.split_once(':').map_or(key, |(date, _)| date)
+}
+
+fn merge_expiration_map(into: &mut ExpirationMap, from: ExpirationMap) {
+    for (expiration, strikes) in from {
+        let into_strikes = into.entry(expiration).or_default();
+        for (strike, contracts) in strikes {
+            let into_contracts = into_strikes.entry(strike).or_default();
+            let mut seen: HashSet<String> =
+                into_contracts.iter().map(|c| c.symbol.clone()).collect();
+            into_contracts.extend(
+                contracts
+                    .into_iter()
+                    .filter(|contract| seen.insert(contract.symbol.clone())),
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UnderlyingInfo {
     pub symbol: String,
     pub description: String,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub change: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub percent_change: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub close: f64,
     pub quote_time: i64,
     pub trade_time: i64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub bid: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub ask: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub last: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub mark: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub mark_change: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub mark_percent_change: f64,
     pub bid_size: i64,
     pub ask_size: i64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub high_price: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub low_price: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub open_price: f64,
     pub total_volume: i64,
     #[serde(rename = "52WeekHigh")]
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub fifty_two_week_high: f64,
     #[serde(rename = "52WeekLow")]
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub fifty_two_week_low: f64,
     pub delayed: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OptionContract {
     #[serde(rename = "putCall")]
@@ -178,38 +1248,58 @@ pub struct OptionContract {
     pub symbol: String,
     pub description: String,
     pub exchange_name: String,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub bid: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub ask: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub last: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub mark: f64,
     pub bid_size: i64,
     pub ask_size: i64,
     pub bid_ask_size: String,
     pub last_size: i64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub high_price: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub low_price: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub open_price: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub close_price: f64,
     pub total_volume: i64,
     pub trade_date: Option<String>,
     pub trade_time_in_long: i64,
     pub quote_time_in_long: i64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub net_change: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub volatility: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub delta: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub gamma: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub theta: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub vega: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub rho: f64,
     pub open_interest: i64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub time_value: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub theoretical_option_value: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub theoretical_volatility: f64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub strike_price: f64,
     pub expiration_date: String,
     pub days_to_expiration: i64,
     pub expiration_type: String,
     pub last_trading_day: i64,
+    #[serde(deserialize_with = "f64_or_nan", serialize_with = "f64_or_nan_string")]
     pub multiplier: f64,
     pub settlement_type: String,
     pub deliverable_note: String,
@@ -217,7 +1307,75 @@ pub struct OptionContract {
     pub is_penny_pilot: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl OptionContract {
+    /// Returns `quote_time_in_long` as a UTC time, or `None` if it is out of range.
+    pub fn quote_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc(self.quote_time_in_long)
+    }
+
+    /// Returns `trade_time_in_long`, the time of the last trade, as a UTC time, or `None` if it is
+    /// out of range.
+    pub fn last_trade_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc(self.trade_time_in_long)
+    }
+
+    /// Returns `last_trading_day` as a UTC time, or `None` if it is out of range.
+    pub fn last_trading_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc(self.last_trading_day)
+    }
+
+    /// Returns the parsed `expiration_date`, or `None` if it is not in a recognized format.
+    pub fn expiration(&self) -> Option<DateTime<Utc>> {
+        parse_expiration_date(&self.expiration_date)
+    }
+
+    /// Returns the time to expiration in years using the ACT/365 day-count convention.
+    pub fn time_to_expiry_years(&self, now: DateTime<Utc>) -> f64 {
+        self.time_to_expiry_years_with(now, DayCount::default())
+    }
+
+    /// Returns the time to expiration in years using the given day-count convention.
+    ///
+    /// Falls back to the whole-day `days_to_expiration` if `expiration_date` cannot be parsed.
+    pub fn time_to_expiry_years_with(&self, now: DateTime<Utc>, day_count: DayCount) -> f64 {
+        match self.expiration() {
+            Some(expiration) => year_fraction(now, expiration, day_count),
+            None => self.days_to_expiration.max(0) as f64 / day_count.days_per_year(),
+        }
+    }
+
+    /// Returns the greeks and model values of this contract, with Schwab's `NaN` and `-999.0`
+    /// placeholders mapped to `None`.
+    pub fn greeks(&self) -> Greeks {
+        Greeks {
+            volatility: present(self.volatility),
+            delta: present(self.delta),
+            gamma: present(self.gamma),
+            theta: present(self.theta),
+            vega: present(self.vega),
+            rho: present(self.rho),
+            time_value: present(self.time_value),
+            theoretical_option_value: present(self.theoretical_option_value),
+            theoretical_volatility: present(self.theoretical_volatility),
+        }
+    }
+}
+
+/// The model values of an [`OptionContract`], each `None` when Schwab could not compute it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Greeks {
+    pub volatility: Option<f64>,
+    pub delta: Option<f64>,
+    pub gamma: Option<f64>,
+    pub theta: Option<f64>,
+    pub vega: Option<f64>,
+    pub rho: Option<f64>,
+    pub time_value: Option<f64>,
+    pub theoretical_option_value: Option<f64>,
+    pub theoretical_volatility: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PriceHistoryResponse {
     pub candles: Vec<Candle>,
@@ -225,7 +1383,7 @@ pub struct PriceHistoryResponse {
     pub empty: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Candle {
     pub open: f64,
@@ -240,14 +1398,26 @@ pub struct Candle {
 /// The response for a movers request is a list of Mover objects.
 pub type MoversResponse = Vec<Mover>;
 
+/// A `/movers` response, which wraps the movers in a `screeners` array.
 #[derive(Debug, Clone, Deserialize)]
+pub(crate) struct MoversPage {
+    #[serde(default)]
+    pub screeners: MoversResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Mover {
+    #[serde(alias = "netChange")]
     pub change: f64,
     pub description: String,
-    pub direction: String,
+    /// Not sent by every version of the endpoint, in which case it is an empty
+    /// `MoverDirection::Unknown`.
+    #[serde(default)]
+    pub direction: MoverDirection,
+    #[serde(alias = "lastPrice")]
     pub last: f64,
-    #[serde(rename = "percentChange")]
+    #[serde(rename = "percentChange", alias = "netPercentChange")]
     pub percent_change: f64,
     pub symbol: String,
     #[serde(rename = "totalVolume")]
@@ -257,20 +1427,84 @@ pub struct Mover {
 /// The response for an instruments request is a list of Instrument objects.
 pub type InstrumentsResponse = Vec<Instrument>;
 
+/// An `/instruments` response, which wraps the instruments in an `instruments` array.
 #[derive(Debug, Clone, Deserialize)]
+pub(crate) struct InstrumentsPage {
+    #[serde(default)]
+    pub instruments: InstrumentsResponse,
+}
+
+/// An instrument found through `SchwabApi::instruments` or `SchwabApi::instrument_cusip`.
+///
+/// `fundamental` is only present with `Projection::Fundamental`; the other projections return
+/// the lightweight fields alone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Instrument {
+    #[serde(default)]
     pub cusip: String,
     pub symbol: String,
+    #[serde(default)]
     pub description: String,
+    #[serde(default)]
     pub exchange: String,
-    pub asset_type: String,
+    #[serde(default)]
+    pub asset_type: AssetType,
+    pub fundamental: Option<InstrumentFundamental>,
+}
+
+/// The fundamentals of an [`Instrument`], returned by the `fundamental` projection.
+///
+/// Ratios and margins are percentages. Dates are ISO 8601 strings as sent by Schwab.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InstrumentFundamental {
+    pub symbol: Option<String>,
+    pub high52: Option<f64>,
+    pub low52: Option<f64>,
+    pub pe_ratio: Option<f64>,
+    pub peg_ratio: Option<f64>,
+    pub pb_ratio: Option<f64>,
+    pub pr_ratio: Option<f64>,
+    pub pcf_ratio: Option<f64>,
+    pub eps: Option<f64>,
+    #[serde(rename = "epsTTM")]
+    pub eps_ttm: Option<f64>,
+    pub market_cap: Option<f64>,
+    pub market_cap_float: Option<f64>,
+    pub shares_outstanding: Option<f64>,
+    pub beta: Option<f64>,
+    pub dividend_amount: Option<f64>,
+    pub dividend_yield: Option<f64>,
+    pub dividend_date: Option<String>,
+    pub dividend_pay_amount: Option<f64>,
+    pub dividend_pay_date: Option<String>,
+    /// The number of dividend payments per year.
+    pub dividend_freq: Option<i32>,
+    pub declaration_date: Option<String>,
+    #[serde(rename = "grossMarginTTM")]
+    pub gross_margin_ttm: Option<f64>,
+    #[serde(rename = "netProfitMarginTTM")]
+    pub net_profit_margin_ttm: Option<f64>,
+    #[serde(rename = "operatingMarginTTM")]
+    pub operating_margin_ttm: Option<f64>,
+    pub return_on_equity: Option<f64>,
+    pub return_on_assets: Option<f64>,
+    pub return_on_investment: Option<f64>,
+    pub quick_ratio: Option<f64>,
+    pub current_ratio: Option<f64>,
+    pub total_debt_to_equity: Option<f64>,
+    pub book_value_per_share: Option<f64>,
+    pub short_int_to_float: Option<f64>,
+    pub short_int_day_to_cover: Option<f64>,
+    pub avg10_days_volume: Option<f64>,
+    pub avg3_month_volume: Option<f64>,
 }
 
 /// The response for market hours is a map of market names to their hours.
 pub type MarketHoursResponse = HashMap<String, MarketHours>;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MarketHours {
     pub date: String,
@@ -280,29 +1514,702 @@ pub struct MarketHours {
     pub product: String,
     pub product_name: String,
     pub is_open: bool,
+    /// The sessions of the day keyed by name, such as `preMarket`, `regularMarket` and
+    /// `postMarket`. Absent on days the market is closed.
     #[serde(rename = "sessionHours")]
     pub session_hours: Option<HashMap<String, Vec<MarketSession>>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl MarketHours {
+    /// Returns `true` if the current time falls within any of the day's sessions.
+    ///
+    /// See [`MarketHours::session_at`].
+    pub fn is_open_now(&self) -> bool {
+        self.session_at(Utc::now()).is_some()
+    }
+
+    /// Returns `true` if `time` falls within any of the day's sessions.
+    pub fn is_open_at(&self, time: DateTime<Utc>) -> bool {
+        self.session_at(time).is_some()
+    }
+
+    /// Returns the name of the session `time` falls within, e.g. `regularMarket`.
+    ///
+    /// Extended-hours sessions count as open. Sessions whose times cannot be parsed are skipped,
+    /// and a day Schwab reports as closed has no sessions at all.
+    pub fn session_at(&self, time: DateTime<Utc>) -> Option<&str> {
+        if !self.is_open {
+            return None;
+        }
+        self.session_hours
+            .as_ref()?
+            .iter()
+            .find(|(_, sessions)| sessions.iter().any(|session| session.contains(time)))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MarketSession {
+    /// The start of the session, in ISO 8601 with the exchange's offset.
     pub start: String,
+    /// The end of the session, in ISO 8601 with the exchange's offset.
     pub end: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl MarketSession {
+    /// Returns `start` as a timestamp, or `None` if it cannot be parsed.
+    pub fn start_time(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.start).ok()
+    }
+
+    /// Returns `end` as a timestamp, or `None` if it cannot be parsed.
+    pub fn end_time(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.end).ok()
+    }
+
+    /// Returns `true` if `time` is at or after the start and before the end of the session.
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        match (self.start_time(), self.end_time()) {
+            (Some(start), Some(end)) => start <= time && time < end,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExpirationChainResponse {
     pub expiration_list: Vec<ExpirationDate>,
     pub status: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExpirationDate {
     pub expiration_date: String,
     pub days_to_expiration: i32,
+    /// `W` for a weekly, `S` for a standard monthly, `Q` for a quarterly and `M` for an
+    /// end-of-month expiration.
     pub expiration_type: String,
     pub standard: bool,
-}
\ No newline at end of file
+}
+
+impl ExpirationChainResponse {
+    /// Returns the expirations flagged as standard, in the order Schwab listed them.
+    pub fn standard_expirations(&self) -> Vec<&ExpirationDate> {
+        self.expiration_list
+            .iter()
+            .filter(|expiration| expiration.standard)
+            .collect()
+    }
+
+    /// Returns the soonest weekly expiration.
+    pub fn next_weekly(&self) -> Option<&ExpirationDate> {
+        self.next_of_type("W")
+    }
+
+    /// Returns the soonest standard monthly expiration, normally the third Friday of the month.
+    pub fn next_monthly(&self) -> Option<&ExpirationDate> {
+        self.next_of_type("S")
+    }
+
+    /// Returns the expiration of `expiration_type` with the earliest date. Entries whose date
+    /// cannot be parsed are skipped.
+    fn next_of_type(&self, expiration_type: &str) -> Option<&ExpirationDate> {
+        self.expiration_list
+            .iter()
+            .filter(|expiration| expiration.expiration_type == expiration_type)
+            .filter_map(|expiration| expiration.expiration().map(|date| (date, expiration)))
+            .min_by_key(|(date, _)| *date)
+            .map(|(_, expiration)| expiration)
+    }
+}
+
+impl ExpirationDate {
+    /// Returns the parsed `expiration_date`, or `None` if it is not in a recognized format.
+    pub fn expiration(&self) -> Option<DateTime<Utc>> {
+        parse_expiration_date(&self.expiration_date)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    /// An option contract from a `/chains` response, with every model value computed.
+    fn contract_json() -> Value {
+        json!({
+            "putCall": "CALL",
+            "symbol": "AAPL  240119C00190000",
+            "description": "AAPL 01/19/2024 190.00 C",
+            "exchangeName": "OPR",
+            "bid": 3.1,
+            "ask": 3.2,
+            "last": 3.15,
+            "mark": 3.15,
+            "bidSize": 12,
+            "askSize": 40,
+            "bidAskSize": "12X40",
+            "lastSize": 1,
+            "highPrice": 3.5,
+            "lowPrice": 2.8,
+            "openPrice": 0.0,
+            "closePrice": 2.95,
+            "totalVolume": 10234,
+            "tradeTimeInLong": 1705438799000i64,
+            "quoteTimeInLong": 1705438800000i64,
+            "netChange": 0.2,
+            "volatility": 21.4,
+            "delta": 0.52,
+            "gamma": 0.061,
+            "theta": -0.21,
+            "vega": 0.08,
+            "rho": 0.01,
+            "openInterest": 25310,
+            "timeValue": 2.35,
+            "theoreticalOptionValue": 3.16,
+            "theoreticalVolatility": 29.0,
+            "strikePrice": 190.0,
+            "expirationDate": "2024-01-19T21:00:00.000+00:00",
+            "daysToExpiration": 2,
+            "expirationType": "S",
+            "lastTradingDay": 1705712400000i64,
+            "multiplier": 100.0,
+            "settlementType": "P",
+            "deliverableNote": "100 AAPL",
+            "inTheMoney": true,
+            "isPennyPilot": true
+        })
+    }
+
+    #[test]
+    fn equity_quote_reads_nan_strings() {
+        let quote: EquityQuote = serde_json::from_value(json!({
+            "52WeekHigh": 199.62,
+            "52WeekLow": 164.08,
+            "closePrice": 101.0,
+            "lastPrice": 101.5,
+            "openPrice": "NaN",
+            "netChange": "NaN",
+            "netPercentChange": 0.5,
+            "securityStatus": "Normal",
+            "totalVolume": 1200
+        }))
+        .unwrap();
+
+        assert_eq!(quote.last_price, Some(101.5));
+        assert_eq!(quote.open_price, None);
+        assert!(quote.net_change.is_nan());
+    }
+
+    #[test]
+    fn mutual_fund_quote_leaves_unreported_fields_empty() {
+        let quote: Quote = serde_json::from_value(json!({
+            "assetMainType": "MUTUAL_FUND",
+            "assetType": "MUTUAL_FUND",
+            "symbol": "VFIAX",
+            "description": "Vanguard 500 Index Admiral",
+            "quoteType": "NBBO",
+            "realtime": true,
+            "ssid": 1882212,
+            "fundamental": {
+                "avg10DaysVolume": 0,
+                "divAmount": 6.6424,
+                "divFreq": 4,
+                "divYield": 1.2321,
+                "eps": 0
+            },
+            "quote": {
+                "52WeekHigh": 553.18,
+                "52WeekLow": 427.95,
+                "closePrice": 540.6,
+                "nAV": 541.87,
+                "netChange": 1.27,
+                "netPercentChange": 0.2349,
+                "securityStatus": "Normal",
+                "totalVolume": 0,
+                "tradeTime": 1728604800000i64
+            }
+        }))
+        .unwrap();
+
+        let equity = quote.equity_quote().unwrap();
+        assert_eq!(equity.close_price, 540.6);
+        assert_eq!(equity.last_price, None);
+        assert_eq!(equity.bid_price, None);
+        assert_eq!(equity.quote_time(), None);
+        assert_eq!(quote.nav(), Some(541.87));
+        assert_eq!(quote.quote.as_ref().unwrap().last_price(), None);
+        assert_eq!(GapInfo::from_quote(equity).open, None);
+
+        let fundamental = quote.fundamental.as_ref().unwrap();
+        assert_eq!(fundamental.div_amount, Some(6.6424));
+        assert_eq!(fundamental.div_freq, Some(4));
+        assert_eq!(fundamental.market_cap, None);
+        assert_eq!(fundamental.div_pay_date, None);
+    }
+
+    #[test]
+    fn etf_quote_reports_the_official_nav_apart_from_the_last_trade() {
+        let quote: Quote = serde_json::from_value(json!({
+            "assetMainType": "EQUITY",
+            "assetSubType": "ETF",
+            "assetType": "EQUITY",
+            "symbol": "SPY",
+            "description": "SPDR S&P 500",
+            "quoteType": "NBBO",
+            "realtime": true,
+            "ssid": 1281357639,
+            "quote": {
+                "52WeekHigh": 585.91,
+                "52WeekLow": 409.21,
+                "askPrice": 579.6,
+                "bidPrice": 579.58,
+                "closePrice": 577.14,
+                "highPrice": 580.2,
+                "lastPrice": 579.59,
+                "lowPrice": 576.4,
+                "mark": 579.59,
+                "nAV": 577.18,
+                "netChange": 2.45,
+                "netPercentChange": 0.4245,
+                "openPrice": 577.5,
+                "quoteTimeInLong": 1728659699000i64,
+                "securityStatus": "Normal",
+                "totalVolume": 31289345,
+                "tradeTime": 1728590400000i64,
+                "tradeTimeInLong": 1728659698000i64
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(quote.nav(), Some(577.18));
+        assert_eq!(
+            quote.nav_as_of(),
+            Some(Utc.with_ymd_and_hms(2024, 10, 10, 20, 0, 0).unwrap())
+        );
+        let equity = quote.equity_quote().unwrap();
+        assert_eq!(equity.last_price, Some(579.59));
+        assert_eq!(
+            equity.last_trade_time(),
+            Some(Utc.with_ymd_and_hms(2024, 10, 11, 15, 14, 58).unwrap())
+        );
+    }
+
+    #[test]
+    fn option_quote_maps_placeholders_to_none() {
+        let quote: OptionQuote = serde_json::from_value(json!({
+            "delta": "NaN",
+            "gamma": -999.0,
+            "theta": -0.05,
+            "vega": null,
+            "underlyingPrice": "101.25"
+        }))
+        .unwrap();
+
+        assert_eq!(quote.delta, None);
+        assert_eq!(quote.gamma, None);
+        assert_eq!(quote.theta, Some(-0.05));
+        assert_eq!(quote.vega, None);
+        assert_eq!(quote.rho, None);
+        assert_eq!(quote.underlying_price, Some(101.25));
+    }
+
+    #[test]
+    fn quote_statuses_parse_into_security_status() {
+        let option: OptionQuote =
+            serde_json::from_value(json!({ "securityStatus": "Halted" })).unwrap();
+        let future: FutureQuote =
+            serde_json::from_value(json!({ "securityStatus": "Suspended" })).unwrap();
+        let forex: ForexQuote = serde_json::from_value(json!({})).unwrap();
+
+        assert_eq!(option.security_status, Some(SecurityStatus::Halted));
+        assert_eq!(
+            future.security_status,
+            Some(SecurityStatus::Unknown("Suspended".to_string()))
+        );
+        assert_eq!(forex.security_status, None);
+        assert_eq!(
+            QuoteData::Option(Box::new(option)).status(),
+            Some(SecurityStatus::Halted)
+        );
+    }
+
+    #[test]
+    fn quote_without_asset_type_is_rejected() {
+        let result = serde_json::from_value::<Quote>(json!({
+            "assetMainType": "EQUITY",
+            "symbol": "AAPL",
+            "description": "Apple Inc"
+        }));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn future_quote_maps_placeholders_to_none() {
+        let quote: FutureQuote = serde_json::from_value(json!({
+            "lastPrice": 5021.25,
+            "openInterest": "NaN",
+            "futurePercentChange": -999.0
+        }))
+        .unwrap();
+
+        assert_eq!(quote.last_price, Some(5021.25));
+        assert_eq!(quote.open_interest, None);
+        assert_eq!(quote.future_percent_change, None);
+    }
+
+    #[test]
+    fn chain_reads_nan_strings() {
+        let chain: ChainsResponse = serde_json::from_value(json!({
+            "symbol": "AAPL",
+            "status": "SUCCESS",
+            "strategy": "SINGLE",
+            "interval": 0.0,
+            "isDelayed": false,
+            "isIndex": false,
+            "interestRate": 5.25,
+            "underlyingPrice": 190.5,
+            "volatility": "NaN",
+            "daysToExpiration": 0.0,
+            "numberOfContracts": 0,
+            "callExpDateMap": {},
+            "putExpDateMap": {}
+        }))
+        .unwrap();
+
+        assert_eq!(chain.underlying_price, 190.5);
+        assert!(chain.volatility.is_nan());
+    }
+
+    #[test]
+    fn option_contract_greeks_map_placeholders_to_none() {
+        let mut json = contract_json();
+        json["delta"] = json!("NaN");
+        json["gamma"] = json!(-999.0);
+        json["mark"] = json!("NaN");
+        let contract: OptionContract = serde_json::from_value(json).unwrap();
+
+        let greeks = contract.greeks();
+        assert_eq!(greeks.delta, None);
+        assert_eq!(greeks.gamma, None);
+        assert_eq!(greeks.theta, Some(-0.21));
+        assert!(contract.mark.is_nan());
+    }
+
+    #[test]
+    fn futures_quotes_parse_from_a_quotes_response() {
+        let quotes: QuotesResponse = serde_json::from_value(json!({
+            "/ES": {
+                "assetMainType": "FUTURE",
+                "assetType": "FUTURE",
+                "ssid": 0,
+                "symbol": "/ES",
+                "realtime": true,
+                "description": "E-mini S&P 500 Index Futures,Dec-2024,ETH",
+                "quote": {
+                    "askMICId": "XCME",
+                    "askPrice": 5872.25,
+                    "askSize": 14,
+                    "askTime": 1730149199000i64,
+                    "bidMICId": "XCME",
+                    "bidPrice": 5872.0,
+                    "bidSize": 21,
+                    "bidTime": 1730149199000i64,
+                    "closePrice": 5862.5,
+                    "futurePercentChange": 0.0017,
+                    "highPrice": 5878.75,
+                    "lastMICId": "XCME",
+                    "lastPrice": 5872.25,
+                    "lastSize": 1,
+                    "lowPrice": 5855.5,
+                    "mark": 5872.25,
+                    "netChange": 9.75,
+                    "openInterest": 2154731,
+                    "openPrice": 5863.0,
+                    "quoteTime": 1730149199000i64,
+                    "quotedInSession": false,
+                    "securityStatus": "Normal",
+                    "settleTime": 1730145600000i64,
+                    "tick": 0.25,
+                    "tickAmount": 12.5,
+                    "totalVolume": 78412,
+                    "tradeTime": 1730149198000i64
+                },
+                "reference": {
+                    "description": "E-mini S&P 500 Index Futures,Dec-2024,ETH",
+                    "exchange": "@",
+                    "exchangeName": "XCME",
+                    "futureActiveSymbol": "/ESZ24",
+                    "futureExpirationDate": 1734715800000i64,
+                    "futureIsActive": true,
+                    "futureMultiplier": 50,
+                    "futurePriceFormat": "D,D",
+                    "futureSettlementPrice": 5862.5,
+                    "futureTradingHours": "GLBX(de=1640;0=-1700151515301600;1=r-17001515r15301600d-15551640;7=d-16401555)",
+                    "product": "/ES"
+                }
+            },
+            "./OZCZ24C565": {
+                "assetMainType": "FUTURE_OPTION",
+                "assetType": "FUTURE_OPTION",
+                "ssid": 0,
+                "symbol": "./OZCZ24C565",
+                "realtime": true,
+                "description": "Corn Futures Options,Dec-2024,Call,5.65",
+                "quote": {
+                    "askMICId": "XCBT",
+                    "askPrice": 0.0063,
+                    "askSize": 125,
+                    "bidMICId": "XCBT",
+                    "bidPrice": 0.005,
+                    "bidSize": 40,
+                    "closePrice": 0.0063,
+                    "highPrice": 0.0063,
+                    "lastMICId": "XCBT",
+                    "lastPrice": 0.0063,
+                    "lastSize": 2,
+                    "lowPrice": 0.005,
+                    "mark": 0.0056,
+                    "markChange": -0.0007,
+                    "netChange": 0.0,
+                    "netPercentChange": 0.0,
+                    "openInterest": 6214,
+                    "openPrice": 0.005,
+                    "quoteTime": 1730149190000i64,
+                    "securityStatus": "Normal",
+                    "settlemetPrice": 0.0063,
+                    "tick": 0.0013,
+                    "tickAmount": 6.25,
+                    "totalVolume": 37,
+                    "tradeTime": 1730141531000i64
+                },
+                "reference": {
+                    "contractType": "C",
+                    "description": "Corn Futures Options,Dec-2024,Call,5.65",
+                    "exchange": "@",
+                    "exchangeName": "XCBT",
+                    "multiplier": 5000,
+                    "expirationDate": 1732310400000i64,
+                    "expirationStyle": "A",
+                    "strikePrice": 5.65,
+                    "underlying": "/ZCZ24"
+                }
+            }
+        }))
+        .unwrap();
+
+        let es = &quotes["/ES"];
+        assert_eq!(es.asset_main_type, AssetMainType::Future);
+        assert_eq!(es.data_kind, DataKind::Realtime);
+        let Some(QuoteData::Future(future)) = &es.quote else {
+            panic!("expected a future quote, got {:?}", es.quote);
+        };
+        assert_eq!(future.last_price, Some(5872.25));
+        assert_eq!(future.open_interest, Some(2154731.0));
+        assert_eq!(future.future_percent_change, Some(0.0017));
+        assert_eq!(future.tick_amount, Some(12.5));
+        assert_eq!(future.quoted_in_session, Some(false));
+        assert_eq!(future.security_status, Some(SecurityStatus::Normal));
+        let reference = es.reference.as_ref().unwrap();
+        assert_eq!(reference.future_active_symbol.as_deref(), Some("/ESZ24"));
+        assert_eq!(reference.future_multiplier, Some(50.0));
+        assert_eq!(reference.future_settlement_price, Some(5862.5));
+
+        let corn = &quotes["./OZCZ24C565"];
+        assert_eq!(corn.asset_main_type, AssetMainType::FutureOption);
+        let Some(QuoteData::FutureOption(option)) = &corn.quote else {
+            panic!("expected a futures option quote, got {:?}", corn.quote);
+        };
+        assert_eq!(option.last_price, Some(0.0063));
+        assert_eq!(option.settlement_price, Some(0.0063));
+        assert_eq!(option.mark_change, Some(-0.0007));
+        assert_eq!(option.security_status, Some(SecurityStatus::Normal));
+        let reference = corn.reference.as_ref().unwrap();
+        assert_eq!(reference.strike_price, Some(5.65));
+        assert_eq!(reference.underlying.as_deref(), Some("/ZCZ24"));
+    }
+
+    #[test]
+    fn chain_round_trips_through_serde_with_nan_greeks() {
+        let mut contract = contract_json();
+        contract["delta"] = json!("NaN");
+        contract["theta"] = json!("NaN");
+        let json = json!({
+            "symbol": "AAPL",
+            "status": "SUCCESS",
+            "strategy": "SINGLE",
+            "interval": 0.0,
+            "isDelayed": false,
+            "isIndex": false,
+            "interestRate": 5.25,
+            "underlyingPrice": 190.5,
+            "volatility": "NaN",
+            "daysToExpiration": 2.0,
+            "numberOfContracts": 1,
+            "callExpDateMap": { "2024-01-19:2": { "190.0": [contract] } },
+            "putExpDateMap": {}
+        });
+        let chain: ChainsResponse = serde_json::from_value(json).unwrap();
+
+        let serialized = serde_json::to_value(&chain).unwrap();
+        let round_tripped: ChainsResponse = serde_json::from_value(serialized.clone()).unwrap();
+
+        assert_eq!(serialized["volatility"], "NaN");
+        assert_eq!(
+            serialized["callExpDateMap"]["2024-01-19:2"]["190.0"][0]["delta"],
+            "NaN"
+        );
+        assert_eq!(serde_json::to_value(&round_tripped).unwrap(), serialized);
+        assert!(round_tripped.volatility.is_nan());
+        let contract = round_tripped.all_contracts().next().unwrap();
+        assert_eq!(contract.symbol, "AAPL  240119C00190000");
+        assert!(contract.delta.is_nan());
+        assert!(contract.theta.is_nan());
+        assert_eq!(contract.gamma, 0.061);
+        assert_eq!(contract.greeks().delta, None);
+    }
+
+    #[test]
+    fn quote_sections_parse_reference_extended_and_regular_data() {
+        let quote: Quote = serde_json::from_value(json!({
+            "assetMainType": "EQUITY",
+            "assetType": "COMMON_STOCK",
+            "symbol": "GME",
+            "description": "GameStop Corp",
+            "realtime": true,
+            "extended": {
+                "askPrice": 22.61,
+                "askSize": 300,
+                "bidPrice": 22.55,
+                "bidSize": 100,
+                "lastPrice": 22.58,
+                "lastSize": 50,
+                "mark": 0.0,
+                "quoteTime": 1730160000000i64,
+                "totalVolume": 412345,
+                "tradeTime": 1730159990000i64
+            },
+            "reference": {
+                "cusip": "36467W109",
+                "description": "GameStop Corp",
+                "exchange": "N",
+                "exchangeName": "NYSE",
+                "isHardToBorrow": true,
+                "isShortable": true,
+                "htbRate": 12.75,
+                "htbQuantity": 84000
+            },
+            "regular": {
+                "regularMarketLastPrice": 22.43,
+                "regularMarketLastSize": 1200,
+                "regularMarketNetChange": -0.31,
+                "regularMarketPercentChange": -1.3632,
+                "regularMarketTradeTime": 1730145600000i64
+            }
+        }))
+        .unwrap();
+
+        let reference = quote.reference.as_ref().unwrap();
+        assert_eq!(reference.cusip.as_deref(), Some("36467W109"));
+        assert_eq!(reference.exchange_name.as_deref(), Some("NYSE"));
+        assert_eq!(reference.is_hard_to_borrow, Some(true));
+        assert_eq!(reference.is_shortable, Some(true));
+        assert_eq!(reference.htb_rate, Some(12.75));
+        assert_eq!(reference.htb_quantity, Some(84000));
+
+        let extended = quote.extended.as_ref().unwrap();
+        assert_eq!(extended.bid_price, Some(22.55));
+        assert_eq!(extended.total_volume, Some(412345));
+        assert_eq!(quote.extended_hours_last(), Some(22.58));
+
+        let regular = quote.regular.as_ref().unwrap();
+        assert_eq!(regular.regular_market_last_price, Some(22.43));
+        assert_eq!(regular.regular_market_last_size, Some(1200));
+        assert_eq!(regular.regular_market_percent_change, Some(-1.3632));
+        assert_eq!(regular.regular_market_trade_time, Some(1730145600000));
+    }
+
+    #[test]
+    fn easy_to_borrow_reference_leaves_htb_fields_empty() {
+        let reference: ReferenceData = serde_json::from_value(json!({
+            "cusip": "037833100",
+            "description": "Apple Inc",
+            "exchange": "Q",
+            "exchangeName": "NASDAQ",
+            "isHardToBorrow": false,
+            "isShortable": true,
+            "htbRate": "NaN"
+        }))
+        .unwrap();
+
+        assert_eq!(reference.is_hard_to_borrow, Some(false));
+        assert_eq!(reference.htb_rate, None);
+        assert_eq!(reference.htb_quantity, None);
+    }
+
+    #[test]
+    fn extended_hours_last_ignores_missing_and_zero_prices() {
+        let quote = |extended: Value| -> Quote {
+            serde_json::from_value(json!({
+                "assetMainType": "EQUITY",
+                "assetType": "COMMON_STOCK",
+                "symbol": "AAPL",
+                "description": "Apple Inc",
+                "extended": extended
+            }))
+            .unwrap()
+        };
+
+        let traded = quote(json!({ "lastPrice": 227.41, "lastSize": 25, "totalVolume": 90210 }));
+        let untraded = quote(json!({ "lastPrice": 0.0, "lastSize": 0, "totalVolume": 0 }));
+        let missing = quote(Value::Null);
+
+        assert_eq!(traded.extended_hours_last(), Some(227.41));
+        assert_eq!(untraded.extended_hours_last(), None);
+        assert!(missing.extended.is_none());
+        assert_eq!(missing.extended_hours_last(), None);
+    }
+
+    #[test]
+    fn time_to_expiry_shrinks_during_the_expiration_day() {
+        let contract: OptionContract = serde_json::from_value(contract_json()).unwrap();
+        let morning = Utc.with_ymd_and_hms(2024, 1, 19, 15, 0, 0).unwrap();
+        let after_expiry = Utc.with_ymd_and_hms(2024, 1, 19, 21, 30, 0).unwrap();
+
+        // Six hours before the 21:00 UTC expiration.
+        assert_eq!(contract.time_to_expiry_years(morning), 6.0 / (24.0 * 365.0));
+        assert_eq!(contract.time_to_expiry_years(after_expiry), 0.0);
+    }
+
+    #[test]
+    fn time_to_expiry_spans_months_under_each_day_count() {
+        let contract: OptionContract = serde_json::from_value(contract_json()).unwrap();
+        let now = Utc.with_ymd_and_hms(2023, 10, 19, 21, 0, 0).unwrap();
+
+        // 2023-10-19 to 2024-01-19 is 92 days.
+        assert_eq!(contract.time_to_expiry_years(now), 92.0 / 365.0);
+        assert_eq!(
+            contract.time_to_expiry_years_with(now, DayCount::Act360),
+            92.0 / 360.0
+        );
+        assert_eq!(
+            contract.time_to_expiry_years_with(now, DayCount::Act365_25),
+            92.0 / 365.25
+        );
+    }
+
+    #[test]
+    fn time_to_expiry_falls_back_to_whole_days() {
+        let mut json = contract_json();
+        json["expirationDate"] = json!("not a date");
+        let contract: OptionContract = serde_json::from_value(json).unwrap();
+
+        assert_eq!(contract.time_to_expiry_years(Utc::now()), 2.0 / 365.0);
+    }
+}
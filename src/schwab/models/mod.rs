@@ -1,3 +1,14 @@
+//! Response and message types for the Schwab API.
+//!
+//! Numeric fields follow one rule: a field is `Option<f64>` when Schwab may omit it, and `f64` when
+//! it is always present in the payload. Schwab reports a number it has no value for as `NaN`,
+//! sometimes as the string `"NaN"`, or as `-999.0`. Optional numbers of quotes, option chains and
+//! positions read those placeholders as `None`, the same as a missing key. Always-present numbers
+//! accept the `"NaN"` string and keep it as `f64::NAN`; accessors such as `OptionContract::greeks`
+//! map their placeholders to `None` so that every missing number reads the same way.
+
 pub mod streamer;
 pub mod market_data;
-pub mod trader;
\ No newline at end of file
+pub mod trader;
+pub mod parsed;
+mod numbers;
\ No newline at end of file
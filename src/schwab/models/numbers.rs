@@ -0,0 +1,63 @@
+//! Serde helpers for the numbers Schwab reports as `NaN`, `-999.0` or not at all when it has no
+//! value for them.
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// The placeholder Schwab sends for a value it could not compute.
+const MISSING_VALUE: f64 = -999.0;
+
+/// Maps Schwab's placeholders for a missing number to `None`.
+pub(super) fn present(value: f64) -> Option<f64> {
+    (value.is_finite() && value != MISSING_VALUE).then_some(value)
+}
+
+/// A number that Schwab may instead send as a string such as `"NaN"`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(f64),
+    String(String),
+}
+
+impl NumberOrString {
+    fn parse<E: serde::de::Error>(self) -> Result<f64, E> {
+        match self {
+            NumberOrString::Number(value) => Ok(value),
+            NumberOrString::String(value) => value.trim().parse().map_err(E::custom),
+        }
+    }
+}
+
+/// Deserializes a number that Schwab may instead send as the string `"NaN"`.
+pub(super) fn f64_or_nan<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    NumberOrString::deserialize(deserializer)?.parse()
+}
+
+/// Serializes a number the way [`f64_or_nan`] reads it back, writing values that JSON cannot
+/// represent, such as `NaN`, as strings.
+pub(super) fn f64_or_nan_string<S>(value: &f64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if value.is_finite() {
+        serializer.serialize_f64(*value)
+    } else {
+        serializer.collect_str(value)
+    }
+}
+
+/// Deserializes an optional number, mapping `null`, `"NaN"` and `-999.0` to `None`.
+///
+/// Fields using it need `#[serde(default)]` so that a missing key is `None` as well.
+pub(super) fn option_f64_or_nan<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<NumberOrString>::deserialize(deserializer)? {
+        Some(value) => Ok(present(value.parse()?)),
+        None => Ok(None),
+    }
+}
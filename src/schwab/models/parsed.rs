@@ -0,0 +1,41 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::schwab::error::SchwabError;
+
+/// A response body paired with the result of deserializing it into `T`.
+///
+/// Returned by the `*_parsed` methods of `SchwabApi`. When Schwab changes a field in a way the
+/// typed models do not expect, `typed` holds the error while `raw` still carries the full
+/// response, so callers can fall back to reading the JSON directly instead of losing the data.
+#[derive(Debug)]
+pub struct Parsed<T> {
+    /// The response body as Schwab returned it.
+    pub raw: Value,
+    /// The response deserialized into `T`, or the reason it could not be.
+    pub typed: Result<T, serde_json::Error>,
+}
+
+impl<T: DeserializeOwned> Parsed<T> {
+    /// Deserializes `raw` into `T`, keeping `raw` either way.
+    pub fn from_value(raw: Value) -> Self {
+        let typed = T::deserialize(&raw);
+        Self { raw, typed }
+    }
+}
+
+impl<T> Parsed<T> {
+    /// Returns the typed response, if `raw` could be deserialized.
+    pub fn typed(&self) -> Option<&T> {
+        self.typed.as_ref().ok()
+    }
+
+    /// Returns the typed response, or the same `SchwabError::Deserialize` the typed methods
+    /// return when it could not be deserialized.
+    pub fn into_result(self) -> Result<T, SchwabError> {
+        self.typed.map_err(|source| SchwabError::Deserialize {
+            raw: self.raw.to_string(),
+            source,
+        })
+    }
+}
@@ -1,8 +1,18 @@
 // src/schwab/models/streamer.rs
 
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
 use std::fmt;
 
+use crate::{schwab::models::market_data::SecurityStatus, util::epoch_ms_to_utc_opt};
+
+/// A message sent on the channel returned by `SchwabStreamer::start`.
+///
+/// Market data shares this channel with ADMIN responses and connection events, in the order they
+/// occur, so a `Disconnected` is always received before the updates that follow the reconnect.
+/// Consumers that only want data can ignore the other variants; [`StreamerMessage::symbol`]
+/// returns `None` for all of them.
 #[derive(Debug, Clone)]
 pub enum StreamerMessage {
     LevelOneEquity(LevelOneEquitiesResponse),
@@ -10,9 +20,165 @@ pub enum StreamerMessage {
     LevelOneFutures(LevelOneFuturesResponse),
     LevelOneFuturesOptions(LevelOneFuturesOptionsResponse),
     LevelOneForex(LevelOneForexResponse),
+    ChartEquity(ChartEquityResponse),
+    NasdaqBook(BookResponse),
+    NyseBook(BookResponse),
+    OptionsBook(BookResponse),
+    AccountActivity(AccountActivityResponse),
+    Admin(AdminResponse),
+    /// A continuous futures subscription followed its root to a new front-month contract.
+    FutureRolled {
+        root: String,
+        previous: String,
+        current: String,
+    },
+    /// A SUBS, ADD or VIEW request was rejected, or a SUBS or ADD went unacknowledged even
+    /// after being retried.
+    SubscriptionFailed {
+        service: String,
+        command: String,
+        keys: Vec<String>,
+        reason: String,
+    },
+    /// Updates were dropped because the receiver fell behind, with
+    /// `Backpressure::DropNewest` configured. `dropped` counts the updates skipped since the
+    /// previous message.
+    Lagged {
+        dropped: u64,
+    },
+    /// The WebSocket connection was opened. A LOGIN follows; its outcome is reported as an
+    /// [`StreamerMessage::Admin`] message, [`AdminResponse::LoggedIn`] on success.
+    Connected,
+    /// The connection was lost. No updates arrive until a [`StreamerMessage::Reconnected`]
+    /// follows; if none does, the message channel closes.
+    Disconnected {
+        reason: String,
+    },
+    /// A reconnection attempt is about to be made, starting at `1`.
+    Reconnecting {
+        attempt: u32,
+    },
+    /// The connection dropped and the streamer reconnected, logged in again and replayed its
+    /// subscriptions. Updates published while disconnected were missed.
+    Reconnected {
+        attempts: u32,
+    },
     // We can add more variants here for other data types in the future
 }
 
+impl StreamerMessage {
+    /// Returns the symbol a data update is for, or `None` for status and admin messages.
+    ///
+    /// Account activity is keyed by its subscription key rather than a symbol.
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            StreamerMessage::LevelOneEquity(response) => Some(&response.symbol),
+            StreamerMessage::LevelOneOption(response) => Some(&response.symbol),
+            StreamerMessage::LevelOneFutures(response) => Some(&response.symbol),
+            StreamerMessage::LevelOneFuturesOptions(response) => Some(&response.symbol),
+            StreamerMessage::LevelOneForex(response) => Some(&response.symbol),
+            StreamerMessage::ChartEquity(response) => Some(&response.symbol),
+            StreamerMessage::NasdaqBook(response)
+            | StreamerMessage::NyseBook(response)
+            | StreamerMessage::OptionsBook(response) => Some(&response.symbol),
+            StreamerMessage::AccountActivity(response) => Some(&response.key),
+            _ => None,
+        }
+    }
+}
+
+/// Merges the fields present in a Level 1 `update` into `snapshot`, the last known fields of its
+/// symbol, and returns the merged record.
+///
+/// Level 1 updates only carry the fields that changed, so fields left out of `update` keep their
+/// value from `snapshot`.
+pub(crate) fn merge_fields<T>(
+    snapshot: &mut Map<String, Value>,
+    update: &T,
+) -> serde_json::Result<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    if let Value::Object(changes) = serde_json::to_value(update)? {
+        snapshot.extend(changes.into_iter().filter(|(_, value)| !value.is_null()));
+    }
+    serde_json::from_value(Value::Object(snapshot.clone()))
+}
+
+/// A typed response to an ADMIN service command (LOGIN, LOGOUT or QOS).
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminResponse {
+    /// The LOGIN request was accepted.
+    LoggedIn,
+    /// The LOGOUT request was acknowledged.
+    LoggedOut,
+    /// A QOS change was acknowledged. `message` is the server's description of the new level.
+    QosChanged { message: String },
+    /// The LOGIN was rejected because another streamer session for the account is still
+    /// connected, usually one that was not logged out cleanly.
+    DuplicateSession { message: String },
+    /// The server rejected an ADMIN command with a non-zero response code.
+    Error {
+        command: String,
+        code: i64,
+        message: String,
+    },
+}
+
+/// Common subsets of Level 1 fields, so subscriptions need not list field indexes by hand.
+///
+/// Pass the fields for the service being subscribed, e.g.
+/// `FieldSet::PriceOnly.equities()` to `SchwabStreamer::level_one_equities`. The symbol is
+/// always included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldSet {
+    /// The bid, ask and last prices.
+    PriceOnly,
+    /// The bid, ask and last prices with their sizes, and the day's volume.
+    TopOfBook,
+}
+
+/// Defines one `FieldSet` method per Level 1 service. Every Level 1 field enum names the fields
+/// of the sets the same way, so the sets are written once.
+macro_rules! field_set_methods {
+    ($($(#[$doc:meta])* $method:ident => $field:ident),+ $(,)?) => {
+        impl FieldSet {
+            $(
+                $(#[$doc])*
+                pub fn $method(self) -> Vec<$field> {
+                    use $field::*;
+                    match self {
+                        FieldSet::PriceOnly => vec![Symbol, BidPrice, AskPrice, LastPrice],
+                        FieldSet::TopOfBook => vec![
+                            Symbol,
+                            BidPrice,
+                            AskPrice,
+                            LastPrice,
+                            BidSize,
+                            AskSize,
+                            LastSize,
+                            TotalVolume,
+                        ],
+                    }
+                }
+            )+
+        }
+    };
+}
+
+field_set_methods! {
+    /// Returns the fields of the set for `LEVELONE_EQUITIES`.
+    equities => LevelOneEquitiesField,
+    /// Returns the fields of the set for `LEVELONE_OPTIONS`.
+    options => LevelOneOptionsField,
+    /// Returns the fields of the set for `LEVELONE_FUTURES`.
+    futures => LevelOneFuturesField,
+    /// Returns the fields of the set for `LEVELONE_FUTURES_OPTIONS`.
+    futures_options => LevelOneFuturesOptionsField,
+    /// Returns the fields of the set for `LEVELONE_FOREX`.
+    forex => LevelOneForexField,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum LevelOneOptionsField {
@@ -323,7 +489,7 @@ pub struct LevelOneOptionsResponse {
     #[serde(rename = "32")]
     pub rho: Option<f64>,
     #[serde(rename = "33")]
-    pub security_status: Option<String>,
+    pub security_status: Option<SecurityStatus>,
     #[serde(rename = "34")]
     pub theoretical_option_value: Option<f64>,
     #[serde(rename = "35")]
@@ -437,7 +603,7 @@ pub struct LevelOneEquitiesResponse {
     #[serde(rename = "31")]
     pub regular_market_net_change: Option<f64>,
     #[serde(rename = "32")]
-    pub security_status: Option<String>,
+    pub security_status: Option<SecurityStatus>,
     #[serde(rename = "33")]
     pub mark_price: Option<f64>,
     #[serde(rename = "34")]
@@ -484,6 +650,29 @@ pub struct LevelOneEquitiesResponse {
     pub delayed: Option<bool>,
 }
 
+impl LevelOneEquitiesResponse {
+    /// Returns `quote_time_in_long` as a UTC time, if it is part of this update.
+    pub fn quote_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc_opt(self.quote_time_in_long)
+    }
+
+    /// Returns `trade_time_in_long`, the time of the last trade, as a UTC time, if it is part of
+    /// this update.
+    pub fn last_trade_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc_opt(self.trade_time_in_long)
+    }
+
+    /// Returns the last price if the most recent trade happened outside the regular session.
+    ///
+    /// Relies on `regular_market_trade` (field 28), so both fields must be part of the same update.
+    pub fn extended_hours_last(&self) -> Option<f64> {
+        match self.regular_market_trade {
+            Some(false) => self.last_price,
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LevelOneFuturesResponse {
     #[serde(rename = "key")]
@@ -531,7 +720,7 @@ pub struct LevelOneFuturesResponse {
     #[serde(rename = "21")]
     pub exchange_name: Option<String>,
     #[serde(rename = "22")]
-    pub security_status: Option<String>,
+    pub security_status: Option<SecurityStatus>,
     #[serde(rename = "23")]
     pub open_interest: Option<i32>,
     #[serde(rename = "24")]
@@ -570,6 +759,9 @@ pub struct LevelOneFuturesResponse {
     pub settlement_date: Option<i64>,
 }
 
+/// A Level 1 update for a futures option (`LEVELONE_FUTURES_OPTIONS`), e.g. `./OZCZ24C565`.
+///
+/// Fields that did not change since the previous update are omitted by Schwab and left `None`.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LevelOneFuturesOptionsResponse {
     #[serde(rename = "key")]
@@ -631,13 +823,26 @@ pub struct LevelOneFuturesOptionsResponse {
     #[serde(rename = "28")]
     pub contract_type: Option<char>,
     #[serde(rename = "29")]
-    pub security_status: Option<String>,
+    pub security_status: Option<SecurityStatus>,
     #[serde(rename = "30")]
     pub exchange: Option<char>,
     #[serde(rename = "31")]
     pub exchange_name: Option<String>,
 }
 
+impl LevelOneFuturesOptionsResponse {
+    /// Returns `quote_time` as a UTC time, if it is part of this update.
+    pub fn quote_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc_opt(self.quote_time)
+    }
+
+    /// Returns `trade_time`, the time of the last trade, as a UTC time, if it is part of this
+    /// update.
+    pub fn last_trade_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc_opt(self.trade_time)
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum LevelOneFuturesField {
@@ -880,6 +1085,9 @@ impl fmt::Display for LevelOneForexField {
     }
 }
 
+/// A Level 1 update for a currency pair (`LEVELONE_FOREX`), e.g. `EUR/USD`.
+///
+/// Fields that did not change since the previous update are omitted by Schwab and left `None`.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LevelOneForexResponse {
     #[serde(rename = "key")]
@@ -923,7 +1131,7 @@ pub struct LevelOneForexResponse {
     #[serde(rename = "19")]
     pub digits: Option<i32>,
     #[serde(rename = "20")]
-    pub security_status: Option<String>,
+    pub security_status: Option<SecurityStatus>,
     #[serde(rename = "21")]
     pub tick: Option<f64>,
     #[serde(rename = "22")]
@@ -943,3 +1151,451 @@ pub struct LevelOneForexResponse {
     #[serde(rename = "29")]
     pub mark: Option<f64>,
 }
+
+impl LevelOneForexResponse {
+    /// Returns `quote_time` as a UTC time, if it is part of this update.
+    pub fn quote_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc_opt(self.quote_time)
+    }
+
+    /// Returns `trade_time`, the time of the last trade, as a UTC time, if it is part of this
+    /// update.
+    pub fn last_trade_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc_opt(self.trade_time)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum ChartEquityField {
+    Symbol,
+    OpenPrice,
+    HighPrice,
+    LowPrice,
+    ClosePrice,
+    Volume,
+    Sequence,
+    ChartTime,
+    ChartDay,
+}
+
+impl fmt::Display for ChartEquityField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChartEquityField::Symbol => write!(f, "0"),
+            ChartEquityField::OpenPrice => write!(f, "1"),
+            ChartEquityField::HighPrice => write!(f, "2"),
+            ChartEquityField::LowPrice => write!(f, "3"),
+            ChartEquityField::ClosePrice => write!(f, "4"),
+            ChartEquityField::Volume => write!(f, "5"),
+            ChartEquityField::Sequence => write!(f, "6"),
+            ChartEquityField::ChartTime => write!(f, "7"),
+            ChartEquityField::ChartDay => write!(f, "8"),
+        }
+    }
+}
+
+/// A one-minute OHLCV bar from the `CHART_EQUITY` service.
+///
+/// Unlike the Level 1 services, every bar carries all of the subscribed fields.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChartEquityResponse {
+    #[serde(rename = "key")]
+    pub symbol: String,
+    #[serde(rename = "1")]
+    pub open_price: Option<f64>,
+    #[serde(rename = "2")]
+    pub high_price: Option<f64>,
+    #[serde(rename = "3")]
+    pub low_price: Option<f64>,
+    #[serde(rename = "4")]
+    pub close_price: Option<f64>,
+    #[serde(rename = "5")]
+    pub volume: Option<f64>,
+    /// Identifies the bar; a bar resent with the same sequence replaces the earlier one.
+    #[serde(rename = "6")]
+    pub sequence: Option<i64>,
+    /// The start of the minute, in milliseconds since the epoch.
+    #[serde(rename = "7")]
+    pub chart_time: Option<i64>,
+    /// The number of days since the epoch.
+    #[serde(rename = "8")]
+    pub chart_day: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum BookField {
+    Symbol,
+    BookTime,
+    Bids,
+    Asks,
+}
+
+impl fmt::Display for BookField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BookField::Symbol => write!(f, "0"),
+            BookField::BookTime => write!(f, "1"),
+            BookField::Bids => write!(f, "2"),
+            BookField::Asks => write!(f, "3"),
+        }
+    }
+}
+
+/// A level two order book from the `NASDAQ_BOOK`, `NYSE_BOOK` or `OPTIONS_BOOK` service.
+///
+/// Every message carries the full depth of both sides, not just the levels that changed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BookResponse {
+    #[serde(rename = "key")]
+    pub symbol: String,
+    /// The time of the book, in milliseconds since the epoch.
+    #[serde(rename = "1")]
+    pub book_time: Option<i64>,
+    /// The bid price levels, best (highest) price first.
+    #[serde(rename = "2", default)]
+    pub bids: Vec<BookLevel>,
+    /// The ask price levels, best (lowest) price first.
+    #[serde(rename = "3", default)]
+    pub asks: Vec<BookLevel>,
+}
+
+impl BookResponse {
+    /// Returns `book_time` as a UTC timestamp.
+    pub fn book_time(&self) -> Option<DateTime<Utc>> {
+        epoch_ms_to_utc_opt(self.book_time)
+    }
+}
+
+/// The orders resting at a single price on one side of a [`BookResponse`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BookLevel {
+    #[serde(rename = "0")]
+    pub price: f64,
+    /// The total size across every market maker at this price.
+    #[serde(rename = "1")]
+    pub total_volume: Option<f64>,
+    #[serde(rename = "2")]
+    pub market_maker_count: Option<i64>,
+    #[serde(rename = "3", default)]
+    pub market_makers: Vec<MarketMakerQuote>,
+}
+
+/// One market maker's or exchange's share of a [`BookLevel`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MarketMakerQuote {
+    /// The market maker or exchange identifier, e.g. `NSDQ`.
+    #[serde(rename = "0")]
+    pub market_maker_id: String,
+    #[serde(rename = "1")]
+    pub size: Option<f64>,
+    /// The time of the quote, in milliseconds since midnight Eastern time.
+    #[serde(rename = "2")]
+    pub quote_time: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum AccountActivityField {
+    SubscriptionKey,
+    Account,
+    MessageType,
+    MessageData,
+}
+
+impl fmt::Display for AccountActivityField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AccountActivityField::SubscriptionKey => write!(f, "0"),
+            AccountActivityField::Account => write!(f, "1"),
+            AccountActivityField::MessageType => write!(f, "2"),
+            AccountActivityField::MessageData => write!(f, "3"),
+        }
+    }
+}
+
+/// A notification from the `ACCT_ACTIVITY` service about an order or fill in one of the
+/// accounts linked to the streamer session.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountActivityResponse {
+    /// The subscription key the notification was delivered for.
+    pub key: String,
+    pub seq: Option<i64>,
+    /// The account number the activity belongs to.
+    #[serde(rename = "1")]
+    pub account: Option<String>,
+    /// The notification type, parsed by [`AccountActivityResponse::activity_type`].
+    #[serde(rename = "2")]
+    pub message_type: Option<String>,
+    /// The notification payload, a JSON document encoded as a string. Older message types carry
+    /// XML instead.
+    #[serde(rename = "3")]
+    pub message_data: Option<String>,
+}
+
+impl AccountActivityResponse {
+    /// Returns the parsed notification type.
+    pub fn activity_type(&self) -> AccountActivityType {
+        AccountActivityType::from(self.message_type.as_deref().unwrap_or_default())
+    }
+
+    /// Parses `message_data` as JSON. Returns `None` if there is no payload or it is not JSON.
+    pub fn data_json(&self) -> Option<serde_json::Value> {
+        serde_json::from_str(self.message_data.as_deref()?).ok()
+    }
+}
+
+/// The type of an [`AccountActivityResponse`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AccountActivityType {
+    /// Confirms the `ACCT_ACTIVITY` subscription. Carries no order data.
+    Subscribed,
+    /// The subscription failed.
+    Error,
+    /// An order was submitted.
+    OrderEntryRequest,
+    /// An order was created.
+    OrderCreated,
+    /// An order was accepted by the broker.
+    OrderAccepted,
+    /// An order was rejected.
+    OrderRejected,
+    /// An execution was reported for an order.
+    ExecutionCreated,
+    /// Part of an order was filled.
+    OrderPartialFill,
+    /// An order was completely filled.
+    OrderFill,
+    /// The cancellation of an order was requested.
+    CancelRequest,
+    /// The cancellation of an order was accepted.
+    CancelAccepted,
+    /// The remainder of an order was canceled (`UROUT`).
+    OrderCanceled,
+    /// The replacement of an order was accepted.
+    ChangeAccepted,
+    /// A type this library does not recognize, as sent by Schwab.
+    Other(String),
+}
+
+impl From<&str> for AccountActivityType {
+    fn from(s: &str) -> Self {
+        match s {
+            "SUBSCRIBED" => AccountActivityType::Subscribed,
+            "ERROR" => AccountActivityType::Error,
+            "OrderEntryRequest" => AccountActivityType::OrderEntryRequest,
+            "OrderCreated" => AccountActivityType::OrderCreated,
+            "OrderAccepted" => AccountActivityType::OrderAccepted,
+            "OrderRejected" => AccountActivityType::OrderRejected,
+            "ExecutionCreated" => AccountActivityType::ExecutionCreated,
+            "OrderPartialFill" => AccountActivityType::OrderPartialFill,
+            "OrderFill" | "OrderFillCompleted" => AccountActivityType::OrderFill,
+            "CancelRequest" | "CancelRequested" => AccountActivityType::CancelRequest,
+            "CancelAccepted" => AccountActivityType::CancelAccepted,
+            "UROUT" | "OrderUROutCompleted" => AccountActivityType::OrderCanceled,
+            "ChangeAccepted" => AccountActivityType::ChangeAccepted,
+            _ => AccountActivityType::Other(s.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    fn indexes<F: ToString>(fields: Vec<F>) -> Vec<String> {
+        fields.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn merge_fields_keeps_fields_left_out_of_the_update() {
+        let mut snapshot = Map::new();
+        let first: LevelOneEquitiesResponse =
+            serde_json::from_value(json!({ "key": "AAPL", "1": 189.5, "2": 189.6 })).unwrap();
+        let second: LevelOneEquitiesResponse =
+            serde_json::from_value(json!({ "key": "AAPL", "2": 189.7 })).unwrap();
+
+        merge_fields(&mut snapshot, &first).unwrap();
+        let merged = merge_fields(&mut snapshot, &second).unwrap();
+
+        assert_eq!(merged.bid_price, Some(189.5));
+        assert_eq!(merged.ask_price, Some(189.7));
+        assert_eq!(merged.last_price, None);
+    }
+
+    #[test]
+    fn field_sets_map_to_each_service_indexes() {
+        let top_of_book = ["0", "1", "2", "3", "4", "5", "9", "8"];
+        assert_eq!(indexes(FieldSet::TopOfBook.equities()), top_of_book);
+        assert_eq!(indexes(FieldSet::TopOfBook.futures()), top_of_book);
+        assert_eq!(indexes(FieldSet::TopOfBook.futures_options()), top_of_book);
+        assert_eq!(
+            indexes(FieldSet::TopOfBook.options()),
+            ["0", "2", "3", "4", "16", "17", "18", "8"]
+        );
+        assert_eq!(
+            indexes(FieldSet::TopOfBook.forex()),
+            ["0", "1", "2", "3", "4", "5", "7", "6"]
+        );
+        assert_eq!(indexes(FieldSet::PriceOnly.options()), ["0", "2", "3", "4"]);
+        assert_eq!(indexes(FieldSet::PriceOnly.forex()), ["0", "1", "2", "3"]);
+    }
+
+    #[test]
+    fn futures_options_update_uses_the_field_indexes() {
+        let frame = json!({
+            "key": "./OZCZ24C565",
+            "1": 7.25,
+            "2": 7.5,
+            "3": 7.375,
+            "4": 10,
+            "5": 12,
+            "8": 124,
+            "9": 2,
+            "10": 1731690000123i64,
+            "11": 1731689990456i64,
+            "12": 7.75,
+            "13": 6.875,
+            "14": 7.125,
+            "16": "Corn Options Dec 24 565 Call",
+            "17": 7.0,
+            "18": 1450,
+            "19": 7.375,
+            "20": 0.125,
+            "21": 6.25,
+            "22": 50,
+            "23": 7.125,
+            "24": "/ZCZ24",
+            "25": 565,
+            "26": 1732233600000i64,
+            "27": "A",
+            "28": "C",
+            "29": "Normal",
+            "30": "@",
+            "31": "XCBT"
+        });
+        let update: LevelOneFuturesOptionsResponse = serde_json::from_value(frame.clone()).unwrap();
+
+        assert_eq!(update.symbol, "./OZCZ24C565");
+        let field = |f: LevelOneFuturesOptionsField| &frame[f.to_string()];
+        assert_eq!(
+            field(LevelOneFuturesOptionsField::BidPrice),
+            &json!(update.bid_price)
+        );
+        assert_eq!(
+            field(LevelOneFuturesOptionsField::AskSize),
+            &json!(update.ask_size)
+        );
+        assert_eq!(
+            field(LevelOneFuturesOptionsField::QuoteTime),
+            &json!(update.quote_time)
+        );
+        assert_eq!(
+            field(LevelOneFuturesOptionsField::UnderlyingSymbol),
+            &json!(update.underlying_symbol)
+        );
+        assert_eq!(field(LevelOneFuturesOptionsField::StrikePrice), &json!(565));
+        assert_eq!(update.strike_price, Some(565.0));
+        assert_eq!(update.contract_type, Some('C'));
+        assert_eq!(
+            field(LevelOneFuturesOptionsField::SecurityStatus),
+            &json!(update.security_status)
+        );
+        assert_eq!(
+            field(LevelOneFuturesOptionsField::ExchangeName),
+            &json!(update.exchange_name)
+        );
+        assert_eq!(field(LevelOneFuturesOptionsField::BidId), &Value::Null);
+        assert_eq!(update.bid_id, None);
+        assert_eq!(
+            update.quote_time().unwrap().timestamp_millis(),
+            1731690000123
+        );
+    }
+
+    #[test]
+    fn forex_update_uses_the_field_indexes() {
+        let frame = json!({
+            "key": "EUR/USD",
+            "1": 1.0832,
+            "2": 1.0834,
+            "3": 1.0833,
+            "4": 1000000,
+            "5": 1000000,
+            "6": 0,
+            "7": 0,
+            "8": 1718040000123i64,
+            "9": 1718040000123i64,
+            "10": 1.0851,
+            "11": 1.0812,
+            "12": 1.084,
+            "13": "T",
+            "14": "Euro/USDollar Spot",
+            "15": 1.0839,
+            "16": -0.0007,
+            "17": -0.0646,
+            "18": "GFT",
+            "19": 4,
+            "20": "Unknown",
+            "21": 0,
+            "22": 0,
+            "23": "",
+            "24": "",
+            "25": false,
+            "26": "",
+            "27": 1.1139,
+            "28": 1.0448,
+            "29": 1.0833
+        });
+        let update: LevelOneForexResponse = serde_json::from_value(frame.clone()).unwrap();
+
+        assert_eq!(update.symbol, "EUR/USD");
+        let field = |f: LevelOneForexField| &frame[f.to_string()];
+        assert_eq!(
+            field(LevelOneForexField::BidPrice),
+            &json!(update.bid_price)
+        );
+        assert_eq!(
+            field(LevelOneForexField::AskPrice),
+            &json!(update.ask_price)
+        );
+        assert_eq!(field(LevelOneForexField::Exchange), &json!(update.exchange));
+        assert_eq!(
+            field(LevelOneForexField::Description),
+            &json!(update.description)
+        );
+        assert_eq!(
+            field(LevelOneForexField::NetChange),
+            &json!(update.net_change)
+        );
+        assert_eq!(field(LevelOneForexField::Digits), &json!(update.digits));
+        assert_eq!(
+            field(LevelOneForexField::IsTradable),
+            &json!(update.is_tradable)
+        );
+        assert_eq!(
+            field(LevelOneForexField::FiftyTwoWeekHigh),
+            &json!(update.fifty_two_week_high)
+        );
+        assert_eq!(field(LevelOneForexField::Mark), &json!(update.mark));
+        assert_eq!(update.digits, Some(4));
+        assert_eq!(update.is_tradable, Some(false));
+    }
+
+    #[test]
+    fn extended_hours_last_needs_a_trade_outside_the_regular_session() {
+        let after_hours: LevelOneEquitiesResponse =
+            serde_json::from_value(json!({ "key": "AAPL", "3": 227.41, "28": false })).unwrap();
+        let regular: LevelOneEquitiesResponse =
+            serde_json::from_value(json!({ "key": "AAPL", "3": 226.96, "28": true })).unwrap();
+        let unknown: LevelOneEquitiesResponse =
+            serde_json::from_value(json!({ "key": "AAPL", "3": 227.41 })).unwrap();
+
+        assert_eq!(after_hours.extended_hours_last(), Some(227.41));
+        assert_eq!(regular.extended_hours_last(), None);
+        assert_eq!(unknown.extended_hours_last(), None);
+    }
+}
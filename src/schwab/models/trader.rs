@@ -1,5 +1,11 @@
+use std::{collections::HashMap, fmt};
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::numbers::option_f64_or_nan;
+use crate::schwab::{error::SchwabError, models::market_data::AssetType};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserPreferencesResponse {
@@ -31,12 +37,674 @@ pub struct Offer {
     pub mkt_data_permission: String,
 }
 
+/// Client-identifying values used to open and address a streamer session.
+///
+/// `schwab_client_customer_id` and `schwab_client_correl_id` are attached to every streamer
+/// request and must stay the same for the whole session, including across reconnects; Schwab
+/// rejects requests whose ids do not match the ones used at LOGIN. `schwab_client_channel` and
+/// `schwab_client_function_id` are only sent with LOGIN, but should likewise be reused as-is.
+///
+/// Keys are matched in both camelCase (as returned by `userPreference`) and PascalCase (as sent in
+/// streamer requests). Missing keys deserialize to empty strings so that [`StreamerInfo::validate`]
+/// can report exactly which value is absent.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StreamerInfo {
+    #[serde(default, alias = "StreamerSocketUrl")]
     pub streamer_socket_url: String,
+    #[serde(default, alias = "SchwabClientCustomerId")]
     pub schwab_client_customer_id: String,
+    #[serde(default, alias = "SchwabClientCorrelId")]
     pub schwab_client_correl_id: String,
+    #[serde(default, alias = "SchwabClientChannel")]
     pub schwab_client_channel: String,
+    #[serde(default, alias = "SchwabClientFunctionId")]
     pub schwab_client_function_id: String,
-}
\ No newline at end of file
+}
+
+impl StreamerInfo {
+    /// Checks that every id required to log in to the streamer is present.
+    pub fn validate(&self) -> Result<(), SchwabError> {
+        let required = [
+            ("schwabClientCustomerId", &self.schwab_client_customer_id),
+            ("schwabClientCorrelId", &self.schwab_client_correl_id),
+            ("schwabClientChannel", &self.schwab_client_channel),
+            ("schwabClientFunctionId", &self.schwab_client_function_id),
+        ];
+        for (key, value) in required {
+            if value.trim().is_empty() {
+                return Err(SchwabError::Streamer(format!(
+                    "Streamer info from user preferences is missing `{}`",
+                    key
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A plain account number paired with the encrypted hash that the trader endpoints expect.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountNumberMapping {
+    pub account_number: String,
+    pub hash_value: String,
+}
+
+/// The status of an order, as reported by Schwab and accepted by the `status` filter of the
+/// orders endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderStatus {
+    AwaitingParentOrder,
+    AwaitingCondition,
+    AwaitingStopCondition,
+    AwaitingManualReview,
+    Accepted,
+    AwaitingUrOut,
+    PendingActivation,
+    Queued,
+    Working,
+    Rejected,
+    PendingCancel,
+    Canceled,
+    PendingReplace,
+    Replaced,
+    Filled,
+    Expired,
+    New,
+    AwaitingReleaseTime,
+    PendingAcknowledgement,
+    PendingRecall,
+    #[serde(other)]
+    Unknown,
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderStatus::AwaitingParentOrder => write!(f, "AWAITING_PARENT_ORDER"),
+            OrderStatus::AwaitingCondition => write!(f, "AWAITING_CONDITION"),
+            OrderStatus::AwaitingStopCondition => write!(f, "AWAITING_STOP_CONDITION"),
+            OrderStatus::AwaitingManualReview => write!(f, "AWAITING_MANUAL_REVIEW"),
+            OrderStatus::Accepted => write!(f, "ACCEPTED"),
+            OrderStatus::AwaitingUrOut => write!(f, "AWAITING_UR_OUT"),
+            OrderStatus::PendingActivation => write!(f, "PENDING_ACTIVATION"),
+            OrderStatus::Queued => write!(f, "QUEUED"),
+            OrderStatus::Working => write!(f, "WORKING"),
+            OrderStatus::Rejected => write!(f, "REJECTED"),
+            OrderStatus::PendingCancel => write!(f, "PENDING_CANCEL"),
+            OrderStatus::Canceled => write!(f, "CANCELED"),
+            OrderStatus::PendingReplace => write!(f, "PENDING_REPLACE"),
+            OrderStatus::Replaced => write!(f, "REPLACED"),
+            OrderStatus::Filled => write!(f, "FILLED"),
+            OrderStatus::Expired => write!(f, "EXPIRED"),
+            OrderStatus::New => write!(f, "NEW"),
+            OrderStatus::AwaitingReleaseTime => write!(f, "AWAITING_RELEASE_TIME"),
+            OrderStatus::PendingAcknowledgement => write!(f, "PENDING_ACKNOWLEDGEMENT"),
+            OrderStatus::PendingRecall => write!(f, "PENDING_RECALL"),
+            OrderStatus::Unknown => write!(f, "UNKNOWN"),
+        }
+    }
+}
+
+impl OrderStatus {
+    /// Returns `true` if the order can no longer change: it was filled, canceled, replaced,
+    /// rejected or has expired.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Filled
+                | OrderStatus::Canceled
+                | OrderStatus::Replaced
+                | OrderStatus::Rejected
+                | OrderStatus::Expired
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub order_id: i64,
+    pub account_number: Option<i64>,
+    pub session: Option<String>,
+    pub duration: Option<String>,
+    pub order_type: Option<String>,
+    pub complex_order_strategy_type: Option<String>,
+    pub order_strategy_type: Option<String>,
+    pub quantity: f64,
+    #[serde(default)]
+    pub filled_quantity: f64,
+    #[serde(default)]
+    pub remaining_quantity: f64,
+    pub price: Option<f64>,
+    pub stop_price: Option<f64>,
+    pub status: OrderStatus,
+    pub entered_time: Option<DateTime<Utc>>,
+    pub close_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub cancelable: bool,
+    #[serde(default)]
+    pub editable: bool,
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub order_leg_collection: Vec<OrderLeg>,
+    #[serde(default)]
+    pub order_activity_collection: Vec<OrderActivity>,
+    #[serde(default)]
+    pub child_order_strategies: Vec<Order>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderLeg {
+    pub leg_id: i64,
+    pub order_leg_type: Option<String>,
+    pub instrument: OrderInstrument,
+    /// The side of the leg, e.g. `BUY`, `SELL`, `BUY_TO_OPEN` or `SELL_TO_CLOSE`.
+    pub instruction: String,
+    pub position_effect: Option<String>,
+    pub quantity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderInstrument {
+    pub asset_type: AssetType,
+    pub symbol: String,
+    pub cusip: Option<String>,
+    pub description: Option<String>,
+    pub instrument_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderActivity {
+    pub activity_type: String,
+    pub activity_id: Option<i64>,
+    pub execution_type: Option<String>,
+    pub quantity: Option<f64>,
+    pub order_remaining_quantity: Option<f64>,
+    #[serde(default)]
+    pub execution_legs: Vec<ExecutionLeg>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionLeg {
+    pub leg_id: i64,
+    pub quantity: f64,
+    pub mismarked_quantity: Option<f64>,
+    pub price: f64,
+    pub time: DateTime<Utc>,
+    pub instrument_id: Option<i64>,
+}
+
+/// A single fill, flattened from the execution legs of an order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Execution {
+    pub order_id: i64,
+    pub symbol: String,
+    /// The instruction of the filled leg, e.g. `BUY` or `SELL_TO_CLOSE`.
+    pub side: String,
+    pub quantity: f64,
+    pub price: f64,
+    pub time: DateTime<Utc>,
+}
+
+impl Order {
+    /// Flattens the `EXECUTION` activities of this order into one [`Execution`] per fill.
+    ///
+    /// Each execution leg is matched to its order leg by `legId` to recover the symbol and side.
+    pub fn executions(&self) -> Vec<Execution> {
+        self.order_activity_collection
+            .iter()
+            .filter(|activity| activity.activity_type == "EXECUTION")
+            .flat_map(|activity| &activity.execution_legs)
+            .filter_map(|execution_leg| {
+                let leg = self
+                    .order_leg_collection
+                    .iter()
+                    .find(|leg| leg.leg_id == execution_leg.leg_id)?;
+                Some(Execution {
+                    order_id: self.order_id,
+                    symbol: leg.instrument.symbol.clone(),
+                    side: leg.instruction.clone(),
+                    quantity: execution_leg.quantity,
+                    price: execution_leg.price,
+                    time: execution_leg.time,
+                })
+            })
+            .collect()
+    }
+}
+
+/// An entry of the `accounts` endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub securities_account: SecuritiesAccount,
+    pub aggregated_balance: Option<AggregatedBalance>,
+}
+
+/// A brokerage account with its balances and, when requested, its positions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecuritiesAccount {
+    /// The account type, e.g. `MARGIN` or `CASH`.
+    #[serde(rename = "type")]
+    pub account_type: String,
+    /// The plain account number. Trader endpoints expect its hash, see `AccountId`.
+    pub account_number: String,
+    pub round_trips: Option<i64>,
+    #[serde(default)]
+    pub is_day_trader: bool,
+    #[serde(default)]
+    pub is_closing_only_restricted: bool,
+    #[serde(default)]
+    pub pfcb_flag: bool,
+    /// Only populated when the request asks for `AccountField::Positions`.
+    #[serde(default)]
+    pub positions: Vec<Position>,
+    pub initial_balances: Option<AccountBalances>,
+    pub current_balances: Option<AccountBalances>,
+    pub projected_balances: Option<AccountBalances>,
+}
+
+/// Account balances as reported in `initialBalances`, `currentBalances` or `projectedBalances`.
+///
+/// Which fields are present depends on the account type and the section, so every field is
+/// optional.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AccountBalances {
+    pub accrued_interest: Option<f64>,
+    pub account_value: Option<f64>,
+    pub available_funds: Option<f64>,
+    pub available_funds_non_marginable_trade: Option<f64>,
+    pub bond_value: Option<f64>,
+    pub buying_power: Option<f64>,
+    pub buying_power_non_marginable_trade: Option<f64>,
+    pub cash_available_for_trading: Option<f64>,
+    pub cash_available_for_withdrawal: Option<f64>,
+    pub cash_balance: Option<f64>,
+    pub cash_call: Option<f64>,
+    pub cash_receipts: Option<f64>,
+    pub day_trading_buying_power: Option<f64>,
+    pub day_trading_buying_power_call: Option<f64>,
+    pub equity: Option<f64>,
+    pub equity_percentage: Option<f64>,
+    pub is_in_call: Option<bool>,
+    pub liquidation_value: Option<f64>,
+    pub long_margin_value: Option<f64>,
+    pub long_market_value: Option<f64>,
+    pub long_option_market_value: Option<f64>,
+    pub long_stock_value: Option<f64>,
+    pub maintenance_call: Option<f64>,
+    pub maintenance_requirement: Option<f64>,
+    pub margin: Option<f64>,
+    pub margin_balance: Option<f64>,
+    pub margin_equity: Option<f64>,
+    pub money_market_fund: Option<f64>,
+    pub mutual_fund_value: Option<f64>,
+    pub pending_deposits: Option<f64>,
+    pub reg_t_call: Option<f64>,
+    pub savings: Option<f64>,
+    pub short_balance: Option<f64>,
+    pub short_margin_value: Option<f64>,
+    pub short_market_value: Option<f64>,
+    pub short_option_market_value: Option<f64>,
+    pub short_stock_value: Option<f64>,
+    pub sma: Option<f64>,
+    pub total_cash: Option<f64>,
+    pub unsettled_cash: Option<f64>,
+}
+
+/// The balances aggregated across the account, as shown on Schwab's account summary.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AggregatedBalance {
+    pub current_liquidation_value: Option<f64>,
+    pub liquidation_value: Option<f64>,
+}
+
+/// A holding in a [`SecuritiesAccount`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Position {
+    pub instrument: PositionInstrument,
+    #[serde(default)]
+    pub long_quantity: f64,
+    #[serde(default)]
+    pub short_quantity: f64,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub settled_long_quantity: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub settled_short_quantity: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub aged_quantity: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub previous_session_long_quantity: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub previous_session_short_quantity: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub average_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub average_long_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub average_short_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub tax_lot_average_long_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub tax_lot_average_short_price: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub market_value: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub maintenance_requirement: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub current_day_profit_loss: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub current_day_profit_loss_percentage: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub current_day_cost: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub long_open_profit_loss: Option<f64>,
+    #[serde(default, deserialize_with = "option_f64_or_nan")]
+    pub short_open_profit_loss: Option<f64>,
+}
+
+impl Position {
+    /// Returns the net quantity held: positive for a long position, negative for a short one.
+    pub fn net_quantity(&self) -> f64 {
+        self.long_quantity - self.short_quantity
+    }
+}
+
+/// The instrument of a [`Position`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionInstrument {
+    /// The asset type, e.g. `EQUITY`, `OPTION` or `COLLECTIVE_INVESTMENT`.
+    pub asset_type: AssetType,
+    pub symbol: String,
+    pub cusip: Option<String>,
+    pub description: Option<String>,
+    pub net_change: Option<f64>,
+    #[serde(rename = "type")]
+    pub instrument_type: Option<String>,
+    pub put_call: Option<String>,
+    pub underlying_symbol: Option<String>,
+}
+
+/// The type of an account transaction, as reported by Schwab and accepted by the `types` filter
+/// of the transactions endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransactionType {
+    Trade,
+    ReceiveAndDeliver,
+    DividendOrInterest,
+    AchReceipt,
+    AchDisbursement,
+    CashReceipt,
+    CashDisbursement,
+    ElectronicFund,
+    WireOut,
+    WireIn,
+    Journal,
+    Memorandum,
+    MarginCall,
+    MoneyMarket,
+    SmaAdjustment,
+    #[serde(other)]
+    Unknown,
+}
+
+impl TransactionType {
+    /// Every transaction type the transactions endpoint accepts as a filter.
+    pub const ALL: [TransactionType; 15] = [
+        TransactionType::Trade,
+        TransactionType::ReceiveAndDeliver,
+        TransactionType::DividendOrInterest,
+        TransactionType::AchReceipt,
+        TransactionType::AchDisbursement,
+        TransactionType::CashReceipt,
+        TransactionType::CashDisbursement,
+        TransactionType::ElectronicFund,
+        TransactionType::WireOut,
+        TransactionType::WireIn,
+        TransactionType::Journal,
+        TransactionType::Memorandum,
+        TransactionType::MarginCall,
+        TransactionType::MoneyMarket,
+        TransactionType::SmaAdjustment,
+    ];
+}
+
+impl fmt::Display for TransactionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionType::Trade => write!(f, "TRADE"),
+            TransactionType::ReceiveAndDeliver => write!(f, "RECEIVE_AND_DELIVER"),
+            TransactionType::DividendOrInterest => write!(f, "DIVIDEND_OR_INTEREST"),
+            TransactionType::AchReceipt => write!(f, "ACH_RECEIPT"),
+            TransactionType::AchDisbursement => write!(f, "ACH_DISBURSEMENT"),
+            TransactionType::CashReceipt => write!(f, "CASH_RECEIPT"),
+            TransactionType::CashDisbursement => write!(f, "CASH_DISBURSEMENT"),
+            TransactionType::ElectronicFund => write!(f, "ELECTRONIC_FUND"),
+            TransactionType::WireOut => write!(f, "WIRE_OUT"),
+            TransactionType::WireIn => write!(f, "WIRE_IN"),
+            TransactionType::Journal => write!(f, "JOURNAL"),
+            TransactionType::Memorandum => write!(f, "MEMORANDUM"),
+            TransactionType::MarginCall => write!(f, "MARGIN_CALL"),
+            TransactionType::MoneyMarket => write!(f, "MONEY_MARKET"),
+            TransactionType::SmaAdjustment => write!(f, "SMA_ADJUSTMENT"),
+            TransactionType::Unknown => write!(f, "UNKNOWN"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transaction {
+    pub activity_id: i64,
+    pub time: DateTime<Utc>,
+    pub description: Option<String>,
+    pub account_number: Option<String>,
+    #[serde(rename = "type")]
+    pub transaction_type: TransactionType,
+    pub status: Option<String>,
+    pub sub_account: Option<String>,
+    pub trade_date: Option<DateTime<Utc>>,
+    pub settlement_date: Option<DateTime<Utc>>,
+    pub position_id: Option<i64>,
+    pub order_id: Option<i64>,
+    pub net_amount: Option<f64>,
+    pub activity_type: Option<String>,
+    #[serde(default)]
+    pub transfer_items: Vec<TransferItem>,
+}
+
+impl Transaction {
+    /// Returns the fees charged on the transaction, keyed by `fee_type` (e.g. `COMMISSION` or
+    /// `SEC_FEE`). Amounts are positive; fee types charged more than once are summed.
+    pub fn fees(&self) -> HashMap<String, f64> {
+        let mut fees = HashMap::new();
+        for item in &self.transfer_items {
+            if let Some(fee_type) = &item.fee_type {
+                *fees.entry(fee_type.clone()).or_insert(0.0) += item.cost.abs();
+            }
+        }
+        fees
+    }
+
+    /// Returns the sum of every fee charged on the transaction.
+    pub fn total_fees(&self) -> f64 {
+        self.transfer_items
+            .iter()
+            .filter(|item| item.fee_type.is_some())
+            .fold(0.0, |sum, item| sum + item.cost.abs())
+    }
+}
+
+/// One movement of cash or securities within a [`Transaction`].
+///
+/// A trade carries one item for the security and one item per fee, the latter with `fee_type`
+/// set and a currency instrument.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferItem {
+    pub instrument: TransactionInstrument,
+    /// The quantity moved: positive when received, negative when delivered.
+    #[serde(default)]
+    pub amount: f64,
+    /// The cash effect of the item: negative when paid, positive when received.
+    #[serde(default)]
+    pub cost: f64,
+    pub price: Option<f64>,
+    pub fee_type: Option<String>,
+    /// `OPENING` or `CLOSING` for trades.
+    pub position_effect: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionInstrument {
+    /// The asset type, e.g. `EQUITY`, `OPTION` or `CURRENCY`.
+    pub asset_type: AssetType,
+    pub symbol: Option<String>,
+    pub cusip: Option<String>,
+    pub description: Option<String>,
+    pub instrument_id: Option<i64>,
+    pub put_call: Option<String>,
+    pub underlying_symbol: Option<String>,
+    /// The contract multiplier of an option, usually 100.
+    pub option_premium_multiplier: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    fn preferences(streamer_info: Value) -> Value {
+        json!({
+            "accounts": [{
+                "accountColor": "Green",
+                "accountNumber": "12345678",
+                "autoPositionEffect": false,
+                "displayAcctId": "...678",
+                "lotSelectionMethod": "FIFO",
+                "nickName": "Individual",
+                "primaryAccount": true,
+                "type": "BROKERAGE"
+            }],
+            "offers": [{ "level2Permissions": true, "mktDataPermission": "NP" }],
+            "streamerInfo": [streamer_info]
+        })
+    }
+
+    #[test]
+    fn position_maps_placeholders_to_none() {
+        let position: Position = serde_json::from_value(json!({
+            "instrument": { "assetType": "EQUITY", "symbol": "AAPL" },
+            "longQuantity": 10.0,
+            "averagePrice": "NaN",
+            "marketValue": -999.0,
+            "currentDayProfitLoss": 12.5
+        }))
+        .unwrap();
+
+        assert_eq!(position.net_quantity(), 10.0);
+        assert_eq!(position.average_price, None);
+        assert_eq!(position.market_value, None);
+        assert_eq!(position.current_day_profit_loss, Some(12.5));
+        assert_eq!(position.long_open_profit_loss, None);
+    }
+
+    #[test]
+    fn streamer_ids_parse_from_a_preferences_fixture() {
+        let camel_case: UserPreferencesResponse = serde_json::from_value(preferences(json!({
+            "streamerSocketUrl": "wss://streamer-api.schwab.com/ws",
+            "schwabClientCustomerId": "customer-id",
+            "schwabClientCorrelId": "correl-id",
+            "schwabClientChannel": "N9",
+            "schwabClientFunctionId": "APIAPP"
+        })))
+        .unwrap();
+        let pascal_case: UserPreferencesResponse = serde_json::from_value(preferences(json!({
+            "StreamerSocketUrl": "wss://streamer-api.schwab.com/ws",
+            "SchwabClientCustomerId": "customer-id",
+            "SchwabClientCorrelId": "correl-id",
+            "SchwabClientChannel": "N9",
+            "SchwabClientFunctionId": "APIAPP"
+        })))
+        .unwrap();
+
+        for response in [camel_case, pascal_case] {
+            let info = &response.streamer_info[0];
+            info.validate().unwrap();
+            assert_eq!(info.streamer_socket_url, "wss://streamer-api.schwab.com/ws");
+            assert_eq!(info.schwab_client_customer_id, "customer-id");
+            assert_eq!(info.schwab_client_correl_id, "correl-id");
+            assert_eq!(info.schwab_client_channel, "N9");
+            assert_eq!(info.schwab_client_function_id, "APIAPP");
+        }
+    }
+
+    #[test]
+    fn missing_or_renamed_streamer_ids_are_reported_by_key() {
+        let cases = [
+            (
+                json!({
+                    "schwabClientCustomerId": "customer-id",
+                    "schwabClientChannel": "N9",
+                    "schwabClientFunctionId": "APIAPP"
+                }),
+                "schwabClientCorrelId",
+            ),
+            (
+                json!({
+                    "schwabClientCustomerId": "customer-id",
+                    "schwabClientCorrelID": "correl-id",
+                    "schwabClientChannel": "N9",
+                    "schwabClientFunctionId": "APIAPP"
+                }),
+                "schwabClientCorrelId",
+            ),
+            (
+                json!({
+                    "schwabClientCustomerId": "  ",
+                    "schwabClientCorrelId": "correl-id",
+                    "schwabClientChannel": "N9",
+                    "schwabClientFunctionId": "APIAPP"
+                }),
+                "schwabClientCustomerId",
+            ),
+            (
+                json!({
+                    "schwabClientCustomerId": "customer-id",
+                    "schwabClientCorrelId": "correl-id",
+                    "schwabClientChannel": "N9",
+                    "schwab_client_function_id": "APIAPP"
+                }),
+                "schwabClientFunctionId",
+            ),
+        ];
+
+        for (streamer_info, key) in cases {
+            let response: UserPreferencesResponse =
+                serde_json::from_value(preferences(streamer_info)).unwrap();
+            let error = response.streamer_info[0].validate().unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!(
+                    "streamer error: Streamer info from user preferences is missing `{}`",
+                    key
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::schwab::models::{
+    market_data::AssetType,
+    trader::{Transaction, TransactionType, TransferItem},
+};
+
+/// The order in which closing trades are matched against open lots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LotMatching {
+    /// First in, first out: the oldest open lot is closed first.
+    #[default]
+    Fifo,
+    /// Last in, first out: the most recent open lot is closed first.
+    Lifo,
+}
+
+/// The realized profit and loss of a single symbol.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SymbolPnl {
+    /// The gross realized gain (positive) or loss (negative), before fees.
+    pub realized: f64,
+    /// The fees and commissions paid on every trade of the symbol, opening trades included. The
+    /// fees of a multi-leg trade are split across its legs by quantity.
+    pub fees: f64,
+    /// The quantity closed against earlier lots.
+    pub closed_quantity: f64,
+    /// The quantity still open after the last trade: positive if long, negative if short.
+    pub open_quantity: f64,
+}
+
+impl SymbolPnl {
+    /// Returns the realized gain or loss after fees.
+    pub fn net(&self) -> f64 {
+        self.realized - self.fees
+    }
+}
+
+/// Realized profit and loss per symbol, computed by [`realized_pnl`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RealizedPnl {
+    pub by_symbol: HashMap<String, SymbolPnl>,
+}
+
+impl RealizedPnl {
+    /// Returns the gross realized gain or loss across every symbol.
+    pub fn total(&self) -> f64 {
+        self.by_symbol.values().map(|pnl| pnl.realized).sum()
+    }
+
+    /// Returns the realized gain or loss after fees across every symbol.
+    pub fn total_net(&self) -> f64 {
+        self.by_symbol.values().map(SymbolPnl::net).sum()
+    }
+}
+
+/// An open position opened at a single price. `quantity` is negative for a short lot.
+#[derive(Debug, Clone, Copy)]
+struct Lot {
+    quantity: f64,
+    price: f64,
+}
+
+/// Computes realized profit and loss from `TRADE` transactions, matching lots first in, first out.
+///
+/// See [`realized_pnl_with`].
+pub fn realized_pnl(transactions: &[Transaction], symbol: Option<&str>) -> RealizedPnl {
+    realized_pnl_with(transactions, symbol, LotMatching::default())
+}
+
+/// Computes realized profit and loss from `TRADE` transactions, matching lots in the given order.
+///
+/// Transactions are processed in time order regardless of their order in `transactions`. A
+/// purchase first closes open short lots and a sale first closes open long lots; any remaining
+/// quantity opens a new lot, so short sales are handled like purchases in reverse. Partial fills
+/// split lots as needed. Option gains are scaled by the contract's `optionPremiumMultiplier`.
+///
+/// Only the trades in `transactions` are seen, so positions opened before the first transaction
+/// are treated as new lots when closed. Fetch a range that starts before the positions were
+/// opened for accurate results.
+///
+/// # Arguments
+/// * `transactions` - The transactions, e.g. from `SchwabApi::get_transactions`.
+/// * `symbol` - Only compute the P&L of this symbol.
+/// * `matching` - The order in which open lots are closed.
+pub fn realized_pnl_with(
+    transactions: &[Transaction],
+    symbol: Option<&str>,
+    matching: LotMatching,
+) -> RealizedPnl {
+    let mut trades: Vec<&Transaction> = transactions
+        .iter()
+        .filter(|t| t.transaction_type == TransactionType::Trade)
+        .collect();
+    trades.sort_by_key(|t| t.time);
+
+    let mut lots: HashMap<String, VecDeque<Lot>> = HashMap::new();
+    let mut pnl = RealizedPnl::default();
+
+    for transaction in trades {
+        let (securities, fees): (Vec<&TransferItem>, Vec<&TransferItem>) =
+            transaction.transfer_items.iter().partition(|item| {
+                item.fee_type.is_none() && item.instrument.asset_type != AssetType::Currency
+            });
+        let fees: f64 = fees.iter().map(|item| item.cost.abs()).sum();
+        let legs: Vec<(&str, f64, &TransferItem)> = securities
+            .into_iter()
+            .filter_map(|item| Some((item.instrument.symbol.as_deref()?, item.price?, item)))
+            .collect();
+        // Fees are split across the legs by quantity, so that filtering by symbol keeps the
+        // share of every leg it selects.
+        let total_quantity: f64 = legs.iter().map(|(_, _, item)| item.amount.abs()).sum();
+
+        for (item_symbol, price, item) in legs.iter().copied() {
+            if symbol.is_some_and(|symbol| symbol != item_symbol) {
+                continue;
+            }
+
+            let symbol_pnl = pnl.by_symbol.entry(item_symbol.to_string()).or_default();
+            symbol_pnl.fees += if total_quantity > 0.0 {
+                fees * item.amount.abs() / total_quantity
+            } else {
+                fees / legs.len() as f64
+            };
+
+            let multiplier = item.instrument.option_premium_multiplier.unwrap_or(1.0);
+            let symbol_lots = lots.entry(item_symbol.to_string()).or_default();
+            apply_trade(
+                symbol_lots,
+                symbol_pnl,
+                signed_quantity(item),
+                price,
+                multiplier,
+                matching,
+            );
+            symbol_pnl.open_quantity = symbol_lots.iter().fold(0.0, |sum, lot| sum + lot.quantity);
+        }
+    }
+
+    pnl
+}
+
+/// Returns the traded quantity of `item`: positive for a purchase, negative for a sale.
+///
+/// Schwab signs `amount` by direction; a positive amount with a positive (received) cost is
+/// still treated as a sale.
+fn signed_quantity(item: &TransferItem) -> f64 {
+    if item.amount > 0.0 && item.cost > 0.0 {
+        -item.amount
+    } else {
+        item.amount
+    }
+}
+
+/// Closes open lots against a trade of `quantity` at `price`, then opens a lot for the rest.
+fn apply_trade(
+    lots: &mut VecDeque<Lot>,
+    pnl: &mut SymbolPnl,
+    mut quantity: f64,
+    price: f64,
+    multiplier: f64,
+    matching: LotMatching,
+) {
+    while quantity != 0.0 {
+        let lot = match matching {
+            LotMatching::Fifo => lots.front_mut(),
+            LotMatching::Lifo => lots.back_mut(),
+        };
+        let Some(lot) = lot.filter(|lot| lot.quantity.signum() != quantity.signum()) else {
+            break;
+        };
+
+        let closed = quantity.abs().min(lot.quantity.abs());
+        // A long lot gains when sold above its price, a short lot when bought back below it.
+        pnl.realized += closed * (price - lot.price) * lot.quantity.signum() * multiplier;
+        pnl.closed_quantity += closed;
+
+        lot.quantity -= closed * lot.quantity.signum();
+        quantity -= closed * quantity.signum();
+        if lot.quantity == 0.0 {
+            match matching {
+                LotMatching::Fifo => lots.pop_front(),
+                LotMatching::Lifo => lots.pop_back(),
+            };
+        }
+    }
+
+    if quantity != 0.0 {
+        lots.push_back(Lot { quantity, price });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    fn leg(symbol: &str, amount: f64, price: f64) -> Value {
+        json!({
+            "instrument": { "assetType": "EQUITY", "symbol": symbol },
+            "amount": amount,
+            "cost": -amount * price,
+            "price": price
+        })
+    }
+
+    fn trade(activity_id: i64, legs: Vec<Value>, fees: f64) -> Transaction {
+        let mut items = legs;
+        if fees > 0.0 {
+            items.push(json!({
+                "instrument": { "assetType": "CURRENCY", "symbol": "CURRENCY_USD" },
+                "cost": -fees,
+                "feeType": "COMMISSION"
+            }));
+        }
+        serde_json::from_value(json!({
+            "activityId": activity_id,
+            "time": format!("2024-03-0{}T15:00:00Z", activity_id),
+            "type": "TRADE",
+            "transferItems": items
+        }))
+        .unwrap()
+    }
+
+    /// Two purchases at different prices, closed by a partial sale and then the rest.
+    fn two_lots() -> Vec<Transaction> {
+        vec![
+            trade(4, vec![leg("AAPL", -5.0, 130.0)], 0.0),
+            trade(1, vec![leg("AAPL", 10.0, 100.0)], 0.0),
+            trade(2, vec![leg("AAPL", 10.0, 110.0)], 0.0),
+            trade(3, vec![leg("AAPL", -15.0, 120.0)], 0.0),
+        ]
+    }
+
+    #[test]
+    fn fifo_closes_the_oldest_lot_first() {
+        let transactions = two_lots();
+
+        let partial = realized_pnl(&transactions[1..], None).by_symbol["AAPL"];
+        // 10 from the lot at 100 and 5 from the lot at 110.
+        assert_eq!(partial.realized, 10.0 * 20.0 + 5.0 * 10.0);
+        assert_eq!(partial.closed_quantity, 15.0);
+        assert_eq!(partial.open_quantity, 5.0);
+
+        let full = realized_pnl(&transactions, None).by_symbol["AAPL"];
+        // The remaining 5 of the lot at 110, sold at 130.
+        assert_eq!(full.realized, 250.0 + 5.0 * 20.0);
+        assert_eq!(full.open_quantity, 0.0);
+    }
+
+    #[test]
+    fn lifo_closes_the_newest_lot_first() {
+        let transactions = two_lots();
+
+        let partial =
+            realized_pnl_with(&transactions[1..], None, LotMatching::Lifo).by_symbol["AAPL"];
+        // 10 from the lot at 110 and 5 from the lot at 100.
+        assert_eq!(partial.realized, 10.0 * 10.0 + 5.0 * 20.0);
+        assert_eq!(partial.open_quantity, 5.0);
+
+        let full = realized_pnl_with(&transactions, None, LotMatching::Lifo).by_symbol["AAPL"];
+        // The remaining 5 of the lot at 100, sold at 130.
+        assert_eq!(full.realized, 200.0 + 5.0 * 30.0);
+        assert_eq!(full.open_quantity, 0.0);
+    }
+
+    #[test]
+    fn short_sales_gain_when_bought_back_lower() {
+        let transactions = vec![
+            trade(1, vec![leg("TSLA", -10.0, 50.0)], 0.0),
+            trade(2, vec![leg("TSLA", 4.0, 40.0)], 0.0),
+        ];
+
+        let pnl = realized_pnl(&transactions, None).by_symbol["TSLA"];
+        assert_eq!(pnl.realized, 40.0);
+        assert_eq!(pnl.open_quantity, -6.0);
+    }
+
+    #[test]
+    fn option_gains_use_the_premium_multiplier() {
+        let option = |amount: f64, price: f64| {
+            json!({
+                "instrument": {
+                    "assetType": "OPTION",
+                    "symbol": "SPY   240621C00500000",
+                    "optionPremiumMultiplier": 100.0
+                },
+                "amount": amount,
+                "cost": -amount * price * 100.0,
+                "price": price
+            })
+        };
+        let transactions = vec![
+            trade(1, vec![option(1.0, 2.0)], 0.65),
+            trade(2, vec![option(-1.0, 3.0)], 0.65),
+        ];
+
+        let pnl = realized_pnl(&transactions, None).by_symbol["SPY   240621C00500000"];
+        assert_eq!(pnl.realized, 100.0);
+        assert_eq!(pnl.fees, 1.3);
+        assert_eq!(pnl.net(), 98.7);
+    }
+
+    #[test]
+    fn symbol_filter_keeps_the_fee_share_of_later_legs() {
+        let transactions = vec![trade(
+            1,
+            vec![leg("AAPL", 3.0, 100.0), leg("MSFT", 1.0, 400.0)],
+            2.0,
+        )];
+
+        let msft = realized_pnl(&transactions, Some("MSFT"));
+        assert_eq!(msft.by_symbol.len(), 1);
+        assert_eq!(msft.by_symbol["MSFT"].fees, 0.5);
+
+        let all = realized_pnl(&transactions, None);
+        assert_eq!(all.by_symbol["AAPL"].fees, 1.5);
+        assert_eq!(all.by_symbol["MSFT"].fees, 0.5);
+    }
+}
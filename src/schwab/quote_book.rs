@@ -0,0 +1,166 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serde_json::Value;
+use tokio::sync::{RwLock, mpsc, watch};
+use tracing::warn;
+
+use crate::schwab::models::streamer::{LevelOneEquitiesResponse, StreamerMessage, merge_fields};
+
+/// The latest Level 1 equity quote for every symbol seen on a stream.
+///
+/// The streamer only sends the fields that changed since the previous update, so each update is
+/// merged into the stored quote for its symbol rather than replacing it. The result is a complete,
+/// current quote per symbol, suited to dashboards that render a table instead of individual ticks.
+///
+/// Memory grows with the number of distinct symbols, not with the number of updates: one merged
+/// `LevelOneEquitiesResponse` is kept per symbol until it is removed with [`QuoteBook::remove`] or
+/// [`QuoteBook::clear`]. [`QuoteBook::snapshot`] clones the whole map, so prefer
+/// [`QuoteBook::get`] when only a few symbols are needed.
+///
+/// Clones share the same underlying map.
+#[derive(Debug, Clone)]
+pub struct QuoteBook {
+    equities: Arc<RwLock<HashMap<String, LevelOneEquitiesResponse>>>,
+    version: Arc<watch::Sender<u64>>,
+}
+
+impl Default for QuoteBook {
+    fn default() -> Self {
+        Self {
+            equities: Arc::new(RwLock::new(HashMap::new())),
+            version: Arc::new(watch::channel(0).0),
+        }
+    }
+}
+
+impl QuoteBook {
+    /// Creates an empty `QuoteBook`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a `QuoteBook` that is kept up to date from a streamer's message channel.
+    ///
+    /// The book takes over the receiver returned by `SchwabStreamer::start`, so messages that are
+    /// not Level 1 equity quotes are discarded. Feed the book through [`QuoteBook::apply`] instead
+    /// to keep handling other messages yourself.
+    pub fn spawn(mut receiver: mpsc::Receiver<StreamerMessage>) -> Self {
+        let book = Self::new();
+        let task_book = book.clone();
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                task_book.apply(&message).await;
+            }
+        });
+        book
+    }
+
+    /// Merges a Level 1 equity update into the book. Returns `true` if the book changed, and
+    /// `false` for other messages and for updates that carry no new field values.
+    ///
+    /// Every change bumps the version observed through [`QuoteBook::updates`].
+    pub async fn apply(&self, message: &StreamerMessage) -> bool {
+        let StreamerMessage::LevelOneEquity(update) = message else {
+            return false;
+        };
+
+        let mut equities = self.equities.write().await;
+        let changed = match equities.get_mut(&update.symbol) {
+            Some(existing) => merge(existing, update),
+            None => {
+                equities.insert(update.symbol.clone(), update.clone());
+                true
+            }
+        };
+        drop(equities);
+
+        if changed {
+            self.version.send_modify(|version| *version += 1);
+        }
+        changed
+    }
+
+    /// Returns a copy of the latest quote for every symbol.
+    pub async fn snapshot(&self) -> HashMap<String, LevelOneEquitiesResponse> {
+        self.equities.read().await.clone()
+    }
+
+    /// Returns the latest quote for `symbol`, if any update for it has been received.
+    pub async fn get(&self, symbol: &str) -> Option<LevelOneEquitiesResponse> {
+        self.equities.read().await.get(symbol).cloned()
+    }
+
+    /// Removes `symbol` from the book, e.g. after unsubscribing from it.
+    pub async fn remove(&self, symbol: &str) -> Option<LevelOneEquitiesResponse> {
+        self.equities.write().await.remove(symbol)
+    }
+
+    /// Removes every symbol from the book.
+    pub async fn clear(&self) {
+        self.equities.write().await.clear();
+    }
+
+    /// Returns a receiver that is notified whenever the book changes.
+    ///
+    /// The value is a counter of applied updates. Await `changed()` on the receiver and then call
+    /// [`QuoteBook::snapshot`] to redraw on every batch.
+    pub fn updates(&self) -> watch::Receiver<u64> {
+        self.version.subscribe()
+    }
+}
+
+/// Overwrites the fields of `existing` with every field present in `update`. Returns `true` if
+/// any of them changed.
+fn merge(existing: &mut LevelOneEquitiesResponse, update: &LevelOneEquitiesResponse) -> bool {
+    let Ok(Value::Object(mut snapshot)) = serde_json::to_value(&*existing) else {
+        return false;
+    };
+    let before = snapshot.clone();
+
+    match merge_fields(&mut snapshot, update) {
+        Ok(quote) => {
+            *existing = quote;
+            snapshot != before
+        }
+        Err(e) => {
+            warn!("Failed to merge update for {}: {}", update.symbol, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn equity(frame: Value) -> StreamerMessage {
+        StreamerMessage::LevelOneEquity(serde_json::from_value(frame).unwrap())
+    }
+
+    #[tokio::test]
+    async fn apply_reports_whether_the_book_changed() {
+        let book = QuoteBook::new();
+        let updates = book.updates();
+
+        let mut changed = Vec::new();
+        for message in [
+            equity(json!({ "key": "AAPL", "1": 189.5 })),
+            equity(json!({ "key": "AAPL", "2": 189.7 })),
+            // Repeats a known value, then carries no field at all.
+            equity(json!({ "key": "AAPL", "1": 189.5 })),
+            equity(json!({ "key": "AAPL" })),
+            StreamerMessage::Connected,
+        ] {
+            changed.push(book.apply(&message).await);
+        }
+
+        assert_eq!(changed, [true, true, false, false, false]);
+
+        assert_eq!(*updates.borrow(), 2);
+        let quote = book.get("AAPL").await.unwrap();
+        assert_eq!(quote.bid_price, Some(189.5));
+        assert_eq!(quote.ask_price, Some(189.7));
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::Mutex;
+
+/// A token-bucket rate limiter that delays requests instead of rejecting them.
+///
+/// The bucket refills continuously at `requests_per_minute / 60` tokens per second and holds at
+/// most one second worth of tokens, so short bursts are allowed without ever exceeding the
+/// per-minute budget. Clones share the same bucket, which keeps every clone of a `SchwabApi`
+/// under a single limit.
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a limiter allowing `requests_per_minute` requests. Must be greater than zero.
+    pub(crate) fn new(requests_per_minute: u32) -> Self {
+        let refill_per_sec = f64::from(requests_per_minute) / 60.0;
+        let capacity = refill_per_sec.ceil().max(1.0);
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                capacity,
+                tokens: capacity,
+                refill_per_sec,
+                last_refill: Instant::now(),
+            })),
+        }
+    }
+
+    /// Waits until a token is available and takes it.
+    pub(crate) async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                bucket.refill();
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.refill_per_sec)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl Bucket {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::join_all;
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::schwab::{test_support, transport::MockTransport};
+
+    #[tokio::test]
+    async fn requests_over_the_limit_are_delayed() {
+        let transport = Arc::new(MockTransport::new());
+        for _ in 0..10 {
+            transport.respond(StatusCode::OK, "{}");
+        }
+        let api = test_support::api(&transport).await.with_rate_limit(120);
+
+        let started = Instant::now();
+        let results = join_all((0..10).map(|_| {
+            let api = api.clone();
+            async move { api.get_quotes(vec!["AAPL".to_string()], None, None).await }
+        }))
+        .await;
+        let elapsed = started.elapsed();
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(transport.requests().len(), 10);
+        // Two requests fit in the initial burst; the other eight wait half a second each.
+        assert!(elapsed >= Duration::from_millis(3900), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(6), "{:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn clones_share_one_bucket() {
+        let limiter = RateLimiter::new(600);
+        let clone = limiter.clone();
+
+        let started = Instant::now();
+        for _ in 0..10 {
+            limiter.acquire().await;
+        }
+        clone.acquire().await;
+
+        // The first ten tokens are the burst; the eleventh refills at ten per second.
+        assert!(
+            started.elapsed() >= Duration::from_millis(90),
+            "{:?}",
+            started.elapsed()
+        );
+    }
+}
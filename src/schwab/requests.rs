@@ -0,0 +1,999 @@
+use std::fmt;
+
+use chrono::{DateTime, Month, NaiveDate, NaiveTime, Utc};
+use serde::Serialize;
+
+use crate::{
+    schwab::{
+        error::SchwabError,
+        schwab_api::{ContractType, FrequencyType, PeriodType},
+    },
+    util::{parse_params, time_to_epoch_ms, time_to_yyyymmdd},
+};
+
+/// Represents the strategy used to build an options chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChainStrategy {
+    /// Single contracts.
+    Single,
+    /// Theoretical values computed from the `volatility`, `underlying_price`, `interest_rate` and
+    /// `days_to_expiration` inputs.
+    Analytical,
+    Covered,
+    Vertical,
+    Calendar,
+    Strangle,
+    Straddle,
+    Butterfly,
+    Condor,
+    Diagonal,
+    Collar,
+    Roll,
+}
+
+impl fmt::Display for ChainStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainStrategy::Single => write!(f, "SINGLE"),
+            ChainStrategy::Analytical => write!(f, "ANALYTICAL"),
+            ChainStrategy::Covered => write!(f, "COVERED"),
+            ChainStrategy::Vertical => write!(f, "VERTICAL"),
+            ChainStrategy::Calendar => write!(f, "CALENDAR"),
+            ChainStrategy::Strangle => write!(f, "STRANGLE"),
+            ChainStrategy::Straddle => write!(f, "STRADDLE"),
+            ChainStrategy::Butterfly => write!(f, "BUTTERFLY"),
+            ChainStrategy::Condor => write!(f, "CONDOR"),
+            ChainStrategy::Diagonal => write!(f, "DIAGONAL"),
+            ChainStrategy::Collar => write!(f, "COLLAR"),
+            ChainStrategy::Roll => write!(f, "ROLL"),
+        }
+    }
+}
+
+/// Represents the range of strikes returned in an options chain, relative to the underlying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StrikeRange {
+    /// In-the-money.
+    InTheMoney,
+    /// Near-the-money.
+    NearTheMoney,
+    /// Out-of-the-money.
+    OutOfTheMoney,
+    /// Strikes above market.
+    StrikesAboveMarket,
+    /// Strikes below market.
+    StrikesBelowMarket,
+    /// Strikes near market.
+    StrikesNearMarket,
+    /// All strikes.
+    All,
+}
+
+impl fmt::Display for StrikeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrikeRange::InTheMoney => write!(f, "ITM"),
+            StrikeRange::NearTheMoney => write!(f, "NTM"),
+            StrikeRange::OutOfTheMoney => write!(f, "OTM"),
+            StrikeRange::StrikesAboveMarket => write!(f, "SAK"),
+            StrikeRange::StrikesBelowMarket => write!(f, "SBK"),
+            StrikeRange::StrikesNearMarket => write!(f, "SNK"),
+            StrikeRange::All => write!(f, "ALL"),
+        }
+    }
+}
+
+/// Represents whether standard or non-standard contracts are returned in an options chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OptionType {
+    /// Standard contracts.
+    Standard,
+    /// Non-standard contracts, e.g. after a corporate action.
+    NonStandard,
+    /// All contracts.
+    All,
+}
+
+impl fmt::Display for OptionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionType::Standard => write!(f, "S"),
+            OptionType::NonStandard => write!(f, "NS"),
+            OptionType::All => write!(f, "ALL"),
+        }
+    }
+}
+
+/// Represents the market data entitlement of the requesting client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Entitlement {
+    /// Paying professional.
+    PayingPro,
+    /// Non-professional.
+    NonPro,
+    /// Non-paying professional.
+    NonPayingPro,
+}
+
+impl fmt::Display for Entitlement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entitlement::PayingPro => write!(f, "PP"),
+            Entitlement::NonPro => write!(f, "NP"),
+            Entitlement::NonPayingPro => write!(f, "PN"),
+        }
+    }
+}
+
+/// The full set of parameters accepted by Schwab's `/chains` endpoint.
+///
+/// Only the parameters that are set are sent. Use with `SchwabApi::get_chains_with`.
+#[derive(Debug, Clone)]
+pub struct ChainsRequest {
+    symbol: String,
+    contract_type: Option<ContractType>,
+    strike_count: Option<u64>,
+    include_underlying_quote: Option<bool>,
+    strategy: Option<ChainStrategy>,
+    interval: Option<f64>,
+    strike: Option<f64>,
+    range: Option<StrikeRange>,
+    from_date: Option<DateTime<Utc>>,
+    to_date: Option<DateTime<Utc>>,
+    volatility: Option<f64>,
+    underlying_price: Option<f64>,
+    interest_rate: Option<f64>,
+    days_to_expiration: Option<u64>,
+    exp_month: Option<Month>,
+    option_type: Option<OptionType>,
+    entitlement: Option<Entitlement>,
+}
+
+impl ChainsRequest {
+    /// Creates a request for the options chain of `symbol` with every other parameter unset.
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            contract_type: None,
+            strike_count: None,
+            include_underlying_quote: None,
+            strategy: None,
+            interval: None,
+            strike: None,
+            range: None,
+            from_date: None,
+            to_date: None,
+            volatility: None,
+            underlying_price: None,
+            interest_rate: None,
+            days_to_expiration: None,
+            exp_month: None,
+            option_type: None,
+            entitlement: None,
+        }
+    }
+
+    /// Sets the type of contracts to return.
+    pub fn contract_type(mut self, contract_type: ContractType) -> Self {
+        self.contract_type = Some(contract_type);
+        self
+    }
+
+    /// Sets the number of strikes to return above and below the at-the-money price.
+    ///
+    /// Must be at least 1. Leave it unset to return every strike; Schwab may cap very large
+    /// counts at the number of strikes listed.
+    pub fn strike_count(mut self, strike_count: u64) -> Self {
+        self.strike_count = Some(strike_count);
+        self
+    }
+
+    /// Sets whether to include a quote for the underlying.
+    pub fn include_underlying_quote(mut self, include_underlying_quote: bool) -> Self {
+        self.include_underlying_quote = Some(include_underlying_quote);
+        self
+    }
+
+    /// Sets the strategy used to build the chain.
+    pub fn strategy(mut self, strategy: ChainStrategy) -> Self {
+        self.strategy = Some(strategy);
+        self
+    }
+
+    /// Sets the strike interval for spread strategy chains.
+    pub fn interval(mut self, interval: f64) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Returns only contracts with the given strike price.
+    pub fn strike(mut self, strike: f64) -> Self {
+        self.strike = Some(strike);
+        self
+    }
+
+    /// Sets the range of strikes to return.
+    pub fn range(mut self, range: StrikeRange) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    /// Returns only expirations on or after `from_date`.
+    pub fn from_date(mut self, from_date: DateTime<Utc>) -> Self {
+        self.from_date = Some(from_date);
+        self
+    }
+
+    /// Returns only expirations on or before `to_date`.
+    pub fn to_date(mut self, to_date: DateTime<Utc>) -> Self {
+        self.to_date = Some(to_date);
+        self
+    }
+
+    /// Sets the volatility used in `ChainStrategy::Analytical` calculations.
+    pub fn volatility(mut self, volatility: f64) -> Self {
+        self.volatility = Some(volatility);
+        self
+    }
+
+    /// Sets the underlying price used in `ChainStrategy::Analytical` calculations.
+    pub fn underlying_price(mut self, underlying_price: f64) -> Self {
+        self.underlying_price = Some(underlying_price);
+        self
+    }
+
+    /// Sets the interest rate used in `ChainStrategy::Analytical` calculations.
+    pub fn interest_rate(mut self, interest_rate: f64) -> Self {
+        self.interest_rate = Some(interest_rate);
+        self
+    }
+
+    /// Sets the days to expiration used in `ChainStrategy::Analytical` calculations.
+    pub fn days_to_expiration(mut self, days_to_expiration: u64) -> Self {
+        self.days_to_expiration = Some(days_to_expiration);
+        self
+    }
+
+    /// Returns only contracts expiring in the given month.
+    pub fn exp_month(mut self, exp_month: Month) -> Self {
+        self.exp_month = Some(exp_month);
+        self
+    }
+
+    /// Sets whether standard or non-standard contracts are returned.
+    pub fn option_type(mut self, option_type: OptionType) -> Self {
+        self.option_type = Some(option_type);
+        self
+    }
+
+    /// Sets the market data entitlement of the client.
+    pub fn entitlement(mut self, entitlement: Entitlement) -> Self {
+        self.entitlement = Some(entitlement);
+        self
+    }
+
+    /// Returns the symbol the chain is requested for.
+    pub(crate) fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Narrows the request to the contracts in `range` that expire on `date`, clearing every other
+    /// strike and expiration filter.
+    pub(crate) fn expiration_window(mut self, date: NaiveDate, range: StrikeRange) -> Self {
+        let day = date.and_time(NaiveTime::MIN).and_utc();
+        self.strike_count = None;
+        self.strike = None;
+        self.exp_month = None;
+        self.range = Some(range);
+        self.from_date = Some(day);
+        self.to_date = Some(day);
+        self
+    }
+
+    /// Checks the parameters Schwab would otherwise answer with an empty or misleading chain.
+    pub(crate) fn validate(&self) -> Result<(), SchwabError> {
+        if self.strike_count == Some(0) {
+            return Err(SchwabError::InvalidInput(
+                "Invalid chains request: strikeCount must be at least 1; leave it unset to return every strike"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the query parameters for the set fields.
+    pub(crate) fn to_params(&self) -> Vec<(String, String)> {
+        parse_params(vec![
+            ("symbol", Some(self.symbol.clone())),
+            ("contractType", self.contract_type.map(|c| c.to_string())),
+            ("strikeCount", self.strike_count.map(|c| c.to_string())),
+            (
+                "includeUnderlyingQuote",
+                self.include_underlying_quote.map(|b| b.to_string()),
+            ),
+            ("strategy", self.strategy.map(|s| s.to_string())),
+            ("interval", self.interval.map(|i| i.to_string())),
+            ("strike", self.strike.map(|s| s.to_string())),
+            ("range", self.range.map(|r| r.to_string())),
+            ("fromDate", time_to_yyyymmdd(self.from_date)),
+            ("toDate", time_to_yyyymmdd(self.to_date)),
+            ("volatility", self.volatility.map(|v| v.to_string())),
+            (
+                "underlyingPrice",
+                self.underlying_price.map(|p| p.to_string()),
+            ),
+            ("interestRate", self.interest_rate.map(|r| r.to_string())),
+            (
+                "daysToExpiration",
+                self.days_to_expiration.map(|d| d.to_string()),
+            ),
+            (
+                "expMonth",
+                self.exp_month.map(|m| m.name()[..3].to_uppercase()),
+            ),
+            ("optionType", self.option_type.map(|o| o.to_string())),
+            ("entitlement", self.entitlement.map(|e| e.to_string())),
+        ])
+    }
+}
+
+/// A validated set of parameters for Schwab's `/pricehistory` endpoint.
+///
+/// Created with [`PriceHistoryRequest::builder`]. Use with `SchwabApi::price_history_with`.
+#[derive(Debug, Clone)]
+pub struct PriceHistoryRequest {
+    symbol: String,
+    period_type: Option<PeriodType>,
+    period: Option<u64>,
+    frequency_type: Option<FrequencyType>,
+    frequency: Option<u64>,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+    need_extended_hours_data: Option<bool>,
+    need_previous_close: Option<bool>,
+}
+
+impl PriceHistoryRequest {
+    /// Starts building a price history request for `symbol`.
+    pub fn builder(symbol: impl Into<String>) -> PriceHistoryRequestBuilder {
+        PriceHistoryRequestBuilder {
+            request: PriceHistoryRequest {
+                symbol: symbol.into(),
+                period_type: None,
+                period: None,
+                frequency_type: None,
+                frequency: None,
+                start_date: None,
+                end_date: None,
+                need_extended_hours_data: None,
+                need_previous_close: None,
+            },
+        }
+    }
+
+    /// Returns the query parameters for the set fields.
+    pub(crate) fn to_params(&self) -> Vec<(String, String)> {
+        parse_params(vec![
+            ("symbol", Some(self.symbol.clone())),
+            ("periodType", self.period_type.map(|p| p.to_string())),
+            ("period", self.period.map(|p| p.to_string())),
+            ("frequencyType", self.frequency_type.map(|f| f.to_string())),
+            ("frequency", self.frequency.map(|f| f.to_string())),
+            ("startDate", time_to_epoch_ms(self.start_date)),
+            ("endDate", time_to_epoch_ms(self.end_date)),
+            (
+                "needExtendedHoursData",
+                self.need_extended_hours_data.map(|b| b.to_string()),
+            ),
+            (
+                "needPreviousClose",
+                self.need_previous_close.map(|b| b.to_string()),
+            ),
+        ])
+    }
+}
+
+/// Builds a [`PriceHistoryRequest`], rejecting combinations Schwab does not accept.
+///
+/// Schwab allows the following, with unset values taking the defaults in bold:
+///
+/// | `period_type` | `period`                      | `frequency_type`                   |
+/// |---------------|-------------------------------|------------------------------------|
+/// | **day**       | 1, 2, 3, 4, 5, **10**         | **minute**                         |
+/// | month         | **1**, 2, 3, 6                | daily, **weekly**                  |
+/// | year          | **1**, 2, 3, 5, 10, 15, 20    | daily, weekly, **monthly**         |
+/// | ytd           | **1**                         | daily, **weekly**                  |
+///
+/// `frequency` may be 1, 5, 10, 15 or 30 for minute bars and must be 1 otherwise.
+#[derive(Debug, Clone)]
+pub struct PriceHistoryRequestBuilder {
+    request: PriceHistoryRequest,
+}
+
+impl PriceHistoryRequestBuilder {
+    /// Sets the unit of `period`. Defaults to `PeriodType::Day`.
+    pub fn period_type(mut self, period_type: PeriodType) -> Self {
+        self.request.period_type = Some(period_type);
+        self
+    }
+
+    /// Sets the number of `period_type` units to return.
+    pub fn period(mut self, period: u64) -> Self {
+        self.request.period = Some(period);
+        self
+    }
+
+    /// Sets the unit of `frequency`.
+    pub fn frequency_type(mut self, frequency_type: FrequencyType) -> Self {
+        self.request.frequency_type = Some(frequency_type);
+        self
+    }
+
+    /// Sets the number of `frequency_type` units in each candle. Defaults to 1.
+    pub fn frequency(mut self, frequency: u64) -> Self {
+        self.request.frequency = Some(frequency);
+        self
+    }
+
+    /// Sets the start of the range. Takes precedence over `period`.
+    pub fn start_date(mut self, start_date: DateTime<Utc>) -> Self {
+        self.request.start_date = Some(start_date);
+        self
+    }
+
+    /// Sets the end of the range. Defaults to the previous business day's close.
+    pub fn end_date(mut self, end_date: DateTime<Utc>) -> Self {
+        self.request.end_date = Some(end_date);
+        self
+    }
+
+    /// Sets whether to include pre- and post-market candles.
+    pub fn need_extended_hours_data(mut self, need_extended_hours_data: bool) -> Self {
+        self.request.need_extended_hours_data = Some(need_extended_hours_data);
+        self
+    }
+
+    /// Sets whether to include the previous close price and date.
+    pub fn need_previous_close(mut self, need_previous_close: bool) -> Self {
+        self.request.need_previous_close = Some(need_previous_close);
+        self
+    }
+
+    /// Validates the parameters and returns the request.
+    ///
+    /// Returns `SchwabError::InvalidInput` describing the first invalid parameter.
+    pub fn build(self) -> Result<PriceHistoryRequest, SchwabError> {
+        let request = self.request;
+        let period_type = request.period_type.unwrap_or(PeriodType::Day);
+
+        let (periods, frequency_types): (&[u64], &[FrequencyType]) = match period_type {
+            PeriodType::Day => (&[1, 2, 3, 4, 5, 10], &[FrequencyType::Minute]),
+            PeriodType::Month => (
+                &[1, 2, 3, 6],
+                &[FrequencyType::Daily, FrequencyType::Weekly],
+            ),
+            PeriodType::Year => (
+                &[1, 2, 3, 5, 10, 15, 20],
+                &[
+                    FrequencyType::Daily,
+                    FrequencyType::Weekly,
+                    FrequencyType::Monthly,
+                ],
+            ),
+            PeriodType::Ytd => (&[1], &[FrequencyType::Daily, FrequencyType::Weekly]),
+        };
+
+        if let Some(period) = request.period
+            && !periods.contains(&period)
+        {
+            return Err(invalid(format!(
+                "period {} is not allowed for periodType {}; expected one of {}",
+                period,
+                period_type,
+                join(periods)
+            )));
+        }
+
+        // Without an explicit frequency type, Schwab uses the last one listed for the period type.
+        let frequency_type = match request.frequency_type {
+            Some(frequency_type) if !frequency_types.contains(&frequency_type) => {
+                return Err(invalid(format!(
+                    "frequencyType {} is not allowed for periodType {}; expected one of {}",
+                    frequency_type,
+                    period_type,
+                    join(frequency_types)
+                )));
+            }
+            Some(frequency_type) => frequency_type,
+            None => frequency_types[frequency_types.len() - 1],
+        };
+
+        let frequencies: &[u64] = match frequency_type {
+            FrequencyType::Minute => &[1, 5, 10, 15, 30],
+            _ => &[1],
+        };
+        if let Some(frequency) = request.frequency
+            && !frequencies.contains(&frequency)
+        {
+            return Err(invalid(format!(
+                "frequency {} is not allowed for frequencyType {}; expected one of {}",
+                frequency,
+                frequency_type,
+                join(frequencies)
+            )));
+        }
+
+        if let (Some(start_date), Some(end_date)) = (request.start_date, request.end_date)
+            && end_date < start_date
+        {
+            return Err(invalid(format!(
+                "endDate {} is before startDate {}",
+                end_date, start_date
+            )));
+        }
+
+        Ok(request)
+    }
+}
+
+fn invalid(message: String) -> SchwabError {
+    SchwabError::InvalidInput(format!("Invalid price history request: {}", message))
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// The type of an order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderType {
+    Market,
+    Limit,
+    /// A multi-leg order paying at most `price`.
+    NetDebit,
+    /// A multi-leg order receiving at least `price`.
+    NetCredit,
+}
+
+/// The trading session an order is eligible for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderSession {
+    /// The regular session.
+    Normal,
+    /// The pre-market session.
+    Am,
+    /// The post-market session.
+    Pm,
+    /// Every session, including extended hours.
+    Seamless,
+}
+
+/// How long an order stays working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderDuration {
+    Day,
+    GoodTillCancel,
+    FillOrKill,
+    ImmediateOrCancel,
+}
+
+/// The side of an order leg.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Instruction {
+    Buy,
+    Sell,
+    SellShort,
+    BuyToCover,
+    BuyToOpen,
+    SellToOpen,
+    BuyToClose,
+    SellToClose,
+}
+
+/// The asset type of an order leg.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderAssetType {
+    Equity,
+    Option,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderLegRequest {
+    instruction: Instruction,
+    quantity: f64,
+    instrument: OrderInstrumentRequest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderInstrumentRequest {
+    symbol: String,
+    asset_type: OrderAssetType,
+}
+
+/// A validated order, ready for `SchwabApi::place_order`.
+///
+/// Created through one of the shortcuts, such as [`OrderRequest::equity_limit`], or
+/// [`OrderRequest::builder`] for anything else.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderRequest {
+    order_type: OrderType,
+    session: OrderSession,
+    duration: OrderDuration,
+    order_strategy_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    complex_order_strategy_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price: Option<f64>,
+    order_leg_collection: Vec<OrderLegRequest>,
+}
+
+impl OrderRequest {
+    /// Starts building a market order with no legs, for the regular session, good for the day.
+    pub fn builder() -> OrderRequestBuilder {
+        OrderRequestBuilder {
+            order_type: OrderType::Market,
+            session: OrderSession::Normal,
+            duration: OrderDuration::Day,
+            price: None,
+            vertical: false,
+            legs: Vec::new(),
+        }
+    }
+
+    /// Starts building a market order for `quantity` shares of `symbol`.
+    pub fn equity_market(
+        symbol: impl Into<String>,
+        instruction: Instruction,
+        quantity: f64,
+    ) -> OrderRequestBuilder {
+        Self::builder().leg(instruction, OrderAssetType::Equity, symbol, quantity)
+    }
+
+    /// Starts building a limit order for `quantity` shares of `symbol` at `price`.
+    pub fn equity_limit(
+        symbol: impl Into<String>,
+        instruction: Instruction,
+        quantity: f64,
+        price: f64,
+    ) -> OrderRequestBuilder {
+        Self::equity_market(symbol, instruction, quantity).limit(price)
+    }
+
+    /// Starts building a limit order for `quantity` contracts of the option `symbol` at `price`.
+    pub fn option_limit(
+        symbol: impl Into<String>,
+        instruction: Instruction,
+        quantity: f64,
+        price: f64,
+    ) -> OrderRequestBuilder {
+        Self::builder()
+            .leg(instruction, OrderAssetType::Option, symbol, quantity)
+            .limit(price)
+    }
+
+    /// Starts building a vertical spread that buys `buy_symbol` and sells `sell_symbol`.
+    ///
+    /// With `opening` set the legs are `BUY_TO_OPEN` and `SELL_TO_OPEN`, otherwise `BUY_TO_CLOSE`
+    /// and `SELL_TO_CLOSE`. Finish with [`OrderRequestBuilder::net_debit`] or
+    /// [`OrderRequestBuilder::net_credit`].
+    pub fn vertical_spread(
+        buy_symbol: impl Into<String>,
+        sell_symbol: impl Into<String>,
+        quantity: f64,
+        opening: bool,
+    ) -> OrderRequestBuilder {
+        let (buy, sell) = if opening {
+            (Instruction::BuyToOpen, Instruction::SellToOpen)
+        } else {
+            (Instruction::BuyToClose, Instruction::SellToClose)
+        };
+
+        let mut builder = Self::builder()
+            .leg(buy, OrderAssetType::Option, buy_symbol, quantity)
+            .leg(sell, OrderAssetType::Option, sell_symbol, quantity);
+        builder.vertical = true;
+        builder
+    }
+}
+
+/// Builds an [`OrderRequest`], rejecting orders Schwab would refuse before anything is sent.
+#[derive(Debug, Clone)]
+pub struct OrderRequestBuilder {
+    order_type: OrderType,
+    session: OrderSession,
+    duration: OrderDuration,
+    price: Option<f64>,
+    vertical: bool,
+    legs: Vec<OrderLegRequest>,
+}
+
+impl OrderRequestBuilder {
+    /// Sets the order type. Prefer [`OrderRequestBuilder::limit`] and friends, which also set
+    /// the price.
+    pub fn order_type(mut self, order_type: OrderType) -> Self {
+        self.order_type = order_type;
+        self
+    }
+
+    /// Sets the limit price, or the net price of a multi-leg order.
+    pub fn price(mut self, price: f64) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    /// Makes this a limit order at `price`.
+    pub fn limit(self, price: f64) -> Self {
+        self.order_type(OrderType::Limit).price(price)
+    }
+
+    /// Makes this a multi-leg order paying at most `price`.
+    pub fn net_debit(self, price: f64) -> Self {
+        self.order_type(OrderType::NetDebit).price(price)
+    }
+
+    /// Makes this a multi-leg order receiving at least `price`.
+    pub fn net_credit(self, price: f64) -> Self {
+        self.order_type(OrderType::NetCredit).price(price)
+    }
+
+    /// Sets the trading session. Defaults to `OrderSession::Normal`.
+    pub fn session(mut self, session: OrderSession) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Sets how long the order stays working. Defaults to `OrderDuration::Day`.
+    pub fn duration(mut self, duration: OrderDuration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Adds a leg for `quantity` units of `symbol`.
+    pub fn leg(
+        mut self,
+        instruction: Instruction,
+        asset_type: OrderAssetType,
+        symbol: impl Into<String>,
+        quantity: f64,
+    ) -> Self {
+        self.legs.push(OrderLegRequest {
+            instruction,
+            quantity,
+            instrument: OrderInstrumentRequest {
+                symbol: symbol.into(),
+                asset_type,
+            },
+        });
+        self
+    }
+
+    /// Validates the order and returns it.
+    ///
+    /// Returns `SchwabError::InvalidInput` if the order has no legs, a leg has no positive
+    /// quantity, a priced order type has no positive price, or a market order has a price.
+    pub fn build(self) -> Result<OrderRequest, SchwabError> {
+        if self.legs.is_empty() {
+            return Err(invalid_order("an order needs at least one leg"));
+        }
+        if let Some(leg) = self
+            .legs
+            .iter()
+            .find(|leg| !(leg.quantity.is_finite() && leg.quantity > 0.0))
+        {
+            return Err(invalid_order(&format!(
+                "leg {} has quantity {}, expected a positive quantity",
+                leg.instrument.symbol, leg.quantity
+            )));
+        }
+
+        match (self.order_type, self.price) {
+            (OrderType::Market, Some(_)) => {
+                return Err(invalid_order("a MARKET order cannot have a price"));
+            }
+            (OrderType::Market, None) => {}
+            (order_type, None) => {
+                return Err(invalid_order(&format!(
+                    "a {:?} order needs a price",
+                    order_type
+                )));
+            }
+            (order_type, Some(price)) if !(price.is_finite() && price > 0.0) => {
+                return Err(invalid_order(&format!(
+                    "a {:?} order needs a positive price, got {}",
+                    order_type, price
+                )));
+            }
+            _ => {}
+        }
+
+        let multi_leg = matches!(self.order_type, OrderType::NetDebit | OrderType::NetCredit);
+        if multi_leg && self.legs.len() < 2 {
+            return Err(invalid_order(
+                "NET_DEBIT and NET_CREDIT orders need at least two legs",
+            ));
+        }
+        if self.vertical
+            && (self.legs.len() != 2
+                || self
+                    .legs
+                    .iter()
+                    .any(|leg| leg.instrument.asset_type != OrderAssetType::Option))
+        {
+            return Err(invalid_order(
+                "a vertical spread needs exactly two option legs",
+            ));
+        }
+
+        Ok(OrderRequest {
+            order_type: self.order_type,
+            session: self.session,
+            duration: self.duration,
+            order_strategy_type: "SINGLE",
+            complex_order_strategy_type: self.vertical.then_some("VERTICAL"),
+            price: self.price,
+            order_leg_collection: self.legs,
+        })
+    }
+}
+
+fn invalid_order(message: &str) -> SchwabError {
+    SchwabError::InvalidInput(format!("Invalid order: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn rejection(builder: PriceHistoryRequestBuilder) -> String {
+        match builder.build() {
+            Err(SchwabError::InvalidInput(message)) => message,
+            other => panic!("expected InvalidInput, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn price_history_accepts_every_allowed_combination() {
+        let matrix: [(PeriodType, &[u64], &[FrequencyType]); 4] = [
+            (
+                PeriodType::Day,
+                &[1, 2, 3, 4, 5, 10],
+                &[FrequencyType::Minute],
+            ),
+            (
+                PeriodType::Month,
+                &[1, 2, 3, 6],
+                &[FrequencyType::Daily, FrequencyType::Weekly],
+            ),
+            (
+                PeriodType::Year,
+                &[1, 2, 3, 5, 10, 15, 20],
+                &[
+                    FrequencyType::Daily,
+                    FrequencyType::Weekly,
+                    FrequencyType::Monthly,
+                ],
+            ),
+            (
+                PeriodType::Ytd,
+                &[1],
+                &[FrequencyType::Daily, FrequencyType::Weekly],
+            ),
+        ];
+
+        for (period_type, periods, frequency_types) in matrix {
+            for &period in periods {
+                for &frequency_type in frequency_types {
+                    let frequencies: &[u64] = match frequency_type {
+                        FrequencyType::Minute => &[1, 5, 10, 15, 30],
+                        _ => &[1],
+                    };
+                    for &frequency in frequencies {
+                        let request = PriceHistoryRequest::builder("AAPL")
+                            .period_type(period_type)
+                            .period(period)
+                            .frequency_type(frequency_type)
+                            .frequency(frequency)
+                            .build()
+                            .unwrap_or_else(|e| {
+                                panic!(
+                                    "{} {} {} {} was rejected: {}",
+                                    period_type, period, frequency_type, frequency, e
+                                )
+                            });
+
+                        assert_eq!(
+                            request.to_params()[1..],
+                            [
+                                ("periodType".to_string(), period_type.to_string()),
+                                ("period".to_string(), period.to_string()),
+                                ("frequencyType".to_string(), frequency_type.to_string()),
+                                ("frequency".to_string(), frequency.to_string()),
+                            ]
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn price_history_leaves_unset_parameters_to_schwab() {
+        let request = PriceHistoryRequest::builder("AAPL").build().unwrap();
+
+        assert_eq!(
+            request.to_params(),
+            [("symbol".to_string(), "AAPL".to_string())]
+        );
+    }
+
+    #[test]
+    fn price_history_rejects_disallowed_combinations() {
+        let message = rejection(
+            PriceHistoryRequest::builder("AAPL")
+                .period_type(PeriodType::Day)
+                .frequency_type(FrequencyType::Weekly),
+        );
+        assert_eq!(
+            message,
+            "Invalid price history request: frequencyType weekly is not allowed for periodType \
+             day; expected one of minute"
+        );
+
+        let message = rejection(
+            PriceHistoryRequest::builder("AAPL")
+                .period_type(PeriodType::Month)
+                .period(5),
+        );
+        assert_eq!(
+            message,
+            "Invalid price history request: period 5 is not allowed for periodType month; \
+             expected one of 1, 2, 3, 6"
+        );
+
+        // Without a period type, Schwab assumes `day`, which only takes minute candles.
+        rejection(PriceHistoryRequest::builder("AAPL").frequency_type(FrequencyType::Daily));
+        rejection(
+            PriceHistoryRequest::builder("AAPL")
+                .period_type(PeriodType::Ytd)
+                .period(2),
+        );
+        rejection(
+            PriceHistoryRequest::builder("AAPL")
+                .frequency_type(FrequencyType::Minute)
+                .frequency(2),
+        );
+        rejection(
+            PriceHistoryRequest::builder("AAPL")
+                .period_type(PeriodType::Year)
+                .frequency(5),
+        );
+    }
+
+    #[test]
+    fn price_history_rejects_an_end_before_the_start() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 3, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+
+        let message = rejection(
+            PriceHistoryRequest::builder("AAPL")
+                .start_date(start)
+                .end_date(end),
+        );
+
+        assert!(message.contains("is before startDate"), "{}", message);
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use reqwest::{
+    StatusCode,
+    header::{HeaderMap, RETRY_AFTER},
+};
+
+/// Controls how `SchwabApi` retries GET requests that fail with HTTP 429 or a 5xx status.
+///
+/// Delays grow exponentially from `base_delay`, capped at `max_delay`, with random jitter so
+/// that concurrent clients do not retry in lockstep. A `Retry-After` header on a 429 response
+/// takes precedence over the computed delay. Non-idempotent requests are never retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// The maximum number of retries after the initial attempt. `0` disables retrying.
+    pub max_retries: u32,
+    /// The delay before the first retry, doubled on every subsequent retry.
+    pub base_delay: Duration,
+    /// The upper bound for any single delay, including one requested through `Retry-After`.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Returns a `RetryConfig` that never retries.
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` if a response with the given status is worth retrying.
+    pub(crate) fn is_retryable(status: StatusCode) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+    }
+
+    /// Computes the delay before the given retry (starting at `0`), preferring `retry_after` when
+    /// the server provided one.
+    pub(crate) fn delay_for(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_delay);
+        }
+
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+
+        // Equal jitter: keep half of the delay and randomize the other half.
+        let half = exponential / 2;
+        half + half.mul_f64(random_unit())
+    }
+}
+
+/// Parses the `Retry-After` header, which Schwab sends as a number of seconds.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Returns a pseudo-random number in `[0, 1)`, good enough for spreading out retries.
+fn random_unit() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
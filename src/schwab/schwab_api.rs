@@ -1,27 +1,61 @@
-use std::{env, fmt, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    env, fmt,
+    sync::Arc,
+    time::Duration,
+};
 
-use chrono::{DateTime, Utc};
-use reqwest::{header::HeaderMap, Client, RequestBuilder, Response, StatusCode};
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
+use futures_util::{StreamExt, TryStreamExt, stream};
+use reqwest::{
+    Client, Method, Request, RequestBuilder, Response, StatusCode,
+    header::{
+        AUTHORIZATION, ETAG, HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+        LAST_MODIFIED, LOCATION, USER_AGENT,
+    },
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 use tokio::sync::Mutex;
-use tracing::info;
+use tracing::{debug, info, warn};
 use urlencoding::encode;
 
 use crate::{
     schwab::{
-        common::{SCHWAB_MARKET_DATA_API_URL, SCHWAB_TRADER_API_URL, TOKENS_FILE},
+        cache::{ResponseCache, cache_key},
+        common::{
+            CHAIN_EXPIRATION_CONCURRENCY, DEFAULT_QUOTE_CHUNK_CONCURRENCY,
+            DEFAULT_QUOTE_CHUNK_SIZE, DEFAULT_REQUEST_TIMEOUT, DEFAULT_USER_AGENT,
+            MAX_MINUTE_HISTORY_DAYS, MAX_ORDER_LOOKBACK_DAYS, MAX_TRANSACTION_RANGE_DAYS,
+            ORDER_POLL_INTERVAL, TOKENS_FILE, default_http_client,
+        },
+        endpoints::SchwabEndpoints,
+        error::{SchwabApiError, SchwabError},
         models::{
             market_data::{
-                ChainsResponse, ExpirationChainResponse, InstrumentsResponse, MarketHours,
-                MarketHoursResponse, MoversResponse, PriceHistoryResponse, QuotesResponse,
+                ChainsResponse, ExpirationChainResponse, GapInfo, InstrumentsPage,
+                InstrumentsResponse, MarketHours, MarketHoursResponse, MarketSnapshot, MoversPage,
+                MoversResponse, PriceHistoryResponse, QuoteData, QuoteErrors, QuotesPage,
+                QuotesResponse,
+            },
+            parsed::Parsed,
+            trader::{
+                Account, AccountNumberMapping, Execution, Order, OrderStatus, Transaction,
+                TransactionType, UserPreferencesResponse,
             },
-            trader::UserPreferencesResponse,
         },
-        schwab_auth::{SchwabAuth, StoredTokenInfo},
+        rate_limit::RateLimiter,
+        requests::{ChainsRequest, OrderRequest, PriceHistoryRequest, StrikeRange},
+        retry::{self, RetryConfig},
+        schwab_auth::{SchwabAuth, Scope, StoredTokenInfo},
+        token_store::{FileTokenStore, TokenStore},
+        transport::HttpTransport,
     },
-    util::{dedup_ordered, parse_params, time_to_epoch_ms, time_to_yyyymmdd},
+    util::{dedup_ordered, new_york_day_bounds, parse_params, time_to_iso8601, time_to_yyyymmdd},
 };
 
 /// Represents the type of contract for an options chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ContractType {
     /// Call options.
     Call,
@@ -54,6 +88,29 @@ pub enum QuoteFields {
     Reference,
     /// Regular data.
     Regular,
+    /// Every section. Takes precedence over any other field in the same request.
+    All,
+}
+
+impl QuoteFields {
+    /// Builds the `fields` parameter of a quotes request. An empty list leaves it to Schwab's
+    /// default.
+    fn to_param(fields: Vec<QuoteFields>) -> Option<String> {
+        if fields.contains(&QuoteFields::All) {
+            return Some(QuoteFields::All.to_string());
+        }
+        match fields.as_slice() {
+            [] => None,
+            [field] => Some(field.to_string()),
+            _ => Some(
+                dedup_ordered(fields)
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ),
+        }
+    }
 }
 
 impl fmt::Display for QuoteFields {
@@ -64,12 +121,13 @@ impl fmt::Display for QuoteFields {
             QuoteFields::Extended => write!(f, "extended"),
             QuoteFields::Reference => write!(f, "reference"),
             QuoteFields::Regular => write!(f, "regular"),
+            QuoteFields::All => write!(f, "all"),
         }
     }
 }
 
 /// Represents the period type for price history.
-#[derive(Eq, PartialEq, Hash, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 pub enum PeriodType {
     /// Day period type.
     Day,
@@ -93,7 +151,7 @@ impl fmt::Display for PeriodType {
 }
 
 /// Represents the frequency type for price history.
-#[derive(Eq, PartialEq, Hash, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 pub enum FrequencyType {
     /// Minute frequency type.
     Minute,
@@ -140,6 +198,51 @@ impl fmt::Display for Sort {
     }
 }
 
+/// Represents the index or market whose movers are requested.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+pub enum MoverIndex {
+    /// The Dow Jones Industrial Average (`$DJI`).
+    Dji,
+    /// The NASDAQ Composite (`$COMPX`).
+    Compx,
+    /// The S&P 500 (`$SPX`).
+    Spx,
+    /// Equities listed on the NYSE.
+    Nyse,
+    /// Equities listed on NASDAQ.
+    Nasdaq,
+    /// Equities traded over the counter.
+    Otcbb,
+    /// Every index.
+    IndexAll,
+    /// Every equity.
+    EquityAll,
+    /// Every option.
+    OptionAll,
+    /// Every put option.
+    OptionPut,
+    /// Every call option.
+    OptionCall,
+}
+
+impl fmt::Display for MoverIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoverIndex::Dji => write!(f, "$DJI"),
+            MoverIndex::Compx => write!(f, "$COMPX"),
+            MoverIndex::Spx => write!(f, "$SPX"),
+            MoverIndex::Nyse => write!(f, "NYSE"),
+            MoverIndex::Nasdaq => write!(f, "NASDAQ"),
+            MoverIndex::Otcbb => write!(f, "OTCBB"),
+            MoverIndex::IndexAll => write!(f, "INDEX_ALL"),
+            MoverIndex::EquityAll => write!(f, "EQUITY_ALL"),
+            MoverIndex::OptionAll => write!(f, "OPTION_ALL"),
+            MoverIndex::OptionPut => write!(f, "OPTION_PUT"),
+            MoverIndex::OptionCall => write!(f, "OPTION_CALL"),
+        }
+    }
+}
+
 /// Represents the projection type for instruments.
 #[derive(Eq, PartialEq, Hash, Clone)]
 pub enum Projection {
@@ -197,15 +300,306 @@ impl fmt::Display for MarketSymbol {
     }
 }
 
+/// Represents the optional sections of an account response.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+pub enum AccountField {
+    /// The positions held in the account.
+    Positions,
+}
+
+impl fmt::Display for AccountField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountField::Positions => write!(f, "positions"),
+        }
+    }
+}
+
+/// Identifies an account for the trader endpoints.
+///
+/// Schwab addresses accounts by an encrypted hash rather than the account number shown to users,
+/// and answers a plain number with HTTP 404. A `Number` is resolved to its hash through the
+/// `accountNumbers` endpoint; the mapping is cached for the lifetime of the `SchwabApi`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AccountId {
+    /// The encrypted account hash, used as-is.
+    Hash(String),
+    /// The plain account number, resolved to its hash before use.
+    Number(String),
+}
+
+/// A market hours response kept for conditional requests, with the validators Schwab sent for it.
+#[derive(Debug, Clone)]
+struct ValidatedResponse {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    body: String,
+}
+
 /// A client for interacting with the Schwab API, with automatic token refreshing.
+///
+/// `SchwabApi` is `Clone`, `Send` and `Sync`. Clones are cheap and share the HTTP client, the
+/// tokens, the rate limiter, the account hash, preferences and market hours caches and the
+/// response cache, so independent calls can be fanned out with `tokio::join!`,
+/// `futures::future::join_all` or `tokio::spawn` on clones of one client.
 #[derive(Debug, Clone)]
 pub struct SchwabApi {
     reqwest_client: Arc<Client>,
+    transport: Arc<dyn HttpTransport>,
     app_key: String,
     app_secret: String,
-    tokens_file_path: String,
+    token_store: Arc<dyn TokenStore>,
     auth: SchwabAuth,
     token_info: Arc<Mutex<StoredTokenInfo>>,
+    retry_config: RetryConfig,
+    rate_limiter: Option<RateLimiter>,
+    account_hashes: Arc<Mutex<HashMap<String, String>>>,
+    preferences: Arc<Mutex<Option<UserPreferencesResponse>>>,
+    quote_chunk_size: usize,
+    quote_chunk_concurrency: usize,
+    endpoints: SchwabEndpoints,
+    timeout: Duration,
+    cache: Option<Arc<dyn ResponseCache>>,
+    default_headers: HeaderMap,
+    conditional_market_hours: bool,
+    /// Market hours responses that carried an `ETag` or `Last-Modified`, keyed by `cache_key`.
+    market_hours_validated: Arc<Mutex<HashMap<String, ValidatedResponse>>>,
+}
+
+/// A builder for `SchwabApi` that collects every configurable option in one place, created with
+/// `SchwabApi::builder()`.
+///
+/// Any option that is not set keeps the same default as `SchwabApi::default()`. The `with_*`
+/// methods on `SchwabApi` change the same options on an existing client.
+#[derive(Debug, Default)]
+pub struct SchwabApiBuilder {
+    app_key: Option<String>,
+    app_secret: Option<String>,
+    token_store: Option<Arc<dyn TokenStore>>,
+    reqwest_client: Option<Arc<Client>>,
+    transport: Option<Arc<dyn HttpTransport>>,
+    retry_config: Option<RetryConfig>,
+    rate_limit: Option<u32>,
+    quote_chunk_size: Option<usize>,
+    quote_chunk_concurrency: Option<usize>,
+    endpoints: Option<SchwabEndpoints>,
+    timeout: Option<Duration>,
+    cache: Option<Arc<dyn ResponseCache>>,
+    default_headers: Option<HeaderMap>,
+    conditional_market_hours: bool,
+}
+
+impl SchwabApiBuilder {
+    /// Creates a new builder with every option left at its default.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the Schwab application key (Client ID).
+    ///
+    /// Default: the `SCHWAB_APP_KEY` environment variable.
+    pub fn app_key(mut self, app_key: impl Into<String>) -> Self {
+        self.app_key = Some(app_key.into());
+        self
+    }
+
+    /// Sets the Schwab application secret (Client Secret).
+    ///
+    /// Default: the `SCHWAB_APP_SECRET` environment variable.
+    pub fn app_secret(mut self, app_secret: impl Into<String>) -> Self {
+        self.app_secret = Some(app_secret.into());
+        self
+    }
+
+    /// Sets the store tokens are loaded from and refreshed tokens are written to.
+    ///
+    /// Default: a `FileTokenStore` at `tokens.json`.
+    pub fn token_store(mut self, token_store: Arc<dyn TokenStore>) -> Self {
+        self.token_store = Some(token_store);
+        self
+    }
+
+    /// Uses a `FileTokenStore` at the given path. Shorthand for `token_store`.
+    ///
+    /// Default: `tokens.json`.
+    pub fn tokens_file_path(self, tokens_file_path: impl Into<String>) -> Self {
+        self.token_store(Arc::new(FileTokenStore::new(tokens_file_path.into())))
+    }
+
+    /// Sets the `reqwest::Client` used for every HTTP request, including token refreshes.
+    ///
+    /// Default: a client with reqwest's default settings on the enabled TLS backend, `native-tls`
+    /// when both `native-tls` and `rustls-tls` are enabled.
+    pub fn reqwest_client(mut self, reqwest_client: Arc<Client>) -> Self {
+        self.reqwest_client = Some(reqwest_client);
+        self
+    }
+
+    /// Sets the transport every API request and token refresh is sent through, e.g. a
+    /// `MockTransport` in tests. The `reqwest::Client` is still used to build the requests.
+    ///
+    /// Default: the `reqwest::Client`.
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Sets the retry policy for GET requests that fail with HTTP 429 or a 5xx status.
+    ///
+    /// Default: `RetryConfig::default()`, which retries up to 3 times.
+    pub fn retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = Some(retry_config);
+        self
+    }
+
+    /// Limits outbound API requests to `requests_per_minute`, delaying requests that would exceed it.
+    ///
+    /// Default: no client-side limit.
+    pub fn rate_limit(mut self, requests_per_minute: u32) -> Self {
+        self.rate_limit = Some(requests_per_minute);
+        self
+    }
+
+    /// Sets the maximum number of symbols sent in a single `/quotes` request. Larger symbol lists
+    /// passed to `get_quotes` are split into chunks of this size.
+    ///
+    /// Default: 250.
+    pub fn quote_chunk_size(mut self, quote_chunk_size: usize) -> Self {
+        self.quote_chunk_size = Some(quote_chunk_size);
+        self
+    }
+
+    /// Sets how many chunk requests of a long `get_quotes` symbol list may be in flight at once.
+    /// A value of `0` is treated as `1`.
+    ///
+    /// Default: 4.
+    pub fn quote_chunk_concurrency(mut self, quote_chunk_concurrency: usize) -> Self {
+        self.quote_chunk_concurrency = Some(quote_chunk_concurrency);
+        self
+    }
+
+    /// Sets the URLs requests are sent to, e.g. to run against a mock server. Token refreshes
+    /// and any `SchwabStreamer` created from the client use them as well.
+    ///
+    /// Default: `SchwabEndpoints::default()`, Schwab's production endpoints.
+    pub fn endpoints(mut self, endpoints: SchwabEndpoints) -> Self {
+        self.endpoints = Some(endpoints);
+        self
+    }
+
+    /// Sets how long a single HTTP request may take before it fails with `SchwabError::Timeout`.
+    /// Each retry of a request gets the full timeout again.
+    ///
+    /// Default: 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Answers market data requests from `cache` when it holds a fresh response, e.g. an
+    /// `InMemoryCache` during development or a pre-seeded `FileCache` for backtests.
+    ///
+    /// Default: no cache.
+    pub fn cache(mut self, cache: Arc<dyn ResponseCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Adds `headers` to every API request, e.g. a custom `User-Agent` or a correlation id
+    /// required by an egress proxy. An `Authorization` header is ignored, since the client
+    /// always sends its own.
+    ///
+    /// Token requests are sent through the `reqwest::Client` directly; configure headers they
+    /// need on the client itself.
+    ///
+    /// Default: only `User-Agent: brokerage-api/<version>`.
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers = Some(headers);
+        self
+    }
+
+    /// Revalidates market hours instead of downloading them again.
+    ///
+    /// When Schwab answers `market_hours` or `market_hour` with an `ETag` or `Last-Modified`
+    /// header, the response is kept and the next request for the same markets and date sends
+    /// `If-None-Match` or `If-Modified-Since`. A `304 Not Modified` is then answered from the
+    /// kept response. Without either header, requests behave as usual. Takes precedence over
+    /// the response cache for these two endpoints.
+    ///
+    /// Default: `false`.
+    pub fn conditional_market_hours(mut self, enabled: bool) -> Self {
+        self.conditional_market_hours = enabled;
+        self
+    }
+
+    /// Builds the `SchwabApi`, loading the current tokens from the token store.
+    pub async fn build(self) -> Result<SchwabApi, SchwabError> {
+        let app_key = match self.app_key {
+            Some(app_key) => app_key,
+            None => env::var("SCHWAB_APP_KEY").map_err(|_| {
+                SchwabError::Config("SCHWAB_APP_KEY environment variable not set".to_string())
+            })?,
+        };
+        let app_secret = match self.app_secret {
+            Some(app_secret) => app_secret,
+            None => env::var("SCHWAB_APP_SECRET").map_err(|_| {
+                SchwabError::Config("SCHWAB_APP_SECRET environment variable not set".to_string())
+            })?,
+        };
+        let token_store = self
+            .token_store
+            .unwrap_or_else(|| Arc::new(FileTokenStore::new(TOKENS_FILE)));
+        let reqwest_client = self
+            .reqwest_client
+            .unwrap_or_else(|| Arc::new(default_http_client()));
+
+        let endpoints = self.endpoints.unwrap_or_default();
+
+        let transport = self
+            .transport
+            .unwrap_or_else(|| reqwest_client.clone() as Arc<dyn HttpTransport>);
+
+        let auth = SchwabAuth::with_token_store(reqwest_client.clone(), token_store.clone())
+            .with_endpoints(endpoints.clone())
+            .with_transport(transport.clone());
+
+        let token_info = token_store.load().await?.ok_or_else(|| {
+            SchwabError::Config(
+                "No tokens found in token store. Run SchwabAuth::authorize first.".to_string(),
+            )
+        })?;
+
+        Ok(SchwabApi {
+            reqwest_client,
+            transport,
+            app_key,
+            app_secret,
+            token_store,
+            auth,
+            token_info: Arc::new(Mutex::new(token_info)),
+            retry_config: self.retry_config.unwrap_or_default(),
+            rate_limiter: self
+                .rate_limit
+                .filter(|&requests_per_minute| requests_per_minute > 0)
+                .map(RateLimiter::new),
+            account_hashes: Arc::new(Mutex::new(HashMap::new())),
+            preferences: Arc::new(Mutex::new(None)),
+            quote_chunk_size: self
+                .quote_chunk_size
+                .unwrap_or(DEFAULT_QUOTE_CHUNK_SIZE)
+                .max(1),
+            quote_chunk_concurrency: self
+                .quote_chunk_concurrency
+                .unwrap_or(DEFAULT_QUOTE_CHUNK_CONCURRENCY)
+                .max(1),
+            endpoints,
+            timeout: self.timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT),
+            cache: self.cache,
+            default_headers: request_default_headers(self.default_headers.unwrap_or_default()),
+            conditional_market_hours: self.conditional_market_hours,
+            market_hours_validated: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
 }
 
 impl SchwabApi {
@@ -219,55 +613,190 @@ impl SchwabApi {
         app_key: String,
         app_secret: String,
         tokens_file_path: String,
-    ) -> anyhow::Result<Self> {
-        let reqwest_client = Arc::new(Client::new());
-        let auth = SchwabAuth::new(reqwest_client.clone(), tokens_file_path.clone());
-
-        let json_string = tokio::fs::read_to_string(&tokens_file_path).await?;
-        let token_info: StoredTokenInfo = serde_json::from_str(&json_string)?;
-
-        Ok(Self {
-            reqwest_client,
+    ) -> Result<Self, SchwabError> {
+        Self::with_token_store(
             app_key,
             app_secret,
-            tokens_file_path,
-            auth,
-            token_info: Arc::new(Mutex::new(token_info)),
-        })
+            Arc::new(FileTokenStore::new(tokens_file_path)),
+        )
+        .await
+    }
+
+    /// Creates a new `SchwabApi` instance that reads and persists tokens through a custom `TokenStore`.
+    ///
+    /// # Arguments
+    /// * `app_key` - Your Schwab application key (Client ID).
+    /// * `app_secret` - Your Schwab application secret (Client Secret).
+    /// * `token_store` - The store holding the tokens obtained through `SchwabAuth::authorize`.
+    pub async fn with_token_store(
+        app_key: String,
+        app_secret: String,
+        token_store: Arc<dyn TokenStore>,
+    ) -> Result<Self, SchwabError> {
+        SchwabApiBuilder::new()
+            .app_key(app_key)
+            .app_secret(app_secret)
+            .token_store(token_store)
+            .build()
+            .await
+    }
+
+    /// Returns a `SchwabApiBuilder` for configuring a `SchwabApi` with non-default options.
+    pub fn builder() -> SchwabApiBuilder {
+        SchwabApiBuilder::new()
+    }
+
+    /// Replaces the retry policy used for GET requests that fail with HTTP 429 or a 5xx status.
+    pub fn with_retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = retry_config;
+        self
+    }
+
+    /// Limits outbound API requests to `requests_per_minute`, shared by every clone of this client.
+    ///
+    /// Requests over the limit are delayed rather than rejected. Schwab throttles market data at
+    /// roughly 120 requests per minute per token. A limit of `0` removes the client-side limit.
+    pub fn with_rate_limit(mut self, requests_per_minute: u32) -> Self {
+        self.rate_limiter =
+            (requests_per_minute > 0).then(|| RateLimiter::new(requests_per_minute));
+        self
+    }
+
+    /// Sets the maximum number of symbols sent in a single `/quotes` request.
+    pub fn with_quote_chunk_size(mut self, quote_chunk_size: usize) -> Self {
+        self.quote_chunk_size = quote_chunk_size.max(1);
+        self
+    }
+
+    /// Sets how many `/quotes` chunk requests may be in flight at once.
+    pub fn with_quote_chunk_concurrency(mut self, quote_chunk_concurrency: usize) -> Self {
+        self.quote_chunk_concurrency = quote_chunk_concurrency.max(1);
+        self
+    }
+
+    /// Sets how long a single HTTP request may take before it fails with `SchwabError::Timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Answers market data requests from `cache` when it holds a fresh response. Trader
+    /// endpoints always go to the network.
+    pub fn with_cache(mut self, cache: Arc<dyn ResponseCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Replaces the extra headers added to every API request. An `Authorization` header in
+    /// `headers` is ignored, and a `User-Agent` is added unless `headers` sets one.
+    pub fn with_default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers = request_default_headers(headers);
+        self
+    }
+
+    /// Sets whether market hours are revalidated with conditional requests. See
+    /// `SchwabApiBuilder::conditional_market_hours`.
+    pub fn with_conditional_market_hours(mut self, enabled: bool) -> Self {
+        self.conditional_market_hours = enabled;
+        self
+    }
+
+    /// Replaces the transport every API request and token refresh is sent through.
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.auth = self.auth.with_transport(transport.clone());
+        self.transport = transport;
+        self
+    }
+
+    /// Replaces the URLs requests, token refreshes and streamer connections are sent to.
+    pub fn with_endpoints(mut self, endpoints: SchwabEndpoints) -> Self {
+        self.auth = self.auth.with_endpoints(endpoints.clone());
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Returns the URLs this client sends requests to.
+    pub fn endpoints(&self) -> &SchwabEndpoints {
+        &self.endpoints
     }
 
     /// Creates a new `SchwabApi` instance with default settings, loading credentials from environment variables.
     /// It expects `SCHWAB_APP_KEY` and `SCHWAB_APP_SECRET` to be set.
-    pub async fn default() -> anyhow::Result<Self> {
-        let app_key = env::var("SCHWAB_APP_KEY")
-            .map_err(|_| anyhow::anyhow!("SCHWAB_APP_KEY environment variable not set"))?;
-        let app_secret = env::var("SCHWAB_APP_SECRET")
-            .map_err(|_| anyhow::anyhow!("SCHWAB_APP_SECRET environment variable not set"))?;
-        Self::new(app_key, app_secret, TOKENS_FILE.to_owned()).await
+    ///
+    /// Unlike `Default::default`, this is async and fallible because it reads the tokens from
+    /// `tokens.json` in the working directory. It is equivalent to `SchwabApi::builder().build()`.
+    ///
+    /// # Errors
+    /// * `SchwabError::Config` if either environment variable is missing, or if no tokens file
+    ///   exists yet.
+    /// * `SchwabError::CorruptTokenFile` if the tokens file cannot be parsed.
+    pub async fn default() -> Result<Self, SchwabError> {
+        SchwabApiBuilder::new().build().await
     }
 
-    /// Centralized request sender that handles authentication and token refreshing.
-    async fn send_request(&self, mut builder: RequestBuilder) -> anyhow::Result<Response> {
-        // Sign the request with the current access token from memory
-        let headers = self.construct_request_headers().await?;
-        builder = builder.headers(headers);
+    /// Centralized request sender that handles authentication, token refreshing and retries.
+    ///
+    /// If the request is rejected with HTTP 401, the access token is refreshed once and the request
+    /// is retried with the new token. A second 401 is returned as an error rather than retried again.
+    ///
+    /// GET requests that fail with HTTP 429 or a 5xx status are retried according to the
+    /// `RetryConfig`. Once the retries are exhausted the last response is returned, and
+    /// `parse_response` turns it into the matching error.
+    async fn send_request(&self, builder: RequestBuilder) -> Result<Response, SchwabError> {
+        self.send_built(builder.build()?).await
+    }
 
-        // Clone the request before sending, so we can retry it if the token is expired
-        let retry_builder = builder
-            .try_clone()
-            .ok_or_else(|| anyhow::anyhow!("Failed to clone request for potential retry"))?;
+    /// Sends an already built request. See [`SchwabApi::send_request`].
+    async fn send_built(&self, request: Request) -> Result<Response, SchwabError> {
+        let is_idempotent = request.method() == Method::GET;
+
+        let mut attempt = 0;
+        loop {
+            let response = self.send_authorized(&request).await?;
+            let status = response.status();
+
+            if !is_idempotent
+                || !RetryConfig::is_retryable(status)
+                || attempt >= self.retry_config.max_retries
+            {
+                return Ok(response);
+            }
+
+            let retry_after = if status == StatusCode::TOO_MANY_REQUESTS {
+                retry::retry_after(response.headers())
+            } else {
+                None
+            };
+            let delay = self.retry_config.delay_for(attempt, retry_after);
+            attempt += 1;
+            warn!(
+                "Request to {} failed with {}. Retrying in {:?} (attempt {} of {}).",
+                request.url().path(),
+                status,
+                delay,
+                attempt,
+                self.retry_config.max_retries
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
 
-        // Send the initial request
-        let response = builder.send().await?;
+    /// Sends a copy of `request` signed with the current access token, refreshing the token once
+    /// if the request is rejected with HTTP 401.
+    async fn send_authorized(&self, request: &Request) -> Result<Response, SchwabError> {
+        let response = self.execute_signed(request).await?;
 
         // Check if the token expired (401 Unauthorized)
         if response.status() == StatusCode::UNAUTHORIZED {
             info!("Token expired. Attempting to refresh...");
             self.refresh_and_store_token().await?;
 
-            // Re-sign the cloned request with the new token
-            let retry_headers = self.construct_request_headers().await?;
-            let response = retry_builder.headers(retry_headers).send().await?;
+            // Re-sign the request with the new token
+            let response = self.execute_signed(request).await?;
+
+            if response.status() == StatusCode::UNAUTHORIZED {
+                return Err(SchwabError::TokenExpired);
+            }
 
             info!("Request successful after token refresh.");
             return Ok(response);
@@ -276,8 +805,159 @@ impl SchwabApi {
         Ok(response)
     }
 
-    /// Refreshes the token, updates the in-memory store, and writes the new token to the file.
-    pub async fn refresh_and_store_token(&self) -> anyhow::Result<()> {
+    /// Clones `request`, signs it with the current access token from memory and sends it.
+    async fn execute_signed(&self, request: &Request) -> Result<Response, SchwabError> {
+        let mut request = request.try_clone().ok_or_else(|| {
+            SchwabError::Internal("Failed to clone request for potential retry".to_string())
+        })?;
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        request
+            .headers_mut()
+            .extend(self.construct_request_headers().await?);
+        *request.timeout_mut() = Some(self.timeout);
+        self.transport.execute(request).await.map_err(|e| match e {
+            SchwabError::Http(e) if e.is_timeout() => SchwabError::Timeout(self.timeout),
+            e => e,
+        })
+    }
+
+    /// Checks the response status and deserializes a successful response body into `T`.
+    ///
+    /// Non-success statuses are mapped to `SchwabError::Auth`, `SchwabError::RateLimited` or
+    /// `SchwabError::Api`. If the body of a successful response does not match `T`, the raw body is
+    /// returned in `SchwabError::Deserialize` to make schema drift easy to diagnose.
+    async fn parse_response<T: DeserializeOwned>(response: Response) -> Result<T, SchwabError> {
+        let raw = Self::ensure_success(response).await?.text().await?;
+        serde_json::from_str(&raw).map_err(|source| SchwabError::Deserialize { raw, source })
+    }
+
+    /// Sends a market data GET request and deserializes the response, going through the response
+    /// cache when one is configured.
+    ///
+    /// A cached body is returned without a network call. Only successful responses that
+    /// deserialize into `T` are written to the cache, so errors are always fetched again.
+    async fn get_market_data<T: DeserializeOwned>(
+        &self,
+        builder: RequestBuilder,
+    ) -> Result<T, SchwabError> {
+        let Some(cache) = &self.cache else {
+            let response = self.send_request(builder).await?;
+            return Self::parse_response(response).await;
+        };
+
+        let request = builder.build()?;
+        let key = cache_key(request.url());
+        match cache.get(&key).await {
+            Ok(Some(raw)) => {
+                debug!("Serving {} from the response cache", request.url().path());
+                return serde_json::from_str(&raw)
+                    .map_err(|source| SchwabError::Deserialize { raw, source });
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to read the response cache: {}", e),
+        }
+
+        let response = self.send_built(request).await?;
+        let raw = Self::ensure_success(response).await?.text().await?;
+        let value = match serde_json::from_str(&raw) {
+            Ok(value) => value,
+            Err(source) => return Err(SchwabError::Deserialize { raw, source }),
+        };
+        if let Err(e) = cache.put(&key, &raw).await {
+            warn!("Failed to write to the response cache: {}", e);
+        }
+        Ok(value)
+    }
+
+    /// Sends a market hours request, revalidating a kept response when conditional market hours
+    /// are enabled. Falls back to `get_market_data` otherwise.
+    async fn get_market_hours_data<T: DeserializeOwned>(
+        &self,
+        builder: RequestBuilder,
+    ) -> Result<T, SchwabError> {
+        if !self.conditional_market_hours {
+            return self.get_market_data(builder).await;
+        }
+
+        let mut request = builder.build()?;
+        let key = cache_key(request.url());
+        let kept = self.market_hours_validated.lock().await.get(&key).cloned();
+        if let Some(kept) = &kept {
+            if let Some(etag) = &kept.etag {
+                request.headers_mut().insert(IF_NONE_MATCH, etag.clone());
+            }
+            if let Some(last_modified) = &kept.last_modified {
+                request
+                    .headers_mut()
+                    .insert(IF_MODIFIED_SINCE, last_modified.clone());
+            }
+        }
+
+        let response = self.send_built(request).await?;
+        if response.status() == StatusCode::NOT_MODIFIED
+            && let Some(kept) = kept
+        {
+            debug!("Market hours not modified, reusing the kept response");
+            let raw = kept.body;
+            return serde_json::from_str(&raw)
+                .map_err(|source| SchwabError::Deserialize { raw, source });
+        }
+
+        let response = Self::ensure_success(response).await?;
+        let etag = response.headers().get(ETAG).cloned();
+        let last_modified = response.headers().get(LAST_MODIFIED).cloned();
+        let raw = response.text().await?;
+        let value = match serde_json::from_str(&raw) {
+            Ok(value) => value,
+            Err(source) => return Err(SchwabError::Deserialize { raw, source }),
+        };
+        if etag.is_some() || last_modified.is_some() {
+            let validated = ValidatedResponse {
+                etag,
+                last_modified,
+                body: raw,
+            };
+            self.market_hours_validated
+                .lock()
+                .await
+                .insert(key, validated);
+        }
+        Ok(value)
+    }
+
+    /// Maps an unsuccessful response to the matching `SchwabError`, passing successful ones
+    /// through for endpoints that answer without a JSON body.
+    async fn ensure_success(response: Response) -> Result<Response, SchwabError> {
+        let status = response.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = retry::retry_after(response.headers());
+            return Err(SchwabError::RateLimited { retry_after });
+        }
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let raw = response.text().await?;
+
+        if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            return Err(SchwabError::Auth { status, body: raw });
+        }
+        Err(SchwabError::Api {
+            status,
+            error: SchwabApiError::parse(&raw),
+            body: raw,
+        })
+    }
+
+    /// Refreshes the token, updates the in-memory copy, and writes the new token to the token store.
+    ///
+    /// Fails with `SchwabError::RefreshTokenExpired` once the refresh token is no longer
+    /// accepted, which happens seven days after authorization. Any request that needs a refresh
+    /// fails the same way, so callers can match on it to prompt for `SchwabAuth::authorize`.
+    pub async fn refresh_and_store_token(&self) -> Result<(), SchwabError> {
         let refresh_token = {
             let token_data = self.token_info.lock().await;
             token_data.refresh_token.clone()
@@ -294,97 +974,82 @@ impl SchwabApi {
             *token_data = new_token_info.clone();
         }
 
-        // Persist the new token to the file for future sessions
-        let json_string = serde_json::to_string_pretty(&new_token_info)?;
-        tokio::fs::write(&self.tokens_file_path, json_string).await?;
+        // Persist the new token to the store for future sessions
+        self.token_store.save(&new_token_info).await?;
         info!("Successfully refreshed and stored new token.");
 
         Ok(())
     }
 
-    /// Constructs the request headers from the in-memory token.
-    async fn construct_request_headers(&self) -> anyhow::Result<HeaderMap> {
-        let mut headers = HeaderMap::new();
+    /// Checks that the current tokens were authorized with `scope`.
+    ///
+    /// Calls that modify the account check for `Scope::Trade` before sending anything, so a
+    /// read-only token fails with `SchwabError::InsufficientScope` instead of an opaque HTTP 403.
+    pub async fn ensure_scope(&self, scope: Scope) -> Result<(), SchwabError> {
+        let token_data = self.token_info.lock().await;
+        if token_data.has_scope(scope) {
+            Ok(())
+        } else {
+            Err(SchwabError::InsufficientScope {
+                required: scope,
+                granted: token_data.scope.clone(),
+            })
+        }
+    }
+
+    /// Constructs the request headers from the default headers and the in-memory token.
+    async fn construct_request_headers(&self) -> Result<HeaderMap, SchwabError> {
+        let mut headers = self.default_headers.clone();
 
         let token_data = self.token_info.lock().await;
         let auth_header = format!("Bearer {}", token_data.access_token);
-        headers.insert("Authorization", auth_header.parse()?);
+        headers.insert(
+            AUTHORIZATION,
+            auth_header.parse().map_err(|_| {
+                SchwabError::Config("Access token is not a valid header value".to_string())
+            })?,
+        );
 
         Ok(headers)
     }
 
-    pub async fn get_preferences(&self) -> anyhow::Result<UserPreferencesResponse> {
+    /// Fetches the user preferences, including the `StreamerInfo` needed to open a streamer
+    /// session.
+    ///
+    /// Always sends a request, and replaces the copy returned by
+    /// [`SchwabApi::get_user_preferences`].
+    pub async fn get_preferences(&self) -> Result<UserPreferencesResponse, SchwabError> {
         let builder = self
             .reqwest_client
-            .get(format!("{SCHWAB_TRADER_API_URL}/userPreference"));
-
-        let response = self.send_request(builder).await?;
-        response.json().await.map_err(Into::into)
-    }
-
-    pub async fn get_quotes(
-        &self,
-        symbols: Vec<String>,
-        fields: Option<Vec<QuoteFields>>,
-        indicative: Option<bool>,
-    ) -> anyhow::Result<QuotesResponse> {
-        let url = format!("{}/quotes", SCHWAB_MARKET_DATA_API_URL);
-
-        let params = parse_params(vec![
-            ("symbols", Some(symbols.join(","))),
-            (
-                "fields",
-                fields.map(|v| {
-                    dedup_ordered(v)
-                        .iter()
-                        .map(|f| f.to_string())
-                        .collect::<Vec<String>>()
-                        .join(",")
-                }),
-            ),
-            ("indicative", indicative.map(|v| v.to_string().to_lowercase())),
-        ]);
+            .get(format!("{}/userPreference", self.endpoints.trader));
 
-        let builder = self.reqwest_client.get(url).query(&params);
         let response = self.send_request(builder).await?;
-        response.json().await.map_err(Into::into)
+        let preferences: UserPreferencesResponse = Self::parse_response(response).await?;
+        *self.preferences.lock().await = Some(preferences.clone());
+        Ok(preferences)
     }
 
-    pub async fn get_chains(
-        &self,
-        symbol: String,
-        contract_type: ContractType,
-        strike_count: u64,
-        include_underlying_quote: bool,
-    ) -> anyhow::Result<ChainsResponse> {
-        let url = format!("{}/chains", SCHWAB_MARKET_DATA_API_URL);
-
-        let params = parse_params(vec![
-            ("symbol", Some(symbol)),
-            ("contractType", Some(contract_type.to_string())),
-            ("strikeCount", Some(strike_count.to_string())),
-            (
-                "includeUnderlyingQuote",
-                Some(include_underlying_quote.to_string()),
-            ),
-        ]);
-
-        let builder = self.reqwest_client.get(url).query(&params);
-        let response = self.send_request(builder).await?;
-        response.json().await.map_err(Into::into)
+    /// Returns the user preferences, fetching them only on the first call.
+    ///
+    /// The copy is shared by every clone of this client, so creating several `SchwabStreamer`s
+    /// from one client sends a single preferences request. Call [`SchwabApi::get_preferences`]
+    /// to refresh it.
+    pub async fn get_user_preferences(&self) -> Result<UserPreferencesResponse, SchwabError> {
+        if let Some(preferences) = self.preferences.lock().await.as_ref() {
+            return Ok(preferences.clone());
+        }
+        self.get_preferences().await
     }
 
-    pub async fn quote(
+    /// Returns the balances, and optionally the positions, of every account linked to the tokens.
+    ///
+    /// # Arguments
+    /// * `fields` - The optional sections to include. Pass `AccountField::Positions` for positions.
+    pub async fn get_accounts(
         &self,
-        symbol_id: String,
-        fields: Option<Vec<QuoteFields>>,
-    ) -> anyhow::Result<QuotesResponse> {
-        let url = format!(
-            "{}/{}/quotes",
-            SCHWAB_MARKET_DATA_API_URL,
-            encode(&symbol_id)
-        );
-
+        fields: Option<Vec<AccountField>>,
+    ) -> Result<Vec<Account>, SchwabError> {
+        let url = format!("{}/accounts", self.endpoints.trader);
         let params = parse_params(vec![(
             "fields",
             fields.map(|v| {
@@ -398,21 +1063,790 @@ impl SchwabApi {
 
         let builder = self.reqwest_client.get(url).query(&params);
         let response = self.send_request(builder).await?;
-        response.json().await.map_err(Into::into)
+        Self::parse_response(response).await
     }
 
-    pub async fn option_expiration_chain(
-        &self,
-        symbol: String,
-    ) -> anyhow::Result<ExpirationChainResponse> {
-        let url = format!("{}/expirationchain", SCHWAB_MARKET_DATA_API_URL);
-        let params = parse_params(vec![("symbol", Some(symbol))]);
+    /// Returns the plain account numbers linked to the tokens, paired with their encrypted hashes.
+    ///
+    /// Also refreshes the cache used to resolve `AccountId::Number`.
+    pub async fn get_account_numbers(&self) -> Result<Vec<AccountNumberMapping>, SchwabError> {
+        let builder = self
+            .reqwest_client
+            .get(format!("{}/accounts/accountNumbers", self.endpoints.trader));
 
-        let builder = self.reqwest_client.get(url).query(&params);
         let response = self.send_request(builder).await?;
-        response.json().await.map_err(Into::into)
+        let accounts: Vec<AccountNumberMapping> = Self::parse_response(response).await?;
+
+        let mut account_hashes = self.account_hashes.lock().await;
+        for account in &accounts {
+            account_hashes.insert(account.account_number.clone(), account.hash_value.clone());
+        }
+
+        Ok(accounts)
     }
 
+    /// Resolves `account` to the encrypted hash used in trader endpoint paths.
+    ///
+    /// An unknown account number triggers one `accountNumbers` lookup before failing with
+    /// `SchwabError::InvalidInput`.
+    pub async fn account_hash(&self, account: &AccountId) -> Result<String, SchwabError> {
+        let account_number = match account {
+            AccountId::Hash(hash) => return Ok(hash.clone()),
+            AccountId::Number(account_number) => account_number,
+        };
+
+        if let Some(hash) = self.account_hashes.lock().await.get(account_number) {
+            return Ok(hash.clone());
+        }
+
+        self.get_account_numbers()
+            .await?
+            .into_iter()
+            .find(|account| &account.account_number == account_number)
+            .map(|account| account.hash_value)
+            .ok_or_else(|| {
+                SchwabError::InvalidInput(format!(
+                    "Account number {} is not linked to these tokens",
+                    account_number
+                ))
+            })
+    }
+
+    /// Resolves a plain account number to the encrypted hash used in trader endpoint paths.
+    ///
+    /// Shorthand for `account_hash(&AccountId::Number(..))`.
+    pub async fn account_hash_for(&self, account_number: &str) -> Result<String, SchwabError> {
+        self.account_hash(&AccountId::Number(account_number.to_string()))
+            .await
+    }
+
+    /// Returns every fill for the account on `date`, flattened from the execution activity of
+    /// its orders.
+    ///
+    /// The day runs from midnight to midnight in New York, the exchanges' time zone, so it covers
+    /// the full pre- and post-market sessions. Fills are selected by their execution time, so
+    /// good-'til-canceled orders entered on an earlier day are included, looking back as far as
+    /// Schwab keeps order history (60 days). Partial fills of orders that were later canceled or
+    /// replaced are included as well.
+    ///
+    /// # Arguments
+    /// * `account` - The account, by hash or plain account number.
+    /// * `date` - The trading day to export fills for.
+    pub async fn executions(
+        &self,
+        account: AccountId,
+        date: NaiveDate,
+    ) -> Result<Vec<Execution>, SchwabError> {
+        let (from, to) = new_york_day_bounds(date);
+        let lookback = chrono::Duration::days(MAX_ORDER_LOOKBACK_DAYS);
+        let entered_from = (from - lookback).max(Utc::now() - lookback).min(from);
+
+        let orders = self.get_orders(account, entered_from, to, None).await?;
+
+        let mut executions: Vec<Execution> = orders
+            .iter()
+            .flat_map(Order::executions)
+            .filter(|execution| execution.time >= from && execution.time < to)
+            .collect();
+        executions.sort_by_key(|execution| execution.time);
+        Ok(executions)
+    }
+
+    /// Places an order and returns its id.
+    ///
+    /// Requires tokens authorized with `Scope::Trade`. Orders are never retried, since a retried
+    /// request could place the order twice.
+    ///
+    /// # Arguments
+    /// * `account` - The account, by hash or plain account number.
+    /// * `order` - The order, built with `OrderRequest`.
+    pub async fn place_order(
+        &self,
+        account: AccountId,
+        order: OrderRequest,
+    ) -> Result<i64, SchwabError> {
+        let (status, order_id) = self.submit_order(&account, &order).await?;
+        order_id.ok_or_else(|| SchwabError::Api {
+            status,
+            body: "Order was accepted, but the response has no order id in its Location header"
+                .to_string(),
+            error: None,
+        })
+    }
+
+    /// Places an order, then polls it until it reaches a terminal status or `timeout` elapses.
+    ///
+    /// Returns the order as last fetched, so check `status` to tell a fill from a rejection, a
+    /// cancellation or a timeout. If the response to the placement carries no order id, the
+    /// most recent order entered for the account since just before the placement is watched
+    /// instead; with several orders placed at once, that may not be this one.
+    ///
+    /// Requires tokens authorized with `Scope::Trade`. The order is placed once and never
+    /// retried.
+    ///
+    /// # Arguments
+    /// * `account` - The account, by hash or plain account number.
+    /// * `order` - The order, built with `OrderRequest`.
+    /// * `timeout` - How long to wait for a terminal status.
+    pub async fn place_order_and_await_fill(
+        &self,
+        account: AccountId,
+        order: OrderRequest,
+        timeout: Duration,
+    ) -> Result<Order, SchwabError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        // Entered times are set by Schwab's clock, so allow for some skew.
+        let placed_after = Utc::now() - TimeDelta::minutes(1);
+
+        let (status, order_id) = self.submit_order(&account, &order).await?;
+        let order_id = match order_id {
+            Some(order_id) => order_id,
+            None => self
+                .get_orders(account.clone(), placed_after, Utc::now(), None)
+                .await?
+                .into_iter()
+                .max_by_key(|order| order.entered_time)
+                .map(|order| order.order_id)
+                .ok_or_else(|| SchwabError::Api {
+                    status,
+                    body: "Order was accepted, but it has no order id and was not found among \
+                           the account's recent orders"
+                        .to_string(),
+                    error: None,
+                })?,
+        };
+
+        loop {
+            let order = self.get_order(account.clone(), order_id).await?;
+            let now = tokio::time::Instant::now();
+            if order.status.is_terminal() || now >= deadline {
+                return Ok(order);
+            }
+            tokio::time::sleep_until(deadline.min(now + ORDER_POLL_INTERVAL)).await;
+        }
+    }
+
+    /// Sends an order and returns the response status along with the new order's id, if the
+    /// response carries one.
+    async fn submit_order(
+        &self,
+        account: &AccountId,
+        order: &OrderRequest,
+    ) -> Result<(StatusCode, Option<i64>), SchwabError> {
+        self.ensure_scope(Scope::Trade).await?;
+
+        let account_hash = self.account_hash(account).await?;
+        let url = format!("{}/accounts/{}/orders", self.endpoints.trader, account_hash);
+
+        let builder = self.reqwest_client.post(url).json(order);
+        let response = Self::ensure_success(self.send_request(builder).await?).await?;
+
+        // The new order is only identified by the URL in the `Location` header, which ends in
+        // the order id.
+        let order_id = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| location.trim_end_matches('/').rsplit('/').next())
+            .and_then(|order_id| order_id.parse().ok());
+        Ok((response.status(), order_id))
+    }
+
+    /// Fetches a single order by its id.
+    ///
+    /// # Arguments
+    /// * `account` - The account, by hash or plain account number.
+    /// * `order_id` - The id returned by `place_order` or found through `get_orders`.
+    pub async fn get_order(&self, account: AccountId, order_id: i64) -> Result<Order, SchwabError> {
+        let account_hash = self.account_hash(&account).await?;
+        let url = format!(
+            "{}/accounts/{}/orders/{}",
+            self.endpoints.trader, account_hash, order_id
+        );
+
+        let builder = self.reqwest_client.get(url);
+        let response = self.send_request(builder).await?;
+        Self::parse_response(response).await
+    }
+
+    /// Fetches the orders entered for an account between `from` and `to`.
+    ///
+    /// # Arguments
+    /// * `account` - The account, by hash or plain account number.
+    /// * `from` - The earliest entered time. Schwab looks back at most 60 days.
+    /// * `to` - The latest entered time.
+    /// * `status` - Only return orders with this status, filtered by Schwab.
+    pub async fn get_orders(
+        &self,
+        account: AccountId,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        status: Option<OrderStatus>,
+    ) -> Result<Vec<Order>, SchwabError> {
+        let account_hash = self.account_hash(&account).await?;
+        let url = format!("{}/accounts/{}/orders", self.endpoints.trader, account_hash);
+        let params = parse_params(vec![
+            ("fromEnteredTime", time_to_iso8601(Some(from))),
+            ("toEnteredTime", time_to_iso8601(Some(to))),
+            ("status", status.map(|s| s.to_string())),
+        ]);
+
+        let builder = self.reqwest_client.get(url).query(&params);
+        let response = self.send_request(builder).await?;
+        Self::parse_response(response).await
+    }
+
+    /// Cancels a working order.
+    ///
+    /// Requires tokens authorized with `Scope::Trade`. Schwab rejects the request if the order
+    /// has already been filled, canceled or has expired.
+    ///
+    /// # Arguments
+    /// * `account` - The account, by hash or plain account number.
+    /// * `order_id` - The id returned by `place_order` or found through `get_orders`.
+    pub async fn cancel_order(&self, account: AccountId, order_id: i64) -> Result<(), SchwabError> {
+        self.ensure_scope(Scope::Trade).await?;
+
+        let account_hash = self.account_hash(&account).await?;
+        let url = format!(
+            "{}/accounts/{}/orders/{}",
+            self.endpoints.trader, account_hash, order_id
+        );
+
+        let builder = self.reqwest_client.delete(url);
+        Self::ensure_success(self.send_request(builder).await?).await?;
+        Ok(())
+    }
+
+    /// Fetches the transactions of an account between `start` and `end`.
+    ///
+    /// Schwab limits the range to 60 days per request; wider ranges fail with
+    /// `SchwabError::InvalidInput` before any request is sent. Use
+    /// [`SchwabApi::get_transactions_range`] for longer ranges.
+    ///
+    /// # Arguments
+    /// * `account` - The account, by hash or plain account number.
+    /// * `start` - The start of the range.
+    /// * `end` - The end of the range.
+    /// * `types` - The transaction types to return. Defaults to every type.
+    /// * `symbol` - Only return transactions for this symbol.
+    pub async fn get_transactions(
+        &self,
+        account: AccountId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        types: Option<Vec<TransactionType>>,
+        symbol: Option<String>,
+    ) -> Result<Vec<Transaction>, SchwabError> {
+        if end < start {
+            return Err(SchwabError::InvalidInput(format!(
+                "Transaction range ends ({}) before it starts ({})",
+                end, start
+            )));
+        }
+        if end - start > TimeDelta::days(MAX_TRANSACTION_RANGE_DAYS) {
+            return Err(SchwabError::InvalidInput(format!(
+                "Transaction range from {} to {} exceeds Schwab's limit of {} days",
+                start, end, MAX_TRANSACTION_RANGE_DAYS
+            )));
+        }
+        let types = match types {
+            Some(types) if !types.is_empty() => dedup_ordered(types),
+            _ => TransactionType::ALL.to_vec(),
+        };
+
+        let account_hash = self.account_hash(&account).await?;
+        let url = format!(
+            "{}/accounts/{}/transactions",
+            self.endpoints.trader, account_hash
+        );
+        let params = parse_params(vec![
+            ("startDate", time_to_iso8601(Some(start))),
+            ("endDate", time_to_iso8601(Some(end))),
+            (
+                "types",
+                Some(
+                    types
+                        .iter()
+                        .map(|t| t.to_string())
+                        .collect::<Vec<String>>()
+                        .join(","),
+                ),
+            ),
+            ("symbol", symbol),
+        ]);
+
+        let builder = self.reqwest_client.get(url).query(&params);
+        let response = self.send_request(builder).await?;
+        Self::parse_response(response).await
+    }
+
+    /// Fetches the transactions of an account over a range of any length.
+    ///
+    /// The range is split into windows of at most 60 days, which are fetched one after another
+    /// through [`SchwabApi::get_transactions`], so the rate limit and retry policy apply to each.
+    /// Transactions returned by two adjacent windows are kept once, by `activity_id`, and the
+    /// result is sorted by time. If any window fails, the whole call fails with that error.
+    ///
+    /// # Arguments
+    /// * `account` - The account, by hash or plain account number.
+    /// * `start` - The start of the range.
+    /// * `end` - The end of the range.
+    /// * `types` - The transaction types to return. Defaults to every type.
+    /// * `symbol` - Only return transactions for this symbol.
+    pub async fn get_transactions_range(
+        &self,
+        account: AccountId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        types: Option<Vec<TransactionType>>,
+        symbol: Option<String>,
+    ) -> Result<Vec<Transaction>, SchwabError> {
+        if end < start {
+            return Err(SchwabError::InvalidInput(format!(
+                "Transaction range ends ({}) before it starts ({})",
+                end, start
+            )));
+        }
+        // Resolve the hash once instead of once per window.
+        let account = AccountId::Hash(self.account_hash(&account).await?);
+
+        let mut seen = HashSet::new();
+        let mut transactions = Vec::new();
+        let mut window_start = start;
+        loop {
+            let window_end = (window_start + TimeDelta::days(MAX_TRANSACTION_RANGE_DAYS)).min(end);
+            let window = self
+                .get_transactions(
+                    account.clone(),
+                    window_start,
+                    window_end,
+                    types.clone(),
+                    symbol.clone(),
+                )
+                .await?;
+            transactions.extend(
+                window
+                    .into_iter()
+                    .filter(|transaction| seen.insert(transaction.activity_id)),
+            );
+
+            if window_end >= end {
+                break;
+            }
+            window_start = window_end;
+        }
+
+        transactions.sort_by_key(|transaction| transaction.time);
+        Ok(transactions)
+    }
+
+    /// Fetches quotes for `symbols`, discarding the list of symbols Schwab could not resolve.
+    ///
+    /// See [`SchwabApi::get_quotes_with_errors`] for how large symbol lists are handled.
+    pub async fn get_quotes(
+        &self,
+        symbols: Vec<String>,
+        fields: Option<Vec<QuoteFields>>,
+        indicative: Option<bool>,
+    ) -> Result<QuotesResponse, SchwabError> {
+        let (quotes, errors) = self
+            .get_quotes_with_errors(symbols, fields, indicative)
+            .await?;
+        if !errors.is_empty() {
+            warn!("Schwab could not resolve some quote symbols: {:?}", errors);
+        }
+        Ok(quotes)
+    }
+
+    /// Fetches quotes for `symbols`, along with the symbols Schwab could not resolve.
+    ///
+    /// Symbol lists longer than the configured chunk size (250 by default, see
+    /// `SchwabApiBuilder::quote_chunk_size`) are split into several requests, at most four of
+    /// which are in flight at once unless changed with
+    /// `SchwabApiBuilder::quote_chunk_concurrency`. The responses are merged into one map, so no
+    /// ordering of symbols is preserved, and the rejected symbols of every chunk are collected
+    /// into one `QuoteErrors`. If any chunk fails outright, the whole call fails with that error.
+    ///
+    /// # Arguments
+    /// * `symbols` - The symbols to quote. Duplicates are requested once.
+    /// * `fields` - The quote sections to return. Defaults to all sections, as does
+    ///   `QuoteFields::All`. `None` or an empty list leaves the parameter out of the request.
+    /// * `indicative` - Whether to include indicative quotes for ETFs. `None` leaves the
+    ///   parameter out of the request rather than sending it blank.
+    pub async fn get_quotes_with_errors(
+        &self,
+        symbols: Vec<String>,
+        fields: Option<Vec<QuoteFields>>,
+        indicative: Option<bool>,
+    ) -> Result<(QuotesResponse, QuoteErrors), SchwabError> {
+        let fields = fields.and_then(QuoteFields::to_param);
+        let indicative = indicative.map(|v| v.to_string());
+
+        let symbols = dedup_ordered(symbols);
+        let pages: Vec<QuotesPage> = stream::iter(symbols.chunks(self.quote_chunk_size))
+            .map(|chunk| self.quotes_page(chunk, fields.clone(), indicative.clone()))
+            .buffer_unordered(self.quote_chunk_concurrency)
+            .try_collect()
+            .await?;
+
+        let mut quotes = QuotesResponse::with_capacity(symbols.len());
+        let mut errors = QuoteErrors::default();
+        for page in pages {
+            quotes.extend(page.quotes);
+            errors.extend(page.errors);
+        }
+        Ok((quotes, errors))
+    }
+
+    /// Fetches quotes for `symbols` like [`SchwabApi::get_quotes`], keeping the raw response
+    /// alongside the typed one.
+    ///
+    /// The chunks of a long symbol list are merged into one JSON object keyed by symbol, with
+    /// Schwab's `errors` entry left out. If any quote fails to deserialize, `typed` holds the
+    /// error and the quotes can still be read from `raw`.
+    pub async fn get_quotes_parsed(
+        &self,
+        symbols: Vec<String>,
+        fields: Option<Vec<QuoteFields>>,
+        indicative: Option<bool>,
+    ) -> Result<Parsed<QuotesResponse>, SchwabError> {
+        let fields = fields.and_then(QuoteFields::to_param);
+        let indicative = indicative.map(|v| v.to_string());
+
+        let symbols = dedup_ordered(symbols);
+        let pages: Vec<Value> = stream::iter(symbols.chunks(self.quote_chunk_size))
+            .map(|chunk| self.quotes_page(chunk, fields.clone(), indicative.clone()))
+            .buffer_unordered(self.quote_chunk_concurrency)
+            .try_collect()
+            .await?;
+
+        let mut quotes = serde_json::Map::new();
+        for page in pages {
+            if let Value::Object(mut page) = page {
+                page.remove("errors");
+                quotes.extend(page);
+            }
+        }
+        Ok(Parsed::from_value(Value::Object(quotes)))
+    }
+
+    /// Fetches quotes for `symbols` like [`SchwabApi::get_quotes`], deserializing each symbol on
+    /// its own so that one malformed quote does not fail the whole batch.
+    ///
+    /// Returns the quotes that deserialized, keyed by symbol, and the symbols that did not, each
+    /// with its deserialization error. Symbols Schwab could not resolve are logged and left out
+    /// of both, as in `get_quotes`.
+    pub async fn get_quotes_lenient(
+        &self,
+        symbols: Vec<String>,
+        fields: Option<Vec<QuoteFields>>,
+        indicative: Option<bool>,
+    ) -> Result<(QuotesResponse, Vec<(String, serde_json::Error)>), SchwabError> {
+        let fields = fields.and_then(QuoteFields::to_param);
+        let indicative = indicative.map(|v| v.to_string());
+
+        let symbols = dedup_ordered(symbols);
+        let pages: Vec<HashMap<String, Value>> =
+            stream::iter(symbols.chunks(self.quote_chunk_size))
+                .map(|chunk| self.quotes_page(chunk, fields.clone(), indicative.clone()))
+                .buffer_unordered(self.quote_chunk_concurrency)
+                .try_collect()
+                .await?;
+
+        let mut quotes = QuotesResponse::with_capacity(symbols.len());
+        let mut failures = Vec::new();
+        let mut errors = QuoteErrors::default();
+        for page in pages {
+            for (symbol, value) in page {
+                if symbol == "errors" {
+                    errors.extend(serde_json::from_value(value).unwrap_or_default());
+                    continue;
+                }
+                match serde_json::from_value(value) {
+                    Ok(quote) => {
+                        quotes.insert(symbol, quote);
+                    }
+                    Err(e) => failures.push((symbol, e)),
+                }
+            }
+        }
+        if !errors.is_empty() {
+            warn!("Schwab could not resolve some quote symbols: {:?}", errors);
+        }
+        Ok((quotes, failures))
+    }
+
+    /// Fetches a single `/quotes` response for at most one chunk of symbols.
+    async fn quotes_page<T: DeserializeOwned>(
+        &self,
+        symbols: &[String],
+        fields: Option<String>,
+        indicative: Option<String>,
+    ) -> Result<T, SchwabError> {
+        let url = format!("{}/quotes", self.endpoints.market_data);
+
+        let params = parse_params(vec![
+            ("symbols", Some(symbols.join(","))),
+            ("fields", fields),
+            ("indicative", indicative),
+        ]);
+
+        let builder = self.reqwest_client.get(url).query(&params);
+        self.get_market_data(builder).await
+    }
+
+    /// Returns the symbols whose security status is normal, dropping halted and closed symbols.
+    ///
+    /// Symbols Schwab cannot resolve, or whose quote carries no security status, are dropped as
+    /// well. The order of `symbols` is kept. A status can change between this check and the
+    /// placement of an order, so a symbol returned here may already be halted when the order
+    /// arrives.
+    pub async fn filter_tradable(&self, symbols: Vec<String>) -> Result<Vec<String>, SchwabError> {
+        let quotes = self
+            .get_quotes(symbols.clone(), Some(vec![QuoteFields::Quote]), None)
+            .await?;
+
+        Ok(dedup_ordered(symbols)
+            .into_iter()
+            .filter(|symbol| {
+                quotes
+                    .get(symbol)
+                    .and_then(|quote| quote.quote.as_ref())
+                    .and_then(QuoteData::status)
+                    .is_some_and(|status| status.is_tradable())
+            })
+            .collect())
+    }
+
+    /// Fetches the gap between the previous close and today's open for each symbol.
+    ///
+    /// Symbols without equity quote data are omitted from the result. Symbols that have not
+    /// opened yet are included with `open`, `gap_amount` and `gap_percent` set to `None`.
+    pub async fn gaps(
+        &self,
+        symbols: Vec<String>,
+    ) -> Result<HashMap<String, GapInfo>, SchwabError> {
+        let quotes = self
+            .get_quotes(symbols, Some(vec![QuoteFields::Quote]), None)
+            .await?;
+
+        Ok(quotes
+            .into_iter()
+            .filter_map(|(symbol, quote)| {
+                quote
+                    .equity_quote()
+                    .map(|equity_quote| (symbol, GapInfo::from_quote(equity_quote)))
+            })
+            .collect())
+    }
+
+    /// Fetches quotes for `symbols` and today's equity market hours concurrently.
+    ///
+    /// Fails if either request fails.
+    pub async fn snapshot(&self, symbols: Vec<String>) -> Result<MarketSnapshot, SchwabError> {
+        let (quotes, equity_hours) = tokio::join!(
+            self.get_quotes(symbols, None, None),
+            self.market_hour(MarketSymbol::Equity, None),
+        );
+
+        Ok(MarketSnapshot {
+            quotes: quotes?,
+            equity_hours: equity_hours?,
+        })
+    }
+
+    /// Fetches the options chain of `symbol`.
+    ///
+    /// # Arguments
+    /// * `symbol` - The underlying symbol.
+    /// * `contract_type` - Calls, puts or both.
+    /// * `strike_count` - The number of strikes above and below the at-the-money price. `None`
+    ///   returns every strike; `Some(0)` fails with `SchwabError::InvalidInput`.
+    /// * `include_underlying_quote` - Whether to include a quote for the underlying.
+    pub async fn get_chains(
+        &self,
+        symbol: String,
+        contract_type: ContractType,
+        strike_count: Option<u64>,
+        include_underlying_quote: bool,
+    ) -> Result<ChainsResponse, SchwabError> {
+        let mut request = ChainsRequest::new(symbol)
+            .contract_type(contract_type)
+            .include_underlying_quote(include_underlying_quote);
+        if let Some(strike_count) = strike_count {
+            request = request.strike_count(strike_count);
+        }
+        self.get_chains_with(request).await
+    }
+
+    /// Fetches an options chain using any combination of the parameters Schwab supports.
+    ///
+    /// # Arguments
+    /// * `request` - The chain parameters. Only the parameters that are set are sent. A
+    ///   `strike_count` of 0 fails with `SchwabError::InvalidInput` before any request is sent.
+    pub async fn get_chains_with(
+        &self,
+        request: ChainsRequest,
+    ) -> Result<ChainsResponse, SchwabError> {
+        request.validate()?;
+        let url = format!("{}/chains", self.endpoints.market_data);
+        let params = request.to_params();
+
+        let builder = self.reqwest_client.get(url).query(&params);
+        self.get_market_data(builder).await
+    }
+
+    /// Fetches a complete options chain in several smaller requests, for underlyings whose full
+    /// chain is too large to return reliably in one response.
+    ///
+    /// The listed expirations are fetched first with `SchwabApi::option_expiration_chain`. The
+    /// chain is then requested in fixed windows: for each expiration, one request with
+    /// `StrikeRange::InTheMoney` and one with `StrikeRange::OutOfTheMoney`. A chain with `n`
+    /// expirations therefore takes `2 * n + 1` requests where `get_chains_with` makes one. Up to
+    /// `CHAIN_EXPIRATION_CONCURRENCY` windows are in flight at once, each through the configured
+    /// rate limit. The windows are merged with `ChainsResponse::merge`, which drops contracts
+    /// returned by more than one window.
+    ///
+    /// The merged chain is checked against what was listed: every expiration must be present, and
+    /// where both calls and puts are returned for an expiration they must cover the same strikes.
+    /// A chain that fails either check is reported as `SchwabError::UnexpectedResponse` rather
+    /// than returned incomplete.
+    ///
+    /// # Arguments
+    /// * `request` - The chain parameters shared by every window. Any `strike`, `strike_count`,
+    ///   `range`, `from_date`, `to_date` or `exp_month` set on it is replaced.
+    pub async fn get_chains_by_strike_window(
+        &self,
+        request: ChainsRequest,
+    ) -> Result<ChainsResponse, SchwabError> {
+        request.validate()?;
+        let expirations = self
+            .option_expiration_chain(request.symbol().to_string())
+            .await?;
+        let dates = expirations
+            .expiration_list
+            .iter()
+            .map(|expiration| {
+                expiration
+                    .expiration()
+                    .map(|date_time| date_time.date_naive())
+                    .ok_or_else(|| {
+                        SchwabError::UnexpectedResponse(format!(
+                            "Unrecognized expiration date `{}` in the expiration chain",
+                            expiration.expiration_date
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<NaiveDate>, SchwabError>>()?;
+        let dates = dedup_ordered(dates);
+
+        let windows = dates.iter().flat_map(|&date| {
+            [StrikeRange::InTheMoney, StrikeRange::OutOfTheMoney]
+                .map(|range| request.clone().expiration_window(date, range))
+        });
+        let mut windows: Vec<ChainsResponse> = stream::iter(windows)
+            .map(|window| self.get_chains_with(window))
+            .buffered(CHAIN_EXPIRATION_CONCURRENCY)
+            .try_collect()
+            .await?;
+        if windows.is_empty() {
+            return self.get_chains_with(request).await;
+        }
+
+        let mut merged = windows.remove(0);
+        for window in windows {
+            merged.merge(window);
+        }
+        check_chain_coverage(&merged, &dates)?;
+        Ok(merged)
+    }
+
+    /// Fetches the options chain of each expiration date in `expirations` separately, with
+    /// several requests in flight at once.
+    ///
+    /// Each request is `request` narrowed to one date through `from_date` and `to_date`, so a
+    /// large chain arrives as several smaller responses instead of one. A failed expiration does
+    /// not affect the others: chains are returned by date in the first map and failures in the
+    /// second. Duplicate dates are requested once, and a date with no listed options yields an
+    /// empty chain.
+    ///
+    /// # Arguments
+    /// * `request` - The chain parameters shared by every expiration. Any `from_date` or
+    ///   `to_date` set on it is replaced.
+    /// * `expirations` - The expiration dates to fetch, e.g. from
+    ///   `SchwabApi::option_expiration_chain`.
+    pub async fn get_chains_per_expiration(
+        &self,
+        request: ChainsRequest,
+        expirations: Vec<NaiveDate>,
+    ) -> (
+        HashMap<NaiveDate, ChainsResponse>,
+        HashMap<NaiveDate, SchwabError>,
+    ) {
+        let results: Vec<(NaiveDate, Result<ChainsResponse, SchwabError>)> =
+            stream::iter(dedup_ordered(expirations))
+                .map(|date| {
+                    let day = date.and_time(NaiveTime::MIN).and_utc();
+                    let request = request.clone().from_date(day).to_date(day);
+                    async move { (date, self.get_chains_with(request).await) }
+                })
+                .buffer_unordered(CHAIN_EXPIRATION_CONCURRENCY)
+                .collect()
+                .await;
+
+        let mut chains = HashMap::new();
+        let mut errors = HashMap::new();
+        for (date, result) in results {
+            match result {
+                Ok(chain) => {
+                    chains.insert(date, chain);
+                }
+                Err(e) => {
+                    errors.insert(date, e);
+                }
+            }
+        }
+        (chains, errors)
+    }
+
+    pub async fn quote(
+        &self,
+        symbol_id: String,
+        fields: Option<Vec<QuoteFields>>,
+    ) -> Result<QuotesResponse, SchwabError> {
+        let url = format!(
+            "{}/{}/quotes",
+            self.endpoints.market_data,
+            encode(&symbol_id)
+        );
+
+        let params = parse_params(vec![("fields", fields.and_then(QuoteFields::to_param))]);
+
+        let builder = self.reqwest_client.get(url).query(&params);
+        self.get_market_data(builder).await
+    }
+
+    /// Fetches every option expiration date listed for `symbol`.
+    ///
+    /// Use [`ExpirationChainResponse::next_weekly`], [`ExpirationChainResponse::next_monthly`] and
+    /// [`ExpirationChainResponse::standard_expirations`] to pick expirations from the list.
+    pub async fn option_expiration_chain(
+        &self,
+        symbol: String,
+    ) -> Result<ExpirationChainResponse, SchwabError> {
+        let url = format!("{}/expirationchain", self.endpoints.market_data);
+        let params = parse_params(vec![("symbol", Some(symbol))]);
+
+        let builder = self.reqwest_client.get(url).query(&params);
+        self.get_market_data(builder).await
+    }
+
+    /// Fetches price history using positional parameters.
+    ///
+    /// The combination of parameters is checked with `PriceHistoryRequestBuilder::build` before
+    /// anything is sent. Prefer [`SchwabApi::price_history_with`] in new code.
+    #[allow(clippy::too_many_arguments)]
     pub async fn price_history(
         &self,
         symbol: String,
@@ -424,95 +1858,245 @@ impl SchwabApi {
         end_date: Option<DateTime<Utc>>,
         need_extended_hours_data: Option<bool>,
         need_previous_close: Option<bool>,
-    ) -> anyhow::Result<PriceHistoryResponse> {
-        let url = format!("{}/pricehistory", SCHWAB_MARKET_DATA_API_URL);
+    ) -> Result<PriceHistoryResponse, SchwabError> {
+        let mut builder = PriceHistoryRequest::builder(symbol);
+        if let Some(period_type) = period_type {
+            builder = builder.period_type(period_type);
+        }
+        if let Some(period) = period {
+            builder = builder.period(period);
+        }
+        if let Some(frequency_type) = frequency_type {
+            builder = builder.frequency_type(frequency_type);
+        }
+        if let Some(frequency) = frequency {
+            builder = builder.frequency(frequency);
+        }
+        if let Some(start_date) = start_date {
+            builder = builder.start_date(start_date);
+        }
+        if let Some(end_date) = end_date {
+            builder = builder.end_date(end_date);
+        }
+        if let Some(need_extended_hours_data) = need_extended_hours_data {
+            builder = builder.need_extended_hours_data(need_extended_hours_data);
+        }
+        if let Some(need_previous_close) = need_previous_close {
+            builder = builder.need_previous_close(need_previous_close);
+        }
 
-        let params = parse_params(vec![
-            ("symbol", Some(symbol)),
-            ("periodType", period_type.map(|p| p.to_string())),
-            ("period", period.map(|p| p.to_string())),
-            ("frequencyType", frequency_type.map(|f| f.to_string())),
-            ("frequency", frequency.map(|f| f.to_string())),
-            ("startDate", time_to_epoch_ms(start_date)),
-            ("endDate", time_to_epoch_ms(end_date)),
-            (
-                "needExtendedHoursData",
-                need_extended_hours_data.map(|b| b.to_string()),
-            ),
-            (
-                "needPreviousClose",
-                need_previous_close.map(|b| b.to_string()),
-            ),
-        ]);
+        self.price_history_with(builder.build()?).await
+    }
+
+    /// Fetches price history for a validated request.
+    ///
+    /// # Arguments
+    /// * `request` - The request, built with [`PriceHistoryRequest::builder`].
+    pub async fn price_history_with(
+        &self,
+        request: PriceHistoryRequest,
+    ) -> Result<PriceHistoryResponse, SchwabError> {
+        let url = format!("{}/pricehistory", self.endpoints.market_data);
+        let params = request.to_params();
 
         let builder = self.reqwest_client.get(url).query(&params);
-        let response = self.send_request(builder).await?;
-        response.json().await.map_err(Into::into)
+        self.get_market_data(builder).await
     }
 
-    pub async fn movers(
+    /// Fetches price history like [`SchwabApi::price_history_with`], keeping the raw response
+    /// alongside the typed one.
+    pub async fn price_history_parsed(
+        &self,
+        request: PriceHistoryRequest,
+    ) -> Result<Parsed<PriceHistoryResponse>, SchwabError> {
+        let url = format!("{}/pricehistory", self.endpoints.market_data);
+        let params = request.to_params();
+
+        let builder = self.reqwest_client.get(url).query(&params);
+        let raw: Value = self.get_market_data(builder).await?;
+        Ok(Parsed::from_value(raw))
+    }
+
+    /// Fetches one-unit candles of `frequency_type` between `start` and `end`, e.g. daily candles
+    /// from one date to another.
+    ///
+    /// The period type is chosen to match the frequency and no `period` is sent, so the range
+    /// alone decides which candles are returned. Use [`SchwabApi::price_history_with`] for
+    /// multi-minute candles or extended-hours data.
+    ///
+    /// # Arguments
+    /// * `symbol` - The symbol to fetch price history for.
+    /// * `start` - The start of the range.
+    /// * `end` - The end of the range. Must be after `start`, and for minute candles no more than
+    ///   48 days later.
+    /// * `frequency_type` - The size of each candle: one minute, day, week or month.
+    pub async fn price_history_range(
+        &self,
+        symbol: String,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        frequency_type: FrequencyType,
+    ) -> Result<PriceHistoryResponse, SchwabError> {
+        if end <= start {
+            return Err(SchwabError::InvalidInput(
+                "price_history_range requires `end` to be after `start`".to_string(),
+            ));
+        }
+        if frequency_type == FrequencyType::Minute
+            && end - start > TimeDelta::days(MAX_MINUTE_HISTORY_DAYS)
+        {
+            return Err(SchwabError::InvalidInput(format!(
+                "Minute candles are limited to {} days, but {} to {} spans {} days",
+                MAX_MINUTE_HISTORY_DAYS,
+                start,
+                end,
+                (end - start).num_days()
+            )));
+        }
+
+        // Minute candles are only valid with the `day` period type; `year` accepts every other one.
+        let period_type = match frequency_type {
+            FrequencyType::Minute => PeriodType::Day,
+            _ => PeriodType::Year,
+        };
+        let request = PriceHistoryRequest::builder(symbol)
+            .period_type(period_type)
+            .frequency_type(frequency_type)
+            .frequency(1)
+            .start_date(start)
+            .end_date(end)
+            .build()?;
+        self.price_history_with(request).await
+    }
+
+    /// Fetches roughly `approx_bars` candles between `start` and `end`, choosing the frequency
+    /// automatically.
+    ///
+    /// The candle size is picked from the combinations Schwab accepts (1, 5, 10, 15 or 30 minute
+    /// bars, or daily, weekly or monthly bars) as the one whose expected candle count for the range
+    /// is closest to `approx_bars`. Expected counts assume 252 trading days a year and 390 minutes
+    /// of regular-hours trading a day, so the actual number of candles can differ. Ranges longer
+    /// than 48 days never use minute candles, which Schwab does not return that far.
+    ///
+    /// # Arguments
+    /// * `symbol` - The symbol to fetch price history for.
+    /// * `start` - The start of the range.
+    /// * `end` - The end of the range. Must be after `start`.
+    /// * `approx_bars` - The desired number of candles.
+    pub async fn history_between(
         &self,
         symbol: String,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        approx_bars: u32,
+    ) -> Result<PriceHistoryResponse, SchwabError> {
+        if end <= start {
+            return Err(SchwabError::InvalidInput(
+                "history_between requires `end` to be after `start`".to_string(),
+            ));
+        }
+        if approx_bars == 0 {
+            return Err(SchwabError::InvalidInput(
+                "history_between requires `approx_bars` to be greater than zero".to_string(),
+            ));
+        }
+
+        let (period_type, frequency_type, frequency) =
+            select_history_frequency(end - start, approx_bars);
+
+        self.price_history(
+            symbol,
+            Some(period_type),
+            None,
+            Some(frequency_type),
+            Some(frequency),
+            Some(start),
+            Some(end),
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// Fetches the top movers of an index or market.
+    ///
+    /// # Arguments
+    /// * `index` - The index or market to rank.
+    /// * `sort` - How to rank the movers.
+    /// * `frequency` - Only include movers whose price changed by at least this many percent
+    ///   (0, 1, 5, 10, 30 or 60).
+    pub async fn movers(
+        &self,
+        index: MoverIndex,
         sort: Option<Sort>,
         frequency: Option<u64>,
-    ) -> anyhow::Result<MoversResponse> {
-        let url = format!("{}/movers/{}", SCHWAB_MARKET_DATA_API_URL, encode(&symbol));
+    ) -> Result<MoversResponse, SchwabError> {
+        let url = format!(
+            "{}/movers/{}",
+            self.endpoints.market_data,
+            encode(&index.to_string())
+        );
         let params = parse_params(vec![
             ("sort", sort.map(|s| s.to_string())),
             ("frequency", frequency.map(|f| f.to_string())),
         ]);
 
         let builder = self.reqwest_client.get(url).query(&params);
-        let response = self.send_request(builder).await?;
-        response.json().await.map_err(Into::into)
+        let page: MoversPage = self.get_market_data(builder).await?;
+        Ok(page.screeners)
     }
 
+    /// Fetches the hours of several markets on one day, keyed by market.
+    ///
+    /// # Arguments
+    /// * `symbols` - The markets to fetch.
+    /// * `date` - The day to fetch. Defaults to today; Schwab accepts dates up to a year ahead.
     pub async fn market_hours(
         &self,
         symbols: Vec<MarketSymbol>,
         date: Option<DateTime<Utc>>,
-    ) -> anyhow::Result<MarketHoursResponse> {
-        let url = format!("{}/markets", SCHWAB_MARKET_DATA_API_URL);
+    ) -> Result<MarketHoursResponse, SchwabError> {
+        let url = format!("{}/markets", self.endpoints.market_data);
 
-        let symbols_string = symbols
+        // Sorted so that the same set of markets always produces the same request.
+        let mut symbols = symbols
             .iter()
             .map(|s| s.to_string())
-            .collect::<Vec<String>>()
-            .join(",");
+            .collect::<Vec<String>>();
+        symbols.sort();
+        symbols.dedup();
 
         let params = parse_params(vec![
-            ("markets", Some(symbols_string)),
+            ("markets", Some(symbols.join(","))),
             ("date", time_to_yyyymmdd(date)),
         ]);
 
         let builder = self.reqwest_client.get(url).query(&params);
-        let response = self.send_request(builder).await?;
-        response.json().await.map_err(Into::into)
+        self.get_market_hours_data(builder).await
     }
 
+    /// Fetches the hours of a single market on one day. `date` defaults to today.
+    ///
+    /// Use [`MarketHours::is_open_now`] to check whether the market is currently in session.
     pub async fn market_hour(
         &self,
         market_id: MarketSymbol,
         date: Option<DateTime<Utc>>,
-    ) -> anyhow::Result<MarketHours> {
-        let url = format!(
-            "{}/markets/{}",
-            SCHWAB_MARKET_DATA_API_URL,
-            market_id.to_string()
-        );
+    ) -> Result<MarketHours, SchwabError> {
+        let url = format!("{}/markets/{}", self.endpoints.market_data, market_id);
 
         let params = parse_params(vec![("date", time_to_yyyymmdd(date))]);
 
         let builder = self.reqwest_client.get(url).query(&params);
-        let response = self.send_request(builder).await?;
-
         // The API wraps the single response in a map with the market name as the key.
         // We find the first value in the map and return it.
-        let response_map: MarketHoursResponse = response.json().await?;
-        let market_hours = response_map
-            .into_values()
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("Market hours response was empty"))?;
+        let response_map: MarketHoursResponse = self.get_market_hours_data(builder).await?;
+        let market_hours = response_map.into_values().next().ok_or_else(|| {
+            SchwabError::UnexpectedResponse(format!(
+                "Market hours response for {} was empty",
+                market_id
+            ))
+        })?;
         Ok(market_hours)
     }
 
@@ -520,8 +2104,8 @@ impl SchwabApi {
         &self,
         symbol: String,
         projection: Projection,
-    ) -> anyhow::Result<InstrumentsResponse> {
-        let url = format!("{}/instruments", SCHWAB_MARKET_DATA_API_URL);
+    ) -> Result<InstrumentsResponse, SchwabError> {
+        let url = format!("{}/instruments", self.endpoints.market_data);
 
         let params = parse_params(vec![
             ("symbol", Some(symbol)),
@@ -529,23 +2113,943 @@ impl SchwabApi {
         ]);
 
         let builder = self.reqwest_client.get(url).query(&params);
-        let response = self.send_request(builder).await?;
-        response.json().await.map_err(Into::into)
+        let page: InstrumentsPage = self.get_market_data(builder).await?;
+        Ok(page.instruments)
+    }
+
+    /// Looks up several symbols in one `/instruments` request, e.g. to resolve the CUSIPs of a
+    /// whole watchlist.
+    ///
+    /// Fails with `SchwabError::InvalidInput` if `symbols` is empty, or if `projection` is
+    /// `Projection::SymbolRegex` or `Projection::DescRegex`, which take a single pattern rather
+    /// than a list.
+    ///
+    /// # Arguments
+    /// * `symbols` - The symbols to look up. Duplicates are requested once.
+    /// * `projection` - The kind of search, e.g. `Projection::SymbolSearch`.
+    pub async fn instruments_bulk(
+        &self,
+        symbols: Vec<String>,
+        projection: Projection,
+    ) -> Result<InstrumentsResponse, SchwabError> {
+        if matches!(projection, Projection::SymbolRegex | Projection::DescRegex) {
+            return Err(SchwabError::InvalidInput(format!(
+                "The {} projection takes a single pattern, not a list of symbols",
+                projection
+            )));
+        }
+        if symbols.is_empty() {
+            return Err(SchwabError::InvalidInput(
+                "No symbols to look up".to_string(),
+            ));
+        }
+
+        self.instruments(dedup_ordered(symbols).join(","), projection)
+            .await
     }
 
-    pub async fn instrument_cusip(&self, cusip_id: String) -> anyhow::Result<InstrumentsResponse> {
+    pub async fn instrument_cusip(
+        &self,
+        cusip_id: String,
+    ) -> Result<InstrumentsResponse, SchwabError> {
         let url = format!(
             "{}/instruments/{}",
-            SCHWAB_MARKET_DATA_API_URL,
+            self.endpoints.market_data,
             encode(&cusip_id)
         );
 
         let builder = self.reqwest_client.get(url);
-        let response = self.send_request(builder).await?;
-        response.json().await.map_err(Into::into)
+        let page: InstrumentsPage = self.get_market_data(builder).await?;
+        Ok(page.instruments)
     }
 
-    pub(crate) async fn token_info(&self) -> StoredTokenInfo {
+    /// Returns a copy of the tokens the client currently signs requests with.
+    ///
+    /// The copy does not follow later refreshes; call this again after a request to see tokens
+    /// refreshed by it.
+    pub async fn token_info(&self) -> StoredTokenInfo {
         self.token_info.lock().await.clone()
     }
-}
\ No newline at end of file
+}
+
+/// Prepares user-supplied default headers: drops any `Authorization` header, which must always
+/// carry the current token, and adds the default `User-Agent` if none was given.
+fn request_default_headers(mut headers: HeaderMap) -> HeaderMap {
+    headers.remove(AUTHORIZATION);
+    if !headers.contains_key(USER_AGENT) {
+        headers.insert(USER_AGENT, HeaderValue::from_static(DEFAULT_USER_AGENT));
+    }
+    headers
+}
+
+/// Checks that `chain` lists every date in `expirations`, and that the calls and puts of each
+/// expiration returned on both sides cover the same strikes.
+fn check_chain_coverage(
+    chain: &ChainsResponse,
+    expirations: &[NaiveDate],
+) -> Result<(), SchwabError> {
+    let date_of = |key: &String| key.split(':').next().unwrap_or_default().to_string();
+
+    let listed: HashSet<String> = chain
+        .call_exp_date_map
+        .keys()
+        .chain(chain.put_exp_date_map.keys())
+        .map(date_of)
+        .collect();
+    let missing: Vec<String> = expirations
+        .iter()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .filter(|date| !listed.contains(date))
+        .collect();
+    if !missing.is_empty() {
+        return Err(SchwabError::UnexpectedResponse(format!(
+            "Chain windows returned no contracts for the listed expirations {}",
+            missing.join(", ")
+        )));
+    }
+
+    for (key, calls) in &chain.call_exp_date_map {
+        let Some(puts) = chain.put_exp_date_map.get(key) else {
+            continue;
+        };
+        let call_strikes: HashSet<&String> = calls.keys().collect();
+        let put_strikes: HashSet<&String> = puts.keys().collect();
+        if call_strikes != put_strikes {
+            let mut uncovered: Vec<&str> = call_strikes
+                .symmetric_difference(&put_strikes)
+                .map(|strike| strike.as_str())
+                .collect();
+            uncovered.sort();
+            return Err(SchwabError::UnexpectedResponse(format!(
+                "Chain windows for {} returned strikes {} for only one of calls and puts",
+                date_of(key),
+                uncovered.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Picks the price history frequency whose expected number of candles over `range` is closest to
+/// `approx_bars`, returned as a valid `(periodType, frequencyType, frequency)` combination. Minute
+/// candles are only considered when `range` fits within `MAX_MINUTE_HISTORY_DAYS`.
+fn select_history_frequency(
+    range: chrono::Duration,
+    approx_bars: u32,
+) -> (PeriodType, FrequencyType, u64) {
+    const TRADING_DAYS_PER_CALENDAR_DAY: f64 = 252.0 / 365.0;
+    const TRADING_MINUTES_PER_DAY: f64 = 390.0;
+
+    let calendar_days = range.num_seconds() as f64 / 86_400.0;
+    let trading_days = calendar_days * TRADING_DAYS_PER_CALENDAR_DAY;
+    let minute_bars = trading_days * TRADING_MINUTES_PER_DAY;
+
+    let minutes_allowed = range <= TimeDelta::days(MAX_MINUTE_HISTORY_DAYS);
+    let candidates = [
+        (FrequencyType::Minute, 1, minute_bars),
+        (FrequencyType::Minute, 5, minute_bars / 5.0),
+        (FrequencyType::Minute, 10, minute_bars / 10.0),
+        (FrequencyType::Minute, 15, minute_bars / 15.0),
+        (FrequencyType::Minute, 30, minute_bars / 30.0),
+        (FrequencyType::Daily, 1, trading_days),
+        (FrequencyType::Weekly, 1, calendar_days / 7.0),
+        (FrequencyType::Monthly, 1, calendar_days / 30.44),
+    ];
+
+    // Compare on a log scale, so that 2x too many and 2x too few candles count as equally far off.
+    let target = f64::from(approx_bars);
+    let distance = |expected: f64| (expected.max(1.0) / target).ln().abs();
+
+    let (frequency_type, frequency, _) = candidates
+        .into_iter()
+        .filter(|&(frequency_type, _, _)| {
+            minutes_allowed || frequency_type != FrequencyType::Minute
+        })
+        .min_by(|a, b| distance(a.2).total_cmp(&distance(b.2)))
+        .unwrap_or((FrequencyType::Daily, 1, trading_days));
+
+    // Minute candles are only valid with the `day` period type; `year` accepts every other one.
+    let period_type = match frequency_type {
+        FrequencyType::Minute => PeriodType::Day,
+        _ => PeriodType::Year,
+    };
+    (period_type, frequency_type, frequency)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use reqwest::{
+        StatusCode,
+        header::{AUTHORIZATION, HeaderMap, RETRY_AFTER},
+    };
+
+    use super::*;
+    use crate::schwab::{
+        cache::InMemoryCache, models::market_data::PutCall, test_support,
+        token_store::InMemoryTokenStore, transport::MockTransport,
+    };
+
+    #[tokio::test]
+    async fn quotes_request_carries_symbols_fields_and_token() {
+        let transport = Arc::new(MockTransport::new());
+        transport.respond(StatusCode::OK, "{}");
+        let api = test_support::api(&transport).await;
+
+        api.get_quotes(
+            vec!["AAPL".to_string(), "MSFT".to_string()],
+            Some(vec![QuoteFields::Quote, QuoteFields::Fundamental]),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(
+            request.url.as_str(),
+            "https://api.schwabapi.com/marketdata/v1/quotes?symbols=AAPL%2CMSFT&fields=quote%2Cfundamental"
+        );
+        assert_eq!(request.headers[AUTHORIZATION], "Bearer access-token");
+    }
+
+    #[tokio::test]
+    async fn quotes_request_sends_indicative_and_leaves_out_unset_parameters() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .respond(StatusCode::OK, "{}")
+            .respond(StatusCode::OK, "{}");
+        let api = test_support::api(&transport).await;
+
+        api.get_quotes(vec!["SPY".to_string()], Some(vec![]), Some(false))
+            .await
+            .unwrap();
+        api.get_quotes(vec!["SPY".to_string()], None, None)
+            .await
+            .unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(
+            requests[0].url.query(),
+            Some("symbols=SPY&indicative=false")
+        );
+        assert_eq!(requests[1].url.query(), Some("symbols=SPY"));
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_until_success() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .respond(StatusCode::SERVICE_UNAVAILABLE, "")
+            .respond(StatusCode::BAD_GATEWAY, "")
+            .respond(StatusCode::OK, "{}");
+        let api = test_support::api(&transport).await;
+
+        let quotes = api.get_quotes(vec!["AAPL".to_string()], None, None).await;
+
+        assert!(quotes.unwrap().is_empty());
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn rate_limiting_fails_once_retries_are_exhausted() {
+        let transport = Arc::new(MockTransport::new());
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "0".parse().unwrap());
+        for _ in 0..3 {
+            transport.respond_with_headers(StatusCode::TOO_MANY_REQUESTS, headers.clone(), "");
+        }
+        let api = test_support::api(&transport).await;
+
+        let error = api
+            .get_quotes(vec!["AAPL".to_string()], None, None)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            SchwabError::RateLimited {
+                retry_after: Some(retry_after)
+            } if retry_after.is_zero()
+        ));
+        assert_eq!(transport.requests().len(), 3);
+        assert_eq!(transport.remaining(), 0);
+    }
+
+    #[tokio::test]
+    async fn error_bodies_are_mapped_to_typed_errors() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .respond(
+                StatusCode::BAD_REQUEST,
+                r#"{"message":"Invalid symbol","errors":["symbol is required"]}"#,
+            )
+            .respond(StatusCode::FORBIDDEN, "not entitled");
+        let api = test_support::api(&transport).await;
+
+        match api.get_quotes(vec!["AAPL".to_string()], None, None).await {
+            Err(SchwabError::Api {
+                status,
+                error: Some(error),
+                ..
+            }) => {
+                assert_eq!(status, StatusCode::BAD_REQUEST);
+                assert_eq!(error.message.as_deref(), Some("Invalid symbol"));
+            }
+            other => panic!("expected an API error, got {:?}", other),
+        }
+        match api.get_quotes(vec!["AAPL".to_string()], None, None).await {
+            Err(SchwabError::Auth { status, body }) => {
+                assert_eq!(status, StatusCode::FORBIDDEN);
+                assert_eq!(body, "not entitled");
+            }
+            other => panic!("expected an auth error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn executions_are_selected_by_execution_time() {
+        let day = (Utc::now() - TimeDelta::days(1)).date_naive();
+        let (start, end) = new_york_day_bounds(day);
+        let execution = |leg_id: i64, time: DateTime<Utc>| {
+            serde_json::json!({
+                "activityType": "EXECUTION",
+                "executionLegs": [{
+                    "legId": leg_id,
+                    "quantity": 5.0,
+                    "price": 100.0,
+                    "time": time_to_iso8601(Some(time)),
+                }],
+            })
+        };
+        let orders = serde_json::json!([{
+            "orderId": 1,
+            "quantity": 10.0,
+            "status": "CANCELED",
+            "orderLegCollection": [{
+                "legId": 1,
+                "instrument": {"assetType": "EQUITY", "symbol": "SPY"},
+                "instruction": "BUY",
+                "quantity": 10.0,
+            }],
+            "orderActivityCollection": [
+                execution(1, start - TimeDelta::hours(2)),
+                execution(1, start + TimeDelta::hours(15)),
+                execution(1, end + TimeDelta::hours(1)),
+            ],
+        }]);
+        let transport = Arc::new(MockTransport::new());
+        transport.respond(StatusCode::OK, orders.to_string());
+        let api = test_support::api(&transport).await;
+
+        let executions = api
+            .executions(AccountId::Hash("hash".to_string()), day)
+            .await
+            .unwrap();
+
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].time, start + TimeDelta::hours(15));
+
+        let requests = transport.requests();
+        let query: HashMap<_, _> = requests[0].url.query_pairs().into_owned().collect();
+        let entered_from: DateTime<Utc> = query["fromEnteredTime"].parse().unwrap();
+        assert!(entered_from < start - TimeDelta::days(50));
+        assert_eq!(query["toEnteredTime"], time_to_iso8601(Some(end)).unwrap());
+        assert!(!query.contains_key("status"));
+    }
+
+    #[tokio::test]
+    async fn transactions_reject_ranges_over_sixty_days() {
+        let transport = Arc::new(MockTransport::new());
+        let api = test_support::api(&transport).await;
+        let start = Utc::now() - TimeDelta::days(90);
+
+        let result = api
+            .get_transactions(
+                AccountId::Hash("hash".to_string()),
+                start,
+                start + TimeDelta::days(MAX_TRANSACTION_RANGE_DAYS + 1),
+                None,
+                None,
+            )
+            .await;
+
+        assert!(matches!(result, Err(SchwabError::InvalidInput(_))));
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn transactions_default_to_every_type() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .respond(StatusCode::OK, "[]")
+            .respond(StatusCode::OK, "[]");
+        let api = test_support::api(&transport).await;
+        let end = Utc::now();
+        let start = end - TimeDelta::days(MAX_TRANSACTION_RANGE_DAYS);
+
+        for types in [None, Some(vec![])] {
+            api.get_transactions(AccountId::Hash("hash".to_string()), start, end, types, None)
+                .await
+                .unwrap();
+        }
+
+        let every_type = TransactionType::ALL
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        for request in transport.requests() {
+            let query: HashMap<_, _> = request.url.query_pairs().into_owned().collect();
+            assert_eq!(query["types"], every_type);
+            assert!(!query.contains_key("symbol"));
+        }
+    }
+
+    #[tokio::test]
+    async fn empty_market_hours_are_an_unexpected_response() {
+        let transport = Arc::new(MockTransport::new());
+        transport.respond(StatusCode::OK, "{}");
+        let api = test_support::api(&transport).await;
+
+        match api.market_hour(MarketSymbol::Equity, None).await {
+            Err(SchwabError::UnexpectedResponse(message)) => {
+                assert_eq!(message, "Market hours response for equity was empty");
+            }
+            other => panic!("expected an unexpected response error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn long_symbol_lists_are_quoted_in_chunks() {
+        let transport = Arc::new(MockTransport::new());
+        for chunk in 0..3 {
+            let errors =
+                serde_json::json!({ "errors": { "invalidSymbols": [format!("BAD{chunk}")] } });
+            transport.respond(StatusCode::OK, errors.to_string());
+        }
+        let api = test_support::api(&transport)
+            .await
+            .with_quote_chunk_concurrency(1);
+        let symbols: Vec<String> = (0..600).map(|i| format!("SYM{i}")).collect();
+
+        let (quotes, errors) = api
+            .get_quotes_with_errors(symbols.clone(), None, None)
+            .await
+            .unwrap();
+
+        assert!(quotes.is_empty());
+        assert_eq!(errors.invalid_symbols, ["BAD0", "BAD1", "BAD2"]);
+        let requested: Vec<Vec<String>> = transport
+            .requests()
+            .iter()
+            .map(|request| {
+                let (_, symbols) = request
+                    .url
+                    .query_pairs()
+                    .find(|(key, _)| key == "symbols")
+                    .unwrap();
+                symbols.split(',').map(str::to_string).collect()
+            })
+            .collect();
+        assert_eq!(
+            requested.iter().map(Vec::len).collect::<Vec<_>>(),
+            [250, 250, 100]
+        );
+        assert_eq!(requested.concat(), symbols);
+    }
+
+    #[test]
+    fn history_frequency_matches_the_requested_bar_count() {
+        let cases = [
+            (1, 390, PeriodType::Day, FrequencyType::Minute, 1),
+            (5, 270, PeriodType::Day, FrequencyType::Minute, 5),
+            (365, 250, PeriodType::Year, FrequencyType::Daily, 1),
+            (730, 100, PeriodType::Year, FrequencyType::Weekly, 1),
+            (3650, 120, PeriodType::Year, FrequencyType::Monthly, 1),
+        ];
+
+        for (days, approx_bars, period_type, frequency_type, frequency) in cases {
+            assert_eq!(
+                select_history_frequency(TimeDelta::days(days), approx_bars),
+                (period_type, frequency_type, frequency),
+                "{} days, {} bars",
+                days,
+                approx_bars
+            );
+        }
+    }
+
+    #[test]
+    fn history_frequency_uses_minutes_only_within_the_minute_history_limit() {
+        let limit = TimeDelta::days(MAX_MINUTE_HISTORY_DAYS);
+
+        assert_eq!(
+            select_history_frequency(limit, 20_000),
+            (PeriodType::Day, FrequencyType::Minute, 1)
+        );
+        assert_eq!(
+            select_history_frequency(limit + TimeDelta::days(1), 20_000),
+            (PeriodType::Year, FrequencyType::Daily, 1)
+        );
+    }
+
+    /// Contracts of `put_call` AAPL options expiring on `expiration`, one per strike, keyed by
+    /// strike as in a chain's expiration map.
+    fn strike_map(expiration: NaiveDate, put_call: &str, strikes: &[f64]) -> Value {
+        strikes
+            .iter()
+            .map(|&strike| {
+                let contract = serde_json::json!({
+                    "putCall": put_call,
+                    "symbol": format!(
+                        "AAPL  {}{}{:08}",
+                        expiration.format("%y%m%d"),
+                        &put_call[..1],
+                        (strike * 1000.0) as u64
+                    ),
+                    "description": format!(
+                        "AAPL {} {:.2} {}",
+                        expiration.format("%m/%d/%Y"),
+                        strike,
+                        &put_call[..1]
+                    ),
+                    "exchangeName": "OPR",
+                    "bid": 3.1,
+                    "ask": 3.2,
+                    "last": 3.15,
+                    "mark": 3.15,
+                    "bidSize": 12,
+                    "askSize": 40,
+                    "bidAskSize": "12X40",
+                    "lastSize": 1,
+                    "highPrice": 3.5,
+                    "lowPrice": 2.8,
+                    "openPrice": 0.0,
+                    "closePrice": 2.95,
+                    "totalVolume": 10234,
+                    "tradeTimeInLong": 1705438799000i64,
+                    "quoteTimeInLong": 1705438800000i64,
+                    "netChange": 0.2,
+                    "volatility": 21.4,
+                    "delta": 0.52,
+                    "gamma": 0.061,
+                    "theta": -0.21,
+                    "vega": 0.08,
+                    "rho": 0.01,
+                    "openInterest": 25310,
+                    "timeValue": 2.35,
+                    "theoreticalOptionValue": 3.16,
+                    "theoreticalVolatility": 29.0,
+                    "strikePrice": strike,
+                    "expirationDate": format!("{}T21:00:00.000+00:00", expiration),
+                    "daysToExpiration": 2,
+                    "expirationType": "S",
+                    "lastTradingDay": 1705712400000i64,
+                    "multiplier": 100.0,
+                    "settlementType": "P",
+                    "deliverableNote": "100 AAPL",
+                    "inTheMoney": (put_call == "CALL") == (strike < 190.0),
+                    "isPennyPilot": true
+                });
+                (format!("{:.1}", strike), serde_json::json!([contract]))
+            })
+            .collect::<serde_json::Map<String, Value>>()
+            .into()
+    }
+
+    /// A window of the AAPL chain with the given call and put strikes for each expiration.
+    fn chain_window(expirations: &[(&str, &[f64], &[f64])]) -> String {
+        let mut call_map = serde_json::Map::new();
+        let mut put_map = serde_json::Map::new();
+        for &(date, calls, puts) in expirations {
+            let expiration = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+            let key = format!("{}:2", date);
+            call_map.insert(key.clone(), strike_map(expiration, "CALL", calls));
+            put_map.insert(key, strike_map(expiration, "PUT", puts));
+        }
+
+        serde_json::json!({
+            "symbol": "AAPL",
+            "status": "SUCCESS",
+            "strategy": "SINGLE",
+            "interval": 0.0,
+            "isDelayed": false,
+            "isIndex": false,
+            "interestRate": 5.25,
+            "underlyingPrice": 190.5,
+            "volatility": 29.0,
+            "daysToExpiration": 0.0,
+            "numberOfContracts": 0,
+            "callExpDateMap": call_map,
+            "putExpDateMap": put_map
+        })
+        .to_string()
+    }
+
+    /// An expiration chain listing `dates` as standard expirations.
+    fn expiration_chain(dates: &[&str]) -> String {
+        let expirations: Vec<Value> = dates
+            .iter()
+            .map(|date| {
+                serde_json::json!({
+                    "expirationDate": date,
+                    "daysToExpiration": 2,
+                    "expirationType": "S",
+                    "standard": true
+                })
+            })
+            .collect();
+        serde_json::json!({ "expirationList": expirations, "status": "SUCCESS" }).to_string()
+    }
+
+    /// The sorted strikes of every contract of `put_call` in `chain`, with their expiration.
+    fn chain_strikes(chain: &ChainsResponse, put_call: PutCall) -> Vec<(String, f64)> {
+        let mut strikes: Vec<(String, f64)> = chain
+            .all_contracts()
+            .filter(|contract| contract.put_call == put_call)
+            .map(|contract| (contract.expiration_date.clone(), contract.strike_price))
+            .collect();
+        strikes.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        strikes
+    }
+
+    #[tokio::test]
+    async fn chains_are_fetched_in_moneyness_windows_per_expiration() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .respond(
+                StatusCode::OK,
+                expiration_chain(&["2024-01-19", "2024-01-26"]),
+            )
+            .respond(
+                StatusCode::OK,
+                chain_window(&[("2024-01-19", &[185.0], &[195.0])]),
+            )
+            .respond(
+                StatusCode::OK,
+                chain_window(&[("2024-01-19", &[190.0, 195.0], &[185.0, 190.0])]),
+            )
+            .respond(
+                StatusCode::OK,
+                chain_window(&[("2024-01-26", &[185.0], &[195.0])]),
+            )
+            .respond(
+                StatusCode::OK,
+                chain_window(&[("2024-01-26", &[190.0, 195.0], &[185.0, 190.0])]),
+            );
+        let api = test_support::api(&transport).await;
+
+        let chain = api
+            .get_chains_by_strike_window(ChainsRequest::new("AAPL").strike_count(1).strike(190.0))
+            .await
+            .unwrap();
+
+        let expected: Vec<(String, f64)> = ["2024-01-19", "2024-01-26"]
+            .into_iter()
+            .flat_map(|date| {
+                [185.0, 190.0, 195.0].map(|strike| (format!("{}T21:00:00.000+00:00", date), strike))
+            })
+            .collect();
+        assert_eq!(chain_strikes(&chain, PutCall::Call), expected);
+        assert_eq!(chain_strikes(&chain, PutCall::Put), expected);
+        assert_eq!(chain.number_of_contracts, 12);
+
+        let mut queries: Vec<String> = transport
+            .requests()
+            .iter()
+            .map(|request| format!("{}?{}", request.url.path(), request.url.query().unwrap()))
+            .collect();
+        queries.sort();
+        assert_eq!(
+            queries,
+            [
+                "/marketdata/v1/chains?symbol=AAPL&range=ITM&fromDate=2024-01-19&toDate=2024-01-19",
+                "/marketdata/v1/chains?symbol=AAPL&range=ITM&fromDate=2024-01-26&toDate=2024-01-26",
+                "/marketdata/v1/chains?symbol=AAPL&range=OTM&fromDate=2024-01-19&toDate=2024-01-19",
+                "/marketdata/v1/chains?symbol=AAPL&range=OTM&fromDate=2024-01-26&toDate=2024-01-26",
+                "/marketdata/v1/expirationchain?symbol=AAPL",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn only_successful_market_data_responses_are_cached() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .respond(StatusCode::BAD_REQUEST, r#"{"errors": []}"#)
+            .respond(StatusCode::OK, "{}")
+            .respond(StatusCode::OK, expiration_chain(&["2024-01-19"]));
+        let cache = InMemoryCache::new(8, None);
+        let api = test_support::api(&transport)
+            .await
+            .with_cache(Arc::new(cache.clone()));
+        let fetch = || api.option_expiration_chain("AAPL".to_string());
+
+        let rejected = fetch().await;
+        let malformed = fetch().await;
+        let fetched = fetch().await.unwrap();
+        let cached = fetch().await.unwrap();
+
+        assert!(matches!(rejected, Err(SchwabError::Api { .. })));
+        assert!(matches!(malformed, Err(SchwabError::Deserialize { .. })));
+        assert_eq!(fetched.expiration_list[0].expiration_date, "2024-01-19");
+        assert_eq!(cached.expiration_list[0].expiration_date, "2024-01-19");
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            cache.get(&cache_key(&requests[2].url)).await.unwrap(),
+            Some(expiration_chain(&["2024-01-19"]))
+        );
+    }
+
+    #[tokio::test]
+    async fn incomplete_chain_windows_are_an_error() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .respond(
+                StatusCode::OK,
+                expiration_chain(&["2024-01-19", "2024-01-26"]),
+            )
+            .respond(
+                StatusCode::OK,
+                chain_window(&[("2024-01-19", &[185.0], &[195.0])]),
+            )
+            .respond(
+                StatusCode::OK,
+                chain_window(&[("2024-01-19", &[190.0, 195.0], &[185.0, 190.0])]),
+            )
+            .respond(StatusCode::OK, chain_window(&[]))
+            .respond(StatusCode::OK, chain_window(&[]))
+            .respond(StatusCode::OK, expiration_chain(&["2024-01-19"]))
+            .respond(
+                StatusCode::OK,
+                chain_window(&[("2024-01-19", &[185.0], &[195.0])]),
+            )
+            .respond(
+                StatusCode::OK,
+                chain_window(&[("2024-01-19", &[190.0, 195.0], &[190.0])]),
+            );
+        let api = test_support::api(&transport).await;
+
+        let missing_expiration = api
+            .get_chains_by_strike_window(ChainsRequest::new("AAPL"))
+            .await;
+        let missing_strike = api
+            .get_chains_by_strike_window(ChainsRequest::new("AAPL"))
+            .await;
+
+        assert_eq!(
+            missing_expiration.unwrap_err().to_string(),
+            "unexpected response: Chain windows returned no contracts for the listed expirations 2024-01-26"
+        );
+        assert_eq!(
+            missing_strike.unwrap_err().to_string(),
+            "unexpected response: Chain windows for 2024-01-19 returned strikes 185.0 for only one of calls and puts"
+        );
+    }
+
+    #[tokio::test]
+    async fn account_hash_resolves_hashes_and_account_numbers() {
+        let transport = Arc::new(MockTransport::new());
+        let accounts = r#"[{"accountNumber": "12345678", "hashValue": "E5B3F1"}]"#;
+        transport
+            .respond(StatusCode::OK, accounts)
+            .respond(StatusCode::OK, accounts);
+        let api = test_support::api(&transport).await;
+
+        let by_hash = api
+            .account_hash(&AccountId::Hash("A1B2C3".to_string()))
+            .await;
+        assert_eq!(by_hash.unwrap(), "A1B2C3");
+        assert!(transport.requests().is_empty());
+
+        let number = AccountId::Number("12345678".to_string());
+        assert_eq!(api.account_hash(&number).await.unwrap(), "E5B3F1");
+        assert_eq!(api.account_hash(&number).await.unwrap(), "E5B3F1");
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].url.as_str(),
+            "https://api.schwabapi.com/trader/v1/accounts/accountNumbers"
+        );
+
+        let unknown = api
+            .account_hash(&AccountId::Number("87654321".to_string()))
+            .await;
+        assert!(matches!(unknown, Err(SchwabError::InvalidInput(_))));
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    /// An equity quote for `symbol` with the given `securityStatus`.
+    fn equity_quote(symbol: &str, security_status: &str) -> Value {
+        serde_json::json!({
+            "assetMainType": "EQUITY",
+            "assetType": "COMMON_STOCK",
+            "symbol": symbol,
+            "description": format!("{} Inc", symbol),
+            "realtime": true,
+            "quote": {
+                "52WeekHigh": 199.62,
+                "52WeekLow": 164.08,
+                "closePrice": 101.0,
+                "lastPrice": 101.5,
+                "netChange": 0.5,
+                "netPercentChange": 0.495,
+                "securityStatus": security_status,
+                "totalVolume": 1200
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn filter_tradable_drops_halted_and_unknown_symbols() {
+        let transport = Arc::new(MockTransport::new());
+        let quotes = serde_json::json!({
+            "AAPL": equity_quote("AAPL", "Normal"),
+            "HALT": equity_quote("HALT", "Halted"),
+            "CLSD": equity_quote("CLSD", "Closed"),
+            "MSFT": equity_quote("MSFT", "Normal"),
+            "errors": { "invalidSymbols": ["NOPE"] }
+        });
+        transport.respond(StatusCode::OK, quotes.to_string());
+        let api = test_support::api(&transport).await;
+
+        let tradable = api
+            .filter_tradable(
+                ["MSFT", "HALT", "NOPE", "AAPL", "CLSD", "MSFT"]
+                    .map(str::to_string)
+                    .to_vec(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(tradable, ["MSFT", "AAPL"]);
+        assert_eq!(
+            transport.requests()[0].url.query(),
+            Some("symbols=MSFT%2CHALT%2CNOPE%2CAAPL%2CCLSD&fields=quote")
+        );
+    }
+
+    #[tokio::test]
+    async fn gaps_compare_the_open_with_the_previous_close() {
+        let mut gapped_up = equity_quote("AAPL", "Normal");
+        gapped_up["quote"]["closePrice"] = serde_json::json!(100.0);
+        gapped_up["quote"]["openPrice"] = serde_json::json!(103.0);
+        let mut gapped_down = equity_quote("MSFT", "Normal");
+        gapped_down["quote"]["closePrice"] = serde_json::json!(400.0);
+        gapped_down["quote"]["openPrice"] = serde_json::json!(390.0);
+        let mut not_open = equity_quote("NVDA", "Normal");
+        not_open["quote"]["openPrice"] = serde_json::json!(0.0);
+        let bond = serde_json::json!({
+            "assetMainType": "BOND",
+            "assetType": "BOND",
+            "symbol": "912797KX4",
+            "description": "US Treasury Bill",
+            "quote": { "lastPrice": 98.7 }
+        });
+        let quotes = serde_json::json!({
+            "AAPL": gapped_up,
+            "MSFT": gapped_down,
+            "NVDA": not_open,
+            "912797KX4": bond
+        });
+        let transport = Arc::new(MockTransport::new());
+        transport.respond(StatusCode::OK, quotes.to_string());
+        let api = test_support::api(&transport).await;
+
+        let gaps = api
+            .gaps(
+                ["AAPL", "MSFT", "NVDA", "912797KX4"]
+                    .map(str::to_string)
+                    .to_vec(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(gaps.len(), 3);
+        assert_eq!(
+            gaps["AAPL"],
+            GapInfo {
+                previous_close: 100.0,
+                open: Some(103.0),
+                gap_amount: Some(3.0),
+                gap_percent: Some(3.0),
+            }
+        );
+        assert_eq!(gaps["MSFT"].gap_amount, Some(-10.0));
+        assert_eq!(gaps["MSFT"].gap_percent, Some(-2.5));
+        assert_eq!(
+            gaps["NVDA"],
+            GapInfo {
+                previous_close: 101.0,
+                open: None,
+                gap_amount: None,
+                gap_percent: None,
+            }
+        );
+    }
+
+    const REFRESHED_TOKENS: &str = r#"{
+        "access_token": "new-access-token",
+        "expires_in": 1800,
+        "id_token": "new-id-token",
+        "refresh_token": "new-refresh-token",
+        "scope": "api",
+        "token_type": "Bearer"
+    }"#;
+
+    #[tokio::test]
+    async fn unauthorized_requests_refresh_the_token_and_retry_once() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .respond(StatusCode::UNAUTHORIZED, "")
+            .respond(StatusCode::OK, REFRESHED_TOKENS)
+            .respond(StatusCode::OK, "{}");
+        let store = Arc::new(InMemoryTokenStore::with_tokens(test_support::tokens()));
+        let api = SchwabApi::builder()
+            .app_key("app-key")
+            .app_secret("app-secret")
+            .token_store(store.clone())
+            .transport(transport.clone())
+            .build()
+            .await
+            .unwrap();
+
+        let quotes = api.get_quotes(vec!["AAPL".to_string()], None, None).await;
+
+        assert!(quotes.unwrap().is_empty());
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].headers[AUTHORIZATION], "Bearer access-token");
+        assert_eq!(requests[1].method, Method::POST);
+        assert_eq!(
+            requests[1].url.as_str(),
+            "https://api.schwabapi.com/v1/oauth/token"
+        );
+        assert_eq!(
+            requests[2].headers[AUTHORIZATION],
+            "Bearer new-access-token"
+        );
+        let stored = store.load().await.unwrap().unwrap();
+        assert_eq!(stored.refresh_token, "new-refresh-token");
+    }
+
+    #[tokio::test]
+    async fn a_second_unauthorized_response_is_not_retried() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .respond(StatusCode::UNAUTHORIZED, "")
+            .respond(StatusCode::OK, REFRESHED_TOKENS)
+            .respond(StatusCode::UNAUTHORIZED, "");
+        let api = test_support::api(&transport).await;
+
+        let quotes = api.get_quotes(vec!["AAPL".to_string()], None, None).await;
+
+        assert!(matches!(quotes, Err(SchwabError::TokenExpired)));
+        assert_eq!(transport.requests().len(), 3);
+    }
+}
@@ -1,19 +1,35 @@
 use std::{
-    fs,
+    fmt,
     io::{self, Write},
+    process,
     sync::Arc,
+    time,
 };
 
-use base64::{engine::general_purpose, Engine};
+use base64::{Engine, engine::general_purpose};
+use chrono::{DateTime, Duration, Utc};
 use reqwest::{
+    Client, StatusCode, Url,
     header::{HeaderMap, HeaderValue},
-    Client, StatusCode,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tracing::info;
+use tokio::{
+    sync::{Mutex, watch},
+    task::JoinHandle,
+    time::{MissedTickBehavior, interval_at, sleep},
+};
+use tracing::{info, warn};
 
-use crate::schwab::common::{REDIRECT_URI, SCHWAB_AUTH_URL, SCHWAB_TOKEN_URL, TOKENS_FILE};
+use crate::schwab::{
+    callback_server,
+    common::{REDIRECT_URI, TOKENS_FILE, default_http_client},
+    endpoints::SchwabEndpoints,
+    error::SchwabError,
+    retry::RetryConfig,
+    token_store::{FileTokenStore, TokenStore},
+    transport::HttpTransport,
+};
 
 #[derive(Serialize, Debug)]
 struct AuthRequestPayload {
@@ -28,29 +44,151 @@ struct RefreshRequestPayload {
     refresh_token: String,
 }
 
-/// Represents the token information stored in a local file.
+/// Represents the token information persisted by a `TokenStore`.
+///
+/// The fields are public so that tokens can be inspected, e.g. to schedule refreshes around
+/// `expires_at`, and moved between token stores. They are credentials: avoid logging them.
 #[derive(Serialize, Deserialize, Debug, Clone)] // Added Clone
-#[allow(dead_code)]
-pub(crate) struct StoredTokenInfo {
+pub struct StoredTokenInfo {
     /// The access token.
-    pub(crate) access_token: String,
+    pub access_token: String,
     /// The number of seconds until the access token expires.
-    pub(crate) expires_in: u64,
+    pub expires_in: u64,
     /// The ID token.
-    pub(crate) id_token: String,
+    pub id_token: String,
     /// The refresh token.
-    pub(crate) refresh_token: String,
+    pub refresh_token: String,
     /// The scope of the access token.
-    pub(crate) scope: String,
+    pub scope: String,
     /// The type of the token.
-    pub(crate) token_type: String,
+    pub token_type: String,
+    /// When the access token was obtained. Token files written before this field existed
+    /// default to the time they are loaded.
+    #[serde(default = "Utc::now")]
+    pub obtained_at: DateTime<Utc>,
+}
+
+impl StoredTokenInfo {
+    /// Returns the time at which the access token expires.
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.obtained_at + Duration::seconds(self.expires_in as i64)
+    }
+
+    /// Returns `true` if the access token has expired.
+    pub fn is_expired(&self) -> bool {
+        Utc::now() >= self.expires_at()
+    }
+
+    /// Returns `true` if the tokens were issued with a scope that covers `scope`.
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        match scope {
+            Scope::ReadOnly => true,
+            Scope::Trade => self
+                .scope
+                .split_whitespace()
+                .any(|granted| granted == Scope::Trade.to_string()),
+        }
+    }
+}
+
+/// The OAuth scope requested during authorization.
+///
+/// The scope is fixed when the tokens are issued. Refreshing keeps the original scope, so switching
+/// from `ReadOnly` to `Trade` requires running the authorization flow again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// Market data and account information only.
+    #[default]
+    ReadOnly,
+    /// Full API access, including placing and canceling orders.
+    Trade,
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scope::ReadOnly => write!(f, "readonly"),
+            Scope::Trade => write!(f, "api"),
+        }
+    }
+}
+
+/// Options for `SchwabAuth::authorize_with_callback`.
+#[derive(Debug, Clone)]
+pub struct AuthorizeCallbackConfig {
+    /// The local port to listen on for the OAuth redirect. Default: `8182`.
+    pub port: u16,
+    /// How long to wait for the redirect before giving up. Default: 5 minutes.
+    pub timeout: time::Duration,
+    /// Whether to open the authorization URL in the default browser. Default: `false`.
+    pub open_browser: bool,
+    /// The OAuth scope to request. Default: `Scope::ReadOnly`.
+    pub scope: Scope,
+    /// The redirect URI sent to Schwab. It must match a callback URL registered for the app.
+    ///
+    /// Default: `http://127.0.0.1:<port>`. Override it if the registered callback differs, for
+    /// example an `https://` URL served by a proxy that forwards to the local port.
+    pub redirect_uri: Option<String>,
+}
+
+impl Default for AuthorizeCallbackConfig {
+    fn default() -> Self {
+        Self {
+            port: 8182,
+            timeout: time::Duration::from_secs(300),
+            open_browser: false,
+            scope: Scope::default(),
+            redirect_uri: None,
+        }
+    }
+}
+
+/// Stops a task started with `SchwabAuth::spawn_refresh_task`.
+///
+/// Dropping it without calling [`RefreshShutdown::shutdown`] leaves the task running; abort the
+/// `JoinHandle` to stop it without waiting for an in-flight refresh.
+#[derive(Debug)]
+pub struct RefreshShutdown {
+    sender: watch::Sender<bool>,
+}
+
+impl RefreshShutdown {
+    /// Asks the task to stop. A refresh in flight is finished and stored first; pending retries
+    /// are abandoned.
+    pub fn shutdown(&self) {
+        self.sender.send_replace(true);
+    }
 }
 
 /// A client for handling the Schwab API authentication process.
 #[derive(Debug, Clone)]
 pub struct SchwabAuth {
     reqwest_client: Arc<Client>,
-    tokens_file_path: String,
+    transport: Arc<dyn HttpTransport>,
+    token_store: Arc<dyn TokenStore>,
+    redirect_uri: String,
+    /// Serializes refreshes and remembers the last one, keyed by the refresh token it consumed.
+    last_refresh: Arc<Mutex<Option<(String, StoredTokenInfo)>>>,
+    persist_on_authorize: bool,
+    endpoints: SchwabEndpoints,
+}
+
+impl Default for SchwabAuth {
+    /// Creates a new `SchwabAuth` instance with default settings.
+    ///
+    /// This uses a default `reqwest::Client` and a `FileTokenStore` at the default `TOKENS_FILE` path.
+    fn default() -> Self {
+        let reqwest_client = Arc::new(default_http_client());
+        Self {
+            transport: reqwest_client.clone(),
+            reqwest_client,
+            token_store: Arc::new(FileTokenStore::new(TOKENS_FILE)),
+            redirect_uri: REDIRECT_URI.to_string(),
+            last_refresh: Arc::new(Mutex::new(None)),
+            persist_on_authorize: true,
+            endpoints: SchwabEndpoints::default(),
+        }
+    }
 }
 
 impl SchwabAuth {
@@ -59,49 +197,215 @@ impl SchwabAuth {
     /// # Arguments
     ///
     /// * `reqwest_client` - An `Arc` wrapped `reqwest::Client` to be used for making HTTP requests.
+    /// * `tokens_file_path` - The path of the file tokens are saved to.
     ///
     /// # Returns
     ///
     /// A new `SchwabAuth` instance.
     pub fn new(reqwest_client: Arc<Client>, tokens_file_path: String) -> Self {
-        Self {
+        Self::with_token_store(
             reqwest_client,
-            tokens_file_path,
-        }
+            Arc::new(FileTokenStore::new(tokens_file_path)),
+        )
     }
 
-    /// Creates a new `SchwabAuth` instance with default settings.
+    /// Creates a new `SchwabAuth` instance that saves tokens to a custom `TokenStore`.
     ///
-    /// This uses a default `reqwest::Client` and the default `TOKENS_FILE` path.
+    /// # Arguments
+    ///
+    /// * `reqwest_client` - An `Arc` wrapped `reqwest::Client` to be used for making HTTP requests.
+    /// * `token_store` - The store that authorized tokens are saved to.
     ///
     /// # Returns
     ///
     /// A new `SchwabAuth` instance.
-    pub fn default() -> Self {
+    pub fn with_token_store(reqwest_client: Arc<Client>, token_store: Arc<dyn TokenStore>) -> Self {
         Self {
-            reqwest_client: Arc::new(Client::new()),
-            tokens_file_path: TOKENS_FILE.to_owned(),
+            transport: reqwest_client.clone(),
+            reqwest_client,
+            token_store,
+            redirect_uri: REDIRECT_URI.to_string(),
+            last_refresh: Arc::new(Mutex::new(None)),
+            persist_on_authorize: true,
+            endpoints: SchwabEndpoints::default(),
         }
     }
 
+    /// Sets the redirect URI used by `authorize`, `authorize_with_scope` and `authorize_with_timeout`.
+    ///
+    /// It must exactly match a callback URL registered for the app in the Schwab Developer Portal,
+    /// e.g. `https://127.0.0.1:8182`. Defaults to `https://127.0.0.1`.
+    ///
+    /// # Arguments
+    ///
+    /// * `redirect_uri` - The callback URL registered for the app.
+    ///
+    /// # Returns
+    ///
+    /// The `SchwabAuth` instance using the given redirect URI.
+    pub fn with_redirect_uri(mut self, redirect_uri: impl Into<String>) -> Self {
+        self.redirect_uri = redirect_uri.into();
+        self
+    }
+
+    /// Controls whether the authorize methods save new tokens to the token store.
+    ///
+    /// Enabled by default. Every authorize method also returns the new `StoredTokenInfo`, so
+    /// callers that route tokens elsewhere themselves can disable the automatic save.
+    ///
+    /// # Arguments
+    ///
+    /// * `persist` - Whether to save authorized tokens to the token store.
+    ///
+    /// # Returns
+    ///
+    /// The `SchwabAuth` instance with the given setting.
+    pub fn with_persist_on_authorize(mut self, persist: bool) -> Self {
+        self.persist_on_authorize = persist;
+        self
+    }
+
+    /// Sets the authorization and token URLs, e.g. to run against a mock server.
+    ///
+    /// Defaults to `SchwabEndpoints::default()`, Schwab's production endpoints.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoints` - The endpoints to use. Only `authorize` and `token` are used here.
+    ///
+    /// # Returns
+    ///
+    /// The `SchwabAuth` instance using the given endpoints.
+    pub fn with_endpoints(mut self, endpoints: SchwabEndpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Sends token requests through `transport` instead of the `reqwest::Client`, which is then
+    /// only used to build them.
+    ///
+    /// # Arguments
+    ///
+    /// * `transport` - The transport that executes token requests, e.g. a `MockTransport`.
+    ///
+    /// # Returns
+    ///
+    /// The `SchwabAuth` instance using the given transport.
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Returns the endpoints this instance authorizes against.
+    pub fn endpoints(&self) -> &SchwabEndpoints {
+        &self.endpoints
+    }
+
+    /// Returns the redirect URI used by the copy-paste authorization flow.
+    pub fn redirect_uri(&self) -> &str {
+        &self.redirect_uri
+    }
+
+    /// Returns the `TokenStore` this instance saves tokens to.
+    pub fn token_store(&self) -> Arc<dyn TokenStore> {
+        self.token_store.clone()
+    }
+
     /// Guides the user through the Schwab API authorization process.
     ///
     /// This method constructs the authorization URL, prompts the user to log in and authorize the application,
     /// and then exchanges the authorization code for an access token and refresh token. The tokens are then
-    /// saved to a local file.
+    /// saved to the configured `TokenStore` and returned.
+    ///
+    /// The prompt waits for input indefinitely. Use `authorize_with_timeout` to bound the wait.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_key` - The application key (Client ID) provided by Schwab.
+    /// * `secret` - The application secret (Client Secret) provided by Schwab.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `StoredTokenInfo`, or a `SchwabError` if the authorization process fails.
+    pub async fn authorize(
+        &self,
+        app_key: &str,
+        secret: &str,
+    ) -> Result<StoredTokenInfo, SchwabError> {
+        self.authorize_interactive(app_key, secret, Scope::ReadOnly, None)
+            .await
+    }
+
+    /// Like `authorize`, but requests the given OAuth scope instead of `Scope::ReadOnly`.
+    ///
+    /// Tokens keep the scope they were issued with, so changing scope means authorizing again.
     ///
     /// # Arguments
     ///
     /// * `app_key` - The application key (Client ID) provided by Schwab.
     /// * `secret` - The application secret (Client Secret) provided by Schwab.
+    /// * `scope` - The OAuth scope to request.
     ///
     /// # Returns
     ///
-    /// A `Result` indicating success (`Ok(())`) or an `anyhow::Error` if the authorization process fails.
-    pub async fn authorize(&self, app_key: &str, secret: &str) -> anyhow::Result<()> {
-        let full_auth_url = format!(
-            "{}&client_id={}&scope=readonly&redirect_uri={}",
-            SCHWAB_AUTH_URL, app_key, REDIRECT_URI
+    /// A `Result` containing the new `StoredTokenInfo`, or a `SchwabError` if the authorization process fails.
+    pub async fn authorize_with_scope(
+        &self,
+        app_key: &str,
+        secret: &str,
+        scope: Scope,
+    ) -> Result<StoredTokenInfo, SchwabError> {
+        self.authorize_interactive(app_key, secret, scope, None)
+            .await
+    }
+
+    /// Like `authorize_with_scope`, but gives up if no URL is pasted within `input_timeout`.
+    ///
+    /// Useful when the flow may be launched unattended, where waiting on stdin forever would hang
+    /// the process. For a flow that needs no input at all, see `authorize_with_callback`.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_key` - The application key (Client ID) provided by Schwab.
+    /// * `secret` - The application secret (Client Secret) provided by Schwab.
+    /// * `scope` - The OAuth scope to request.
+    /// * `input_timeout` - How long to wait for the redirect URL to be pasted.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `StoredTokenInfo`. Fails with `SchwabError::Timeout` if no
+    /// input arrives within `input_timeout`, or with another `SchwabError` if the authorization
+    /// process fails.
+    pub async fn authorize_with_timeout(
+        &self,
+        app_key: &str,
+        secret: &str,
+        scope: Scope,
+        input_timeout: time::Duration,
+    ) -> Result<StoredTokenInfo, SchwabError> {
+        self.authorize_interactive(app_key, secret, scope, Some(input_timeout))
+            .await
+    }
+
+    /// Runs the copy-paste authorization flow, waiting at most `input_timeout` for input if set.
+    async fn authorize_interactive(
+        &self,
+        app_key: &str,
+        secret: &str,
+        scope: Scope,
+        input_timeout: Option<time::Duration>,
+    ) -> Result<StoredTokenInfo, SchwabError> {
+        let redirect_uri = Url::parse(&self.redirect_uri).map_err(|e| {
+            SchwabError::Config(format!(
+                "Redirect URI `{}` is not a valid URL: {}",
+                self.redirect_uri, e
+            ))
+        })?;
+        let full_auth_url = authorization_url(
+            &self.endpoints.authorize,
+            app_key,
+            scope,
+            &self.redirect_uri,
         );
 
         // Prompt the user to log in and authorize the application.
@@ -115,37 +419,140 @@ impl SchwabAuth {
         print!("4. Paste the URL here and press Enter: ");
         io::stdout().flush()?; // Ensure the prompt is displayed immediately.
 
-        let mut returned_url = String::new();
-        io::stdin().read_line(&mut returned_url)?;
+        // Read stdin on a detached thread so the wait can be bounded by a timeout. Unlike
+        // `spawn_blocking`, a thread left blocked on stdin does not hold up runtime shutdown.
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        std::thread::spawn(move || {
+            let mut returned_url = String::new();
+            let _ = tx.send(
+                io::stdin()
+                    .read_line(&mut returned_url)
+                    .map(|_| returned_url),
+            );
+        });
+        let received = match input_timeout {
+            Some(input_timeout) => tokio::time::timeout(input_timeout, rx)
+                .await
+                .map_err(|_| SchwabError::Timeout(input_timeout))?,
+            None => rx.await,
+        };
+        let returned_url = received
+            .map_err(|_| SchwabError::InvalidInput("Failed to read from stdin".to_string()))??;
 
         // Extract the authorization code from the returned URL.
         let response_code = self.extract_auth_code(&returned_url)?;
         info!("Successfully extracted response code: {}", response_code);
 
+        // Schwab only redirects to registered callbacks, so a URL for a different origin means the
+        // configured redirect URI is not the one registered for the app.
+        if let Ok(returned) = Url::parse(returned_url.trim())
+            && returned.origin() != redirect_uri.origin()
+        {
+            return Err(SchwabError::Config(format!(
+                "Schwab redirected to `{}`, which does not match the configured redirect URI `{}`. \
+                 Set SchwabAuth::with_redirect_uri to the callback URL registered for your app.",
+                returned.origin().ascii_serialization(),
+                self.redirect_uri
+            )));
+        }
+
+        self.exchange_code(app_key, secret, &self.redirect_uri, &response_code)
+            .await
+    }
+
+    /// Runs the Schwab authorization flow without the manual copy-paste step.
+    ///
+    /// A temporary HTTP listener is started on `127.0.0.1:<port>` and captures the authorization
+    /// code from Schwab's redirect. The listener shuts down after the code arrives or after the
+    /// configured timeout. The code is then exchanged for tokens, which are saved to the configured
+    /// `TokenStore`.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_key` - The application key (Client ID) provided by Schwab.
+    /// * `secret` - The application secret (Client Secret) provided by Schwab.
+    /// * `config` - The port, timeout, browser and redirect URI options.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `StoredTokenInfo`, or a `SchwabError` if the authorization process fails.
+    pub async fn authorize_with_callback(
+        &self,
+        app_key: &str,
+        secret: &str,
+        config: AuthorizeCallbackConfig,
+    ) -> Result<StoredTokenInfo, SchwabError> {
+        let redirect_uri = config
+            .redirect_uri
+            .clone()
+            .unwrap_or_else(|| format!("http://127.0.0.1:{}", config.port));
+        let full_auth_url = authorization_url(
+            &self.endpoints.authorize,
+            app_key,
+            config.scope,
+            &redirect_uri,
+        );
+
+        // Bind before pointing the user at Schwab, so the redirect cannot arrive too early.
+        let listener = callback_server::bind(config.port).await?;
+
+        println!("\nSchwab API Authorization Guide:");
+        if config.open_browser && open_in_browser(&full_auth_url).is_ok() {
+            println!("1. Your browser has been opened to the Schwab login page.");
+        } else {
+            println!("1. Open the following URL in your browser:");
+        }
+        println!("{}", full_auth_url);
+        println!("2. Log in with your Schwab portfolio credentials and authorize the application.");
+        println!("Waiting for the redirect on 127.0.0.1:{}...", config.port);
+
+        let response_code = callback_server::wait_for_auth_code(listener, config.timeout).await?;
+        info!("Successfully captured response code from redirect.");
+
+        self.exchange_code(app_key, secret, &redirect_uri, &response_code)
+            .await
+    }
+
+    /// Exchanges an authorization code for tokens and, unless disabled, saves them to the token store.
+    async fn exchange_code(
+        &self,
+        app_key: &str,
+        secret: &str,
+        redirect_uri: &str,
+        response_code: &str,
+    ) -> Result<StoredTokenInfo, SchwabError> {
         // Construct headers and payload for the token request.
         let headers = self.construct_headers(app_key, secret);
-        let payload = self.construct_auth_payload(REDIRECT_URI, &response_code);
+        let payload = self.construct_auth_payload(redirect_uri, response_code);
         info!("Constructed headers and payload.");
 
         // Retrieve the tokens using the authorization code.
         let token_response_body = self.retrieve_tokens(headers, payload).await?;
         info!("Successfully retrieved tokens from API.");
 
-        // Convert the token response to a JSON string.
-        let json_string = serde_json::to_string_pretty(&token_response_body)?;
+        // Convert the token response into the stored representation.
+        let mut token_info: StoredTokenInfo = serde_json::from_value(token_response_body)?;
+        token_info.obtained_at = Utc::now();
 
-        // Save the tokens to a local file.
-        info!("Saving tokens to {}", self.tokens_file_path);
-        fs::write(self.tokens_file_path.to_owned(), json_string)?;
-        info!("Tokens saved successfully!");
+        // Save the tokens to the token store.
+        if self.persist_on_authorize {
+            info!("Saving tokens to {:?}", self.token_store);
+            self.token_store.save(&token_info).await?;
+            info!("Tokens saved successfully!");
+        }
 
-        Ok(())
+        Ok(token_info)
     }
 
     /// Refreshes the access token using the provided refresh token.
     ///
     /// This method requests a new access token from Schwab and returns the complete new token info.
-    /// It does NOT read from or write to the tokens file itself.
+    /// It does NOT read from or write to the token store itself.
+    ///
+    /// Schwab rotates the refresh token on every use, so only one refresh runs at a time across all
+    /// clones of this `SchwabAuth`. Callers that arrive with a refresh token that was already
+    /// exchanged while they waited receive the result of that refresh instead of sending the stale
+    /// token, which Schwab would reject.
     ///
     /// # Arguments
     ///
@@ -155,57 +562,173 @@ impl SchwabAuth {
     ///
     /// # Returns
     ///
-    /// A `Result` containing the new `StoredTokenInfo`, or an `anyhow::Error` if the refresh fails.
+    /// A `Result` containing the new `StoredTokenInfo`, or a `SchwabError` if the refresh fails.
+    /// A refresh token Schwab no longer accepts is reported as `SchwabError::RefreshTokenExpired`.
     pub(crate) async fn refresh_tokens(
         &self,
         app_key: &str,
         secret: &str,
         refresh_token: &str,
-    ) -> anyhow::Result<StoredTokenInfo> {
+    ) -> Result<StoredTokenInfo, SchwabError> {
+        let mut last_refresh = self.last_refresh.lock().await;
+        if let Some((consumed_token, new_token_info)) = last_refresh.as_ref()
+            && consumed_token == refresh_token
+        {
+            info!("Tokens were already refreshed by a concurrent caller.");
+            return Ok(new_token_info.clone());
+        }
+
+        let new_token_info = self.request_refresh(app_key, secret, refresh_token).await?;
+        *last_refresh = Some((refresh_token.to_string(), new_token_info.clone()));
+        Ok(new_token_info)
+    }
+
+    /// Spawns a task that refreshes the stored tokens every `interval` and saves the new pair to
+    /// the token store.
+    ///
+    /// Meant for processes that keep tokens fresh for others reading the same store; a
+    /// `SchwabApi` already refreshes its own tokens when they expire. Pick an interval shorter
+    /// than the 30 minute access token lifetime. The first refresh happens after one interval.
+    ///
+    /// A failed refresh is retried with exponential backoff as `RetryConfig::default` prescribes,
+    /// and if every retry fails the task waits for the next interval. The task ends on
+    /// `SchwabError::RefreshTokenExpired` or an empty token store, since only a new
+    /// authorization helps then, or once `RefreshShutdown::shutdown` is called.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_key` - The application key (Client ID).
+    /// * `secret` - The application secret (Client Secret).
+    /// * `interval` - How often to refresh.
+    pub fn spawn_refresh_task(
+        self,
+        app_key: impl Into<String>,
+        secret: impl Into<String>,
+        interval: time::Duration,
+    ) -> (JoinHandle<()>, RefreshShutdown) {
+        let (sender, mut receiver) = watch::channel(false);
+        let app_key = app_key.into();
+        let secret = secret.into();
+
+        let handle = tokio::spawn(async move {
+            let mut ticks = interval_at(tokio::time::Instant::now() + interval, interval);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let shutdown = async move {
+                // A dropped `RefreshShutdown` never signals, so keep waiting for an abort.
+                if receiver.wait_for(|stop| *stop).await.is_err() {
+                    std::future::pending::<()>().await;
+                }
+            };
+            tokio::pin!(shutdown);
+
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = ticks.tick() => {}
+                }
+
+                let retry = RetryConfig::default();
+                let mut attempt = 0;
+                loop {
+                    match self.refresh_stored_tokens(&app_key, &secret).await {
+                        Ok(()) => break,
+                        Err(
+                            e @ (SchwabError::RefreshTokenExpired { .. } | SchwabError::Config(_)),
+                        ) => {
+                            warn!("Stopping the token refresh task: {}", e);
+                            return;
+                        }
+                        Err(e) if attempt < retry.max_retries => {
+                            let delay = retry.delay_for(attempt, None);
+                            warn!("Token refresh failed, retrying in {:?}: {}", delay, e);
+                            attempt += 1;
+                            tokio::select! {
+                                _ = &mut shutdown => return,
+                                _ = sleep(delay) => {}
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Token refresh failed, waiting for the next interval: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+            info!("Token refresh task stopped.");
+        });
+
+        (handle, RefreshShutdown { sender })
+    }
+
+    /// Refreshes the tokens in the token store and saves the new pair in their place.
+    ///
+    /// An empty store is reported as `SchwabError::Config`.
+    async fn refresh_stored_tokens(&self, app_key: &str, secret: &str) -> Result<(), SchwabError> {
+        let token_info = self.token_store.load().await?.ok_or_else(|| {
+            SchwabError::Config(
+                "No tokens found in token store. Run SchwabAuth::authorize first.".to_string(),
+            )
+        })?;
+        let new_token_info = self
+            .refresh_tokens(app_key, secret, &token_info.refresh_token)
+            .await?;
+        self.token_store.save(&new_token_info).await?;
+        info!("Refreshed and stored new tokens.");
+        Ok(())
+    }
+
+    /// Sends the refresh token request to Schwab.
+    async fn request_refresh(
+        &self,
+        app_key: &str,
+        secret: &str,
+        refresh_token: &str,
+    ) -> Result<StoredTokenInfo, SchwabError> {
         let headers = self.construct_headers(app_key, secret);
         let payload = self.construct_refresh_payload(refresh_token.to_string());
 
-        let response = self
+        let request = self
             .reqwest_client
-            .post(SCHWAB_TOKEN_URL)
+            .post(&self.endpoints.token)
             .headers(headers)
             .form(&payload)
-            .send()
-            .await?;
+            .build()?;
+        let response = self.transport.execute(request).await?;
 
         let status = response.status();
         info!("Refresh tokens response status: {:?}", status);
 
         if status.is_success() {
             info!("Retrieved new tokens successfully using refresh token.");
-            let new_token_info: StoredTokenInfo = response.json().await?;
+            let mut new_token_info: StoredTokenInfo = response.json().await?;
+            new_token_info.obtained_at = Utc::now();
             Ok(new_token_info)
         } else {
             let error_bytes = response.bytes().await?;
             let error_text = Self::format_error_body_for_logs(&error_bytes);
 
-            // Schwab returns HTTP 400 when the refresh token is invalid/expired (often `invalid_grant`).
-            // In this state, a full authorization flow is required to obtain a fresh token pair.
+            // Schwab returns HTTP 400 or 401 with an OAuth error code when the refresh token has
+            // expired (after seven days) or was revoked. Only a full authorization flow helps then.
+            if is_refresh_token_rejection(status, &error_bytes) {
+                info!(
+                    "Refresh token is no longer valid. Re-run SchwabAuth::authorize to obtain a new token pair."
+                );
+                return Err(SchwabError::RefreshTokenExpired { body: error_text });
+            }
             if status == StatusCode::BAD_REQUEST {
-                let message = if error_text.contains("invalid_grant") {
-                    "Refresh token is no longer valid (invalid_grant). Re-run SchwabAuth::authorize to obtain a new token pair."
-                } else {
-                    "Refresh token request returned HTTP 400. This usually means the refresh token is invalid or expired; re-run SchwabAuth::authorize to obtain a new token pair."
-                };
+                let message = "Refresh token request returned HTTP 400. This usually means the refresh token is invalid or expired; re-run SchwabAuth::authorize to obtain a new token pair.";
                 info!("{message}");
-                return Err(anyhow::anyhow!(
-                    "{} Raw response body: {}",
-                    message,
-                    error_text
-                ));
+                return Err(SchwabError::Auth {
+                    status,
+                    body: format!("{} Raw response body: {}", message, error_text),
+                });
             }
 
             info!("Failed to refresh tokens: {}", error_text);
-            Err(anyhow::anyhow!(
-                "Failed to refresh tokens (status {}): {}",
+            Err(SchwabError::Auth {
                 status,
-                error_text
-            ))
+                body: error_text,
+            })
         }
     }
 
@@ -217,18 +740,18 @@ impl SchwabAuth {
     ///
     /// # Returns
     ///
-    /// A `Result` containing the extracted authorization code as a `String`, or an `anyhow::Error` if the code cannot be found.
-    fn extract_auth_code(&self, url: &str) -> anyhow::Result<String> {
-        let code_start = url
-            .find("code=")
-            .ok_or_else(|| anyhow::anyhow!("'code=' not found in URL"))?;
-        let code_end = url.find("&").unwrap_or(url.len()); // Use end of string if no space character
+    /// A `Result` containing the extracted authorization code as a `String`, or a `SchwabError` if the code cannot be found.
+    fn extract_auth_code(&self, url: &str) -> Result<String, SchwabError> {
+        let url = Url::parse(url.trim())
+            .map_err(|e| SchwabError::InvalidInput(format!("Invalid redirect URL: {}", e)))?;
 
-        let code = url[code_start + 5..code_end].to_string();
-
-        // The code ends with a special character, we must re-add the '@' which is encoded as %40
-        let decoded_code = code.replace("%40", "@");
-        Ok(decoded_code)
+        // `query_pairs` percent-decodes the value, restoring the trailing '@' (%40) and any other
+        // encoded characters.
+        url.query_pairs()
+            .find(|(key, _)| key == "code")
+            .map(|(_, code)| code.into_owned())
+            .filter(|code| !code.is_empty())
+            .ok_or_else(|| SchwabError::InvalidInput("'code' not found in URL".to_string()))
     }
 
     /// Constructs the necessary `HeaderMap` for authentication requests.
@@ -317,28 +840,44 @@ impl SchwabAuth {
     ///
     /// # Returns
     ///
-    /// A `Result` containing a `serde_json::Value` with the token response, or an `anyhow::Error` if the request fails.
+    /// A `Result` containing a `serde_json::Value` with the token response, or a `SchwabError` if the request fails.
     async fn retrieve_tokens(
         &self,
         headers: HeaderMap,
         payload: AuthRequestPayload,
-    ) -> anyhow::Result<Value> {
+    ) -> Result<Value, SchwabError> {
         // Send the POST request to the token URL.
-        let init_token_response = self
+        let request = self
             .reqwest_client
-            .post(SCHWAB_TOKEN_URL)
+            .post(&self.endpoints.token)
             .headers(headers)
             .form(&payload) // Use .form() for URL-encoded data
-            .send()
-            .await?;
+            .build()?;
+        let init_token_response = self.transport.execute(request).await?;
 
         info!("Response: {:?}", init_token_response);
 
         // Check if the request was successful.
-        if !init_token_response.status().is_success() {
+        let status = init_token_response.status();
+        if !status.is_success() {
             let error_bytes = init_token_response.bytes().await?;
             let error_text = Self::format_error_body_for_logs(&error_bytes);
-            return Err(anyhow::anyhow!("Failed to retrieve tokens: {}", error_text));
+
+            // A redirect URI that differs from the one used to obtain the code, or from the app's
+            // registered callback, is rejected with a 4xx and no further explanation.
+            if status.is_client_error() {
+                return Err(SchwabError::Auth {
+                    status,
+                    body: format!(
+                        "Token exchange was rejected. Check that the redirect URI `{}` exactly matches the callback URL registered for your app. Raw response body: {}",
+                        payload.redirect_uri, error_text
+                    ),
+                });
+            }
+            return Err(SchwabError::Auth {
+                status,
+                body: error_text,
+            });
         }
 
         // Parse the JSON response.
@@ -352,3 +891,174 @@ impl SchwabAuth {
         format!("utf8={utf8} | base64={base64_body}")
     }
 }
+
+/// Returns `true` if a failed refresh response says the refresh token itself was rejected, as
+/// opposed to a problem with the app credentials or the request.
+fn is_refresh_token_rejection(status: StatusCode, body: &[u8]) -> bool {
+    #[derive(Deserialize)]
+    struct OAuthError {
+        error: String,
+    }
+
+    if status != StatusCode::BAD_REQUEST && status != StatusCode::UNAUTHORIZED {
+        return false;
+    }
+    serde_json::from_slice::<OAuthError>(body).is_ok_and(|body| {
+        matches!(
+            body.error.as_str(),
+            "invalid_grant" | "refresh_token_authentication_error" | "invalid_token"
+        )
+    })
+}
+
+/// Opens `url` in the platform's default browser.
+fn open_in_browser(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        process::Command::new("open")
+    } else {
+        process::Command::new("xdg-open")
+    };
+    command.arg(url).spawn().map(|_| ())
+}
+
+/// Builds the URL the user opens to log in and grant the application access.
+fn authorization_url(
+    authorize_url: &str,
+    app_key: &str,
+    scope: Scope,
+    redirect_uri: &str,
+) -> String {
+    format!(
+        "{}?response_type=code&client_id={}&scope={}&redirect_uri={}",
+        authorize_url, app_key, scope, redirect_uri
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::{Method, header::AUTHORIZATION};
+
+    use crate::schwab::{
+        schwab_api::SchwabApi, token_store::InMemoryTokenStore, transport::MockTransport,
+    };
+
+    #[test]
+    fn authorization_url_carries_the_requested_scope() {
+        let url = |scope| {
+            authorization_url(
+                "https://api.schwabapi.com/v1/oauth/authorize",
+                "app-key",
+                scope,
+                "https://127.0.0.1",
+            )
+        };
+
+        assert_eq!(
+            url(Scope::Trade),
+            "https://api.schwabapi.com/v1/oauth/authorize?response_type=code&client_id=app-key\
+             &scope=api&redirect_uri=https://127.0.0.1"
+        );
+        assert!(url(Scope::ReadOnly).contains("&scope=readonly&"));
+    }
+
+    #[test]
+    fn auth_code_is_read_from_realistic_redirect_urls() {
+        let auth = SchwabAuth::default();
+        let code = "C0.b2F1dGgyLmNkYy5zY2h3YWIuY29t.oQBN2cy-y5yLzTh2wCEmJwRZeqVn5uc@";
+
+        let cases = [
+            "https://127.0.0.1/?code=C0.b2F1dGgyLmNkYy5zY2h3YWIuY29t.oQBN2cy-y5yLzTh2wCEmJwRZeqVn5uc%40&session=5a2b8e1c-7d3f-4f0e-9c61-2b7d8a3e4f50",
+            "https://127.0.0.1/?session=5a2b8e1c-7d3f-4f0e-9c61-2b7d8a3e4f50&code=C0.b2F1dGgyLmNkYy5zY2h3YWIuY29t.oQBN2cy-y5yLzTh2wCEmJwRZeqVn5uc%40",
+            "https://127.0.0.1/?code=C0.b2F1dGgyLmNkYy5zY2h3YWIuY29t.oQBN2cy-y5yLzTh2wCEmJwRZeqVn5uc@",
+            "  https://127.0.0.1/?code=C0.b2F1dGgyLmNkYy5zY2h3YWIuY29t.oQBN2cy-y5yLzTh2wCEmJwRZeqVn5uc%40\n",
+        ];
+        for url in cases {
+            assert_eq!(auth.extract_auth_code(url).unwrap(), code, "{}", url);
+        }
+    }
+
+    #[test]
+    fn auth_code_is_fully_percent_decoded() {
+        let auth = SchwabAuth::default();
+
+        let code = auth
+            .extract_auth_code("https://127.0.0.1/?code=C0%2Ea%2Fb%2Bc%3Dd%26e%40&session=abc")
+            .unwrap();
+
+        assert_eq!(code, "C0.a/b+c=d&e@");
+    }
+
+    #[test]
+    fn auth_code_extraction_rejects_urls_without_a_code() {
+        let auth = SchwabAuth::default();
+
+        for url in [
+            "https://127.0.0.1/?session=abc",
+            "https://127.0.0.1/?code=&session=abc",
+            "https://127.0.0.1/?error=access_denied",
+            "not a url",
+        ] {
+            assert!(
+                matches!(
+                    auth.extract_auth_code(url),
+                    Err(SchwabError::InvalidInput(_))
+                ),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn authorized_tokens_are_shared_through_an_in_memory_store() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .respond(
+                StatusCode::OK,
+                r#"{
+                    "access_token": "issued-access-token",
+                    "expires_in": 1800,
+                    "id_token": "issued-id-token",
+                    "refresh_token": "issued-refresh-token",
+                    "scope": "api",
+                    "token_type": "Bearer"
+                }"#,
+            )
+            .respond(StatusCode::OK, "{}");
+        let store = InMemoryTokenStore::new();
+        let auth = SchwabAuth::with_token_store(Arc::new(Client::new()), Arc::new(store.clone()))
+            .with_transport(transport.clone());
+
+        let issued = auth
+            .exchange_code("app-key", "app-secret", REDIRECT_URI, "auth-code@")
+            .await
+            .unwrap();
+        let api = SchwabApi::builder()
+            .app_key("app-key")
+            .app_secret("app-secret")
+            .token_store(Arc::new(store.clone()))
+            .transport(transport.clone())
+            .build()
+            .await
+            .unwrap();
+        api.get_quotes(vec!["AAPL".to_string()], None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(issued.access_token, "issued-access-token");
+        let requests = transport.requests();
+        assert_eq!(requests[0].method, Method::POST);
+        assert_eq!(requests[0].url.as_str(), SchwabEndpoints::default().token);
+        assert_eq!(
+            requests[1].headers[AUTHORIZATION],
+            "Bearer issued-access-token"
+        );
+        let stored = store.load().await.unwrap().unwrap();
+        assert_eq!(stored.refresh_token, "issued-refresh-token");
+    }
+}
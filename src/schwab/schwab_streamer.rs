@@ -1,37 +1,46 @@
 use std::{
     collections::HashMap,
-    fmt,
+    fmt, iter,
+    ops::RangeInclusive,
     sync::{
-        Arc,
+        Arc, Weak,
         atomic::{AtomicBool, AtomicI64, Ordering},
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use anyhow::anyhow;
 use chrono::Utc;
-use futures_util::{SinkExt, StreamExt, stream::SplitSink};
-use serde::Deserialize;
-use serde_json::{Value, json};
+use futures_util::{
+    SinkExt, StreamExt,
+    stream::{SplitSink, SplitStream},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value, json};
 use tokio::{
     net::TcpStream,
-    sync::{Mutex, mpsc},
+    sync::{
+        Mutex, Notify,
+        mpsc::{self, error::TrySendError},
+    },
     task::JoinHandle,
-    time::timeout,
+    time::{interval, sleep, timeout},
 };
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
 use tracing::{debug, warn, trace};
 
 use crate::{
     schwab::{
-        common::SCHWAB_STREAMER_API_URL,
+        error::SchwabError,
+        message_stream::MessageStream,
         models::{
             streamer::{
-                self, LevelOneEquitiesResponse, LevelOneForexField, LevelOneForexResponse, LevelOneFuturesField, LevelOneFuturesOptionsField, LevelOneFuturesOptionsResponse, LevelOneFuturesResponse, LevelOneOptionsField, LevelOneOptionsResponse, StreamerMessage
+                self, AccountActivityResponse, AdminResponse, BookField, BookResponse, ChartEquityField, ChartEquityResponse, LevelOneEquitiesResponse, LevelOneForexField, LevelOneForexResponse, LevelOneFuturesField, LevelOneFuturesOptionsField, LevelOneFuturesOptionsResponse, LevelOneFuturesResponse, LevelOneOptionsField, LevelOneOptionsResponse, StreamerMessage
             },
-            trader::UserPreferencesResponse,
+            trader::{StreamerInfo, UserPreferencesResponse},
         },
+        schwab_api::QuoteFields,
     },
+    util::dedup_ordered,
     SchwabApi,
 };
 
@@ -43,6 +52,7 @@ pub enum Command {
     View,
     Login,
     Logout,
+    Qos,
     #[default]
     Unknown,
 }
@@ -57,6 +67,7 @@ impl fmt::Display for Command {
             Command::View => write!(f, "VIEW"),
             Command::Login => write!(f, "LOGIN"),
             Command::Logout => write!(f, "LOGOUT"),
+            Command::Qos => write!(f, "QOS"),
             Command::Unknown => write!(f, "UNKNOWN"),
         }
     }
@@ -71,6 +82,7 @@ impl From<&str> for Command {
             "VIEW" => Command::View,
             "LOGIN" => Command::Login,
             "LOGOUT" => Command::Logout,
+            "QOS" => Command::Qos,
             _ => Command::Unknown,
         }
     }
@@ -86,14 +98,20 @@ impl<'de> serde::Deserialize<'de> for Command {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[derive(Default, Debug, Eq, PartialEq, Hash, Clone)]
 pub enum Service {
     LevelOneOptions,
     LevelOneEquities,
     LevelOneFutures,
     LevelOneFuturesOptions,
     LevelOneForex,
+    ChartEquity,
+    NasdaqBook,
+    NyseBook,
+    OptionsBook,
+    AcctActivity,
     Admin,
+    #[default]
     Unknown,
 }
 
@@ -105,6 +123,11 @@ impl From<&str> for Service {
             "LEVELONE_FUTURES" => Service::LevelOneFutures,
             "LEVELONE_FUTURES_OPTIONS" => Service::LevelOneFuturesOptions,
             "LEVELONE_FOREX" => Service::LevelOneForex,
+            "CHART_EQUITY" => Service::ChartEquity,
+            "NASDAQ_BOOK" => Service::NasdaqBook,
+            "NYSE_BOOK" => Service::NyseBook,
+            "OPTIONS_BOOK" => Service::OptionsBook,
+            "ACCT_ACTIVITY" => Service::AcctActivity,
             "ADMIN" => Service::Admin,
             _ => Service::Unknown,
         }
@@ -119,6 +142,11 @@ impl fmt::Display for Service {
             Service::LevelOneEquities => write!(f, "LEVELONE_EQUITIES"),
             Service::LevelOneFuturesOptions => write!(f, "LEVELONE_FUTURES_OPTIONS"),
             Service::LevelOneForex => write!(f, "LEVELONE_FOREX"),
+            Service::ChartEquity => write!(f, "CHART_EQUITY"),
+            Service::NasdaqBook => write!(f, "NASDAQ_BOOK"),
+            Service::NyseBook => write!(f, "NYSE_BOOK"),
+            Service::OptionsBook => write!(f, "OPTIONS_BOOK"),
+            Service::AcctActivity => write!(f, "ACCT_ACTIVITY"),
             Service::LevelOneFutures => write!(f, "LEVELONE_FUTURES"),
             Service::Unknown => write!(f, "UNKNOWN"),
         }
@@ -135,6 +163,66 @@ impl<'de> serde::Deserialize<'de> for Service {
     }
 }
 
+impl serde::Serialize for Service {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// How often the streamer sends batched updates, traded off against bandwidth.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum QosLevel {
+    /// Every 500 milliseconds.
+    #[default]
+    Express,
+    /// Every 750 milliseconds.
+    RealTime,
+    /// Every second.
+    Fast,
+    /// Every 1.5 seconds.
+    Moderate,
+    /// Every 3 seconds.
+    Slow,
+    /// Every 5 seconds.
+    Delayed,
+}
+
+impl fmt::Display for QosLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QosLevel::Express => write!(f, "0"),
+            QosLevel::RealTime => write!(f, "1"),
+            QosLevel::Fast => write!(f, "2"),
+            QosLevel::Moderate => write!(f, "3"),
+            QosLevel::Slow => write!(f, "4"),
+            QosLevel::Delayed => write!(f, "5"),
+        }
+    }
+}
+
+/// What the streamer does when the message channel is full because the receiver is not keeping
+/// up.
+///
+/// A single task reads the WebSocket and fills the channel, so while it waits for room nothing
+/// else is read from the connection, heartbeats included. [`Backpressure::Block`] never loses an
+/// update but lets a slow consumer add latency to every symbol, and a long enough stall trips
+/// the stale connection check. [`Backpressure::DropNewest`] keeps the connection read at the
+/// server's pace and keeps latency low, at the cost of skipping updates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Backpressure {
+    /// Wait for the receiver to make room. No message is ever dropped.
+    #[default]
+    Block,
+    /// Drop market data and account activity updates that do not fit, then report how many
+    /// were dropped with a [`StreamerMessage::Lagged`] once there is room again. Connection,
+    /// admin and subscription messages, [`StreamerMessage::FutureRolled`] included, are never
+    /// dropped and wait for room as with [`Backpressure::Block`].
+    DropNewest,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(tag = "service", content = "content")]
 enum StreamerData {
@@ -148,6 +236,16 @@ enum StreamerData {
     LevelOneFuturesOptions(Vec<LevelOneFuturesOptionsResponse>),
     #[serde(rename = "LEVELONE_FOREX")]
     LevelOneForex(Vec<LevelOneForexResponse>),
+    #[serde(rename = "CHART_EQUITY")]
+    ChartEquity(Vec<ChartEquityResponse>),
+    #[serde(rename = "NASDAQ_BOOK")]
+    NasdaqBook(Vec<BookResponse>),
+    #[serde(rename = "NYSE_BOOK")]
+    NyseBook(Vec<BookResponse>),
+    #[serde(rename = "OPTIONS_BOOK")]
+    OptionsBook(Vec<BookResponse>),
+    #[serde(rename = "ACCT_ACTIVITY")]
+    AcctActivity(Vec<AccountActivityResponse>),
     #[serde(rename = "ADMIN")]
     Admin(()),
 }
@@ -175,6 +273,25 @@ impl From<StreamerData> for Vec<StreamerMessage> {
                 .into_iter()
                 .map(StreamerMessage::LevelOneForex)
                 .collect(),
+            StreamerData::ChartEquity(content) => content
+                .into_iter()
+                .map(StreamerMessage::ChartEquity)
+                .collect(),
+            StreamerData::NasdaqBook(content) => content
+                .into_iter()
+                .map(StreamerMessage::NasdaqBook)
+                .collect(),
+            StreamerData::NyseBook(content) => {
+                content.into_iter().map(StreamerMessage::NyseBook).collect()
+            }
+            StreamerData::OptionsBook(content) => content
+                .into_iter()
+                .map(StreamerMessage::OptionsBook)
+                .collect(),
+            StreamerData::AcctActivity(content) => content
+                .into_iter()
+                .map(StreamerMessage::AccountActivity)
+                .collect(),
             StreamerData::Admin(()) => {
                 tracing::warn!("Received unhandled admin message");
                 vec![]
@@ -183,18 +300,105 @@ impl From<StreamerData> for Vec<StreamerMessage> {
     }
 }
 
+/// The response code Schwab returns for a LOGIN when the maximum number of streamer connections
+/// for the account has been reached, typically because an earlier session was never logged out.
+const DUPLICATE_SESSION_CODE: i64 = 12;
+
+/// How long the connection may stay silent before it is reconnected, unless configured otherwise.
+/// Schwab sends a heartbeat roughly every ten seconds on an idle connection.
+const DEFAULT_STALE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The capacity of the message channel, unless configured otherwise.
+const DEFAULT_CHANNEL_CAPACITY: usize = 100;
+
+/// The number of keys sent in a single SUBS, ADD or UNSUBS frame, unless configured otherwise.
+/// Schwab rejects requests that name too many keys at once.
+const DEFAULT_MAX_KEYS_PER_REQUEST: usize = 500;
+
+/// How long `SchwabStreamer::stop` waits for the LOGOUT to be acknowledged before closing the
+/// connection anyway.
+const LOGOUT_ACK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Returns the server message if `login_response` is a duplicate session rejection.
+fn duplicate_session_message(login_response: &Option<AdminResponse>) -> Option<String> {
+    match login_response {
+        Some(AdminResponse::DuplicateSession { message }) => Some(message.clone()),
+        _ => None,
+    }
+}
+
 #[derive(Deserialize, Debug)]
 struct StreamerResponse {
+    #[serde(default)]
+    service: Service,
     command: Command,
+    #[serde(rename = "requestid")]
+    request_id: Option<Value>,
     content: Option<serde_json::Value>,
 }
 
+impl StreamerResponse {
+    /// Returns the id of the request this responds to. Schwab echoes it back as a string.
+    fn request_id(&self) -> Option<i64> {
+        match self.request_id.as_ref()? {
+            Value::Number(id) => id.as_i64(),
+            Value::String(id) => id.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the response code and message from the response content, if present.
+    fn code_and_message(&self) -> Option<(i64, String)> {
+        let content = self.content.as_ref()?;
+        let code = content.get("code").and_then(Value::as_i64)?;
+        let message = content
+            .get("msg")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        Some((code, message))
+    }
+
+    /// Parses an ADMIN service response into a typed `AdminResponse`.
+    fn admin_response(&self) -> Option<AdminResponse> {
+        if self.service != Service::Admin {
+            return None;
+        }
+        let (code, message) = self.code_and_message()?;
+        if self.command == Command::Login && code == DUPLICATE_SESSION_CODE {
+            return Some(AdminResponse::DuplicateSession { message });
+        }
+        if code != 0 {
+            return Some(AdminResponse::Error {
+                command: self.command.to_string(),
+                code,
+                message,
+            });
+        }
+        match self.command {
+            Command::Login => Some(AdminResponse::LoggedIn),
+            Command::Logout => Some(AdminResponse::LoggedOut),
+            Command::Qos => Some(AdminResponse::QosChanged { message }),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 struct TopLevelMessage {
     #[serde(default)]
     response: Vec<StreamerResponse>,
     #[serde(default)]
     data: Vec<StreamerData>,
+    #[serde(default)]
+    notify: Vec<StreamerNotify>,
+}
+
+/// An entry of a `notify` frame. Schwab sends one with a `heartbeat` (the server time in
+/// milliseconds since the epoch) every few seconds while the connection is idle.
+#[derive(Deserialize, Debug)]
+struct StreamerNotify {
+    heartbeat: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -216,6 +420,23 @@ impl StreamRequest {
     }
 }
 
+/// The subscriptions tracked by a `SchwabStreamer`, exported with
+/// `SchwabStreamer::export_subscriptions` so they can be saved and restored after a restart.
+///
+/// Serializes to JSON as a map of service names to the subscribed keys and their field
+/// indexes, e.g. `{"subscriptions":{"LEVELONE_EQUITIES":{"AAPL":["0","1","2"]}}}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionSnapshot {
+    pub subscriptions: HashMap<Service, HashMap<String, Vec<String>>>,
+}
+
+type WsReader = SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>;
+
+/// The channels registered through `SchwabStreamer::subscribe_symbol`, keyed by symbol.
+///
+/// A std mutex, since it is only held briefly and `subscribe_symbol` is not async.
+type SymbolRoutes = Arc<std::sync::Mutex<HashMap<String, Vec<mpsc::Sender<StreamerMessage>>>>>;
+
 #[derive(Debug)]
 struct SchwabStreamerInner {
     schwab_api: SchwabApi,
@@ -223,9 +444,52 @@ struct SchwabStreamerInner {
     writer: Option<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>,
     listener_handle: Option<Arc<JoinHandle<()>>>,
     is_active: Arc<AtomicBool>,
+    /// Futures roots followed across rolls, mapped to the contract currently subscribed.
+    continuous_futures: HashMap<String, String>,
+    /// How long to wait for a SUBS or ADD to be acknowledged. `None` disables tracking.
+    subscription_ack_timeout: Option<Duration>,
+    /// The most keys written in one frame; larger requests are split.
+    max_keys_per_request: usize,
+    /// SUBS and ADD requests that have not been acknowledged yet, keyed by request id.
+    pending_acks: HashMap<i64, PendingAck>,
+    /// VIEW requests waiting for their response, keyed by request id. The new fields are only
+    /// recorded once the server accepts them.
+    pending_views: HashMap<i64, StreamRequest>,
+    /// The last known fields of every Level 1 symbol, when updates are merged.
+    snapshots: HashMap<(Service, String), Map<String, Value>>,
+    /// Notified when a LOGOUT is acknowledged.
+    logout_ack: Arc<Notify>,
+    /// The QoS level sent with every LOGIN, kept in sync with `SchwabStreamer::set_qos`.
+    qos_level: QosLevel,
+    /// When the last frame of any kind, heartbeats included, was received.
+    last_message_at: Instant,
+}
+
+/// A subscription request waiting for its acknowledgment.
+#[derive(Debug)]
+struct PendingAck {
+    request: StreamRequest,
+    sent_at: Instant,
+    retried: bool,
+}
+
+impl Drop for SchwabStreamerInner {
+    /// Stops the listener task once the last handle to the streamer is dropped, rather than when
+    /// it next wakes up.
+    fn drop(&mut self) {
+        if let Some(handle) = self.listener_handle.take() {
+            handle.abort();
+        }
+    }
 }
 
 impl SchwabStreamerInner {
+    /// Applies a subscription command to the tracked subscriptions, mirroring how Schwab applies
+    /// it to the session.
+    ///
+    /// ADD adds its keys and merges its fields into those already tracked for each key. SUBS
+    /// replaces the whole subscription of the service, dropping keys it does not name. UNSUBS
+    /// forgets its keys, and VIEW sets the fields of every key of the service.
     fn record_request(&mut self, stream_request: &StreamRequest) {
         let service_map = self
             .subscriptions
@@ -242,6 +506,16 @@ impl SchwabStreamerInner {
                 }
             }
             Command::Subs => {
+                let dropped: Vec<String> = service_map
+                    .keys()
+                    .filter(|key| !stream_request.keys.contains(key))
+                    .cloned()
+                    .collect();
+                for key in dropped {
+                    service_map.remove(&key);
+                    self.snapshots
+                        .remove(&(stream_request.service.clone(), key));
+                }
                 for key in &stream_request.keys {
                     service_map.insert(key.clone(), stream_request.fields.clone());
                 }
@@ -249,37 +523,357 @@ impl SchwabStreamerInner {
             Command::Unsubs => {
                 for key in &stream_request.keys {
                     service_map.remove(key);
+                    self.snapshots
+                        .remove(&(stream_request.service.clone(), key.clone()));
+                }
+            }
+            Command::View => {
+                for fields in service_map.values_mut() {
+                    *fields = stream_request.fields.clone();
                 }
             }
             _ => {}
         }
     }
 
-    fn handle_command_response(&mut self, response: &StreamerResponse) {
+    /// Fills in the fields a Level 1 update left out with their last known values.
+    fn merge_update(&mut self, message: StreamerMessage) -> StreamerMessage {
+        match message {
+            StreamerMessage::LevelOneEquity(update) => StreamerMessage::LevelOneEquity(
+                self.merge_snapshot(Service::LevelOneEquities, update.symbol.clone(), update),
+            ),
+            StreamerMessage::LevelOneOption(update) => StreamerMessage::LevelOneOption(
+                self.merge_snapshot(Service::LevelOneOptions, update.symbol.clone(), update),
+            ),
+            StreamerMessage::LevelOneFutures(update) => StreamerMessage::LevelOneFutures(
+                self.merge_snapshot(Service::LevelOneFutures, update.symbol.clone(), update),
+            ),
+            StreamerMessage::LevelOneFuturesOptions(update) => {
+                StreamerMessage::LevelOneFuturesOptions(self.merge_snapshot(
+                    Service::LevelOneFuturesOptions,
+                    update.symbol.clone(),
+                    update,
+                ))
+            }
+            StreamerMessage::LevelOneForex(update) => StreamerMessage::LevelOneForex(
+                self.merge_snapshot(Service::LevelOneForex, update.symbol.clone(), update),
+            ),
+            other => other,
+        }
+    }
+
+    /// Merges `update` into the snapshot of `symbol` with [`streamer::merge_fields`] and returns
+    /// the merged record. Falls back to `update` itself if the record cannot be rebuilt.
+    fn merge_snapshot<T>(&mut self, service: Service, symbol: String, update: T) -> T
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let snapshot = self.snapshots.entry((service, symbol)).or_default();
+        match streamer::merge_fields(snapshot, &update) {
+            Ok(merged) => merged,
+            Err(e) => {
+                warn!("Failed to merge streamer update: {}", e);
+                update
+            }
+        }
+    }
+
+    /// Builds the requests that restore every tracked subscription on a new connection.
+    ///
+    /// Keys of a service that share the same fields are grouped into one request. The first
+    /// request of each service is a SUBS and the rest are ADDs, so nothing is left over from
+    /// before the reconnect.
+    fn resubscribe_requests(&self) -> Vec<StreamRequest> {
+        let mut requests = Vec::new();
+        for (service, keys) in &self.subscriptions {
+            let mut groups: Vec<(&Vec<String>, Vec<String>)> = Vec::new();
+            for (key, fields) in keys {
+                match groups
+                    .iter_mut()
+                    .find(|(group_fields, _)| *group_fields == fields)
+                {
+                    Some((_, group_keys)) => group_keys.push(key.clone()),
+                    None => groups.push((fields, vec![key.clone()])),
+                }
+            }
+
+            for (i, (fields, keys)) in groups.into_iter().enumerate() {
+                let command = if i == 0 { Command::Subs } else { Command::Add };
+                requests.push(StreamRequest::new(
+                    service.clone(),
+                    command,
+                    keys,
+                    fields.clone(),
+                ));
+            }
+        }
+        requests
+    }
+
+    /// Records and writes `requests` to the open connection, splitting those with more keys than
+    /// `max_keys_per_request`.
+    async fn write_requests(
+        &mut self,
+        request_id: &Arc<AtomicI64>,
+        streamer_info: &StreamerInfo,
+        requests: Vec<StreamRequest>,
+    ) -> Result<(), SchwabError> {
+        let max_keys = self.max_keys_per_request;
+        for request in requests
+            .into_iter()
+            .flat_map(|request| split_request(request, max_keys))
+        {
+            self.write_request(request_id, streamer_info, request, false)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Records and writes a single request, tracking SUBS and ADD requests until they are
+    /// acknowledged when a subscription ack timeout is configured.
+    async fn write_request(
+        &mut self,
+        request_id: &Arc<AtomicI64>,
+        streamer_info: &StreamerInfo,
+        request: StreamRequest,
+        retried: bool,
+    ) -> Result<(), SchwabError> {
+        let Some(writer) = self.writer.as_mut() else {
+            return Err(SchwabError::Streamer(
+                "Streamer is not connected. Call start() first.".to_string(),
+            ));
+        };
+
+        // A VIEW applies to every key of the service, so it carries no keys of its own.
+        let parameters = if request.command == Command::View {
+            json!({ "fields": request.fields.join(",") })
+        } else {
+            json!({
+                "keys": request.keys.join(","),
+                "fields": request.fields.join(","),
+            })
+        };
+
+        let request_id_num = request_id.fetch_add(1, Ordering::Relaxed);
+        let message = build_message(
+            request_id_num,
+            streamer_info,
+            request.service.clone(),
+            request.command.clone(),
+            parameters,
+        )?;
+
+        debug!("Sending request: {:?}", message);
+        writer
+            .send(Message::Text(message.to_string().into()))
+            .await?;
+
+        if request.command == Command::View {
+            self.pending_views.insert(request_id_num, request);
+            return Ok(());
+        }
+
+        self.record_request(&request);
+        if self.subscription_ack_timeout.is_some()
+            && matches!(request.command, Command::Subs | Command::Add)
+        {
+            self.pending_acks.insert(
+                request_id_num,
+                PendingAck {
+                    request,
+                    sent_at: Instant::now(),
+                    retried,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Retries every SUBS or ADD that has gone unacknowledged for longer than the configured
+    /// timeout, and returns a `SubscriptionFailed` message for those that were already retried.
+    ///
+    /// A SUBS is retried as an ADD of the same keys and fields.
+    async fn expire_pending_acks(
+        &mut self,
+        request_id: &Arc<AtomicI64>,
+        streamer_info: &StreamerInfo,
+    ) -> Vec<StreamerMessage> {
+        let Some(ack_timeout) = self.subscription_ack_timeout else {
+            return Vec::new();
+        };
+
+        let expired: Vec<i64> = self
+            .pending_acks
+            .iter()
+            .filter(|(_, pending)| pending.sent_at.elapsed() >= ack_timeout)
+            .map(|(id, _)| *id)
+            .collect();
+
+        let mut failures = Vec::new();
+        for id in expired {
+            let Some(pending) = self.pending_acks.remove(&id) else {
+                continue;
+            };
+            if pending.retried {
+                failures.push(subscription_failed(
+                    &pending.request,
+                    format!("No acknowledgment received within {:?}", ack_timeout),
+                ));
+                continue;
+            }
+
+            warn!(
+                "{} request {} for {:?} was not acknowledged within {:?}. Retrying once.",
+                pending.request.command, id, pending.request.keys, ack_timeout
+            );
+            // The SUBS already replaced the tracked subscription when it was sent, and resending
+            // it would undo every request written since, such as the ADDs of a split SUBS.
+            let mut request = pending.request.clone();
+            if request.command == Command::Subs {
+                request.command = Command::Add;
+            }
+            if let Err(e) = self
+                .write_request(request_id, streamer_info, request, true)
+                .await
+            {
+                failures.push(subscription_failed(
+                    &pending.request,
+                    format!("Failed to retry unacknowledged request: {}", e),
+                ));
+            }
+        }
+        failures
+    }
+
+    /// Moves a followed futures subscription to the new front-month contract if `response`
+    /// reports that its root has rolled.
+    ///
+    /// The previous contract is unsubscribed and the new one is added with the same fields,
+    /// leaving every other futures subscription untouched.
+    async fn follow_roll(
+        &mut self,
+        request_id: &Arc<AtomicI64>,
+        streamer_info: &StreamerInfo,
+        response: &LevelOneFuturesResponse,
+    ) -> Result<Option<StreamerMessage>, SchwabError> {
+        let Some(active_symbol) = response.future_active_symbol.as_ref() else {
+            return Ok(None);
+        };
+        let Some((root, subscribed)) = self
+            .continuous_futures
+            .iter_mut()
+            .find(|(_, subscribed)| **subscribed == response.symbol)
+        else {
+            return Ok(None);
+        };
+        if active_symbol.is_empty() || active_symbol == subscribed {
+            return Ok(None);
+        }
+
+        let root = root.clone();
+        let previous = std::mem::replace(subscribed, active_symbol.clone());
+        let fields = self
+            .subscriptions
+            .get(&Service::LevelOneFutures)
+            .and_then(|keys| keys.get(&previous))
+            .cloned()
+            .unwrap_or_else(|| (0..=40).map(|v| v.to_string()).collect());
+
+        debug!("{} rolled from {} to {}", root, previous, active_symbol);
+        self.write_requests(
+            request_id,
+            streamer_info,
+            vec![
+                StreamRequest::new(
+                    Service::LevelOneFutures,
+                    Command::Unsubs,
+                    vec![previous.clone()],
+                    vec![],
+                ),
+                StreamRequest::new(
+                    Service::LevelOneFutures,
+                    Command::Add,
+                    vec![active_symbol.clone()],
+                    fields,
+                ),
+            ],
+        )
+        .await?;
+
+        Ok(Some(StreamerMessage::FutureRolled {
+            root,
+            previous,
+            current: active_symbol.clone(),
+        }))
+    }
+
+    /// Updates internal state from a command response, returning a message to forward to the
+    /// consumer for ADMIN responses.
+    fn handle_command_response(&mut self, response: &StreamerResponse) -> Option<StreamerMessage> {
         match response.command {
-            Command::Add | Command::Subs | Command::Unsubs => {
+            Command::Add | Command::Subs => {
+                debug!("Received subscription response: {:?}", response);
+                let pending = self.pending_acks.remove(&response.request_id()?)?;
+                return match response.code_and_message() {
+                    Some((code, message)) if code != 0 => Some(subscription_failed(
+                        &pending.request,
+                        format!("Rejected with code {}: {}", code, message),
+                    )),
+                    _ => None,
+                };
+            }
+            Command::Unsubs => {
                 debug!("Received subscription response: {:?}", response);
             }
             Command::View => {
-                debug!("View command not supported");
+                debug!("Received view response: {:?}", response);
+                let request = self.pending_views.remove(&response.request_id()?)?;
+                return match response.code_and_message() {
+                    Some((code, message)) if code != 0 => Some(subscription_failed(
+                        &request,
+                        format!("Rejected with code {}: {}", code, message),
+                    )),
+                    _ => {
+                        self.record_request(&request);
+                        None
+                    }
+                };
             }
             Command::Login => {
                 debug!("Received login response: {:?}", response);
-                if let Some(content) = &response.content {
-                    if let Some(code) = content.get("code").and_then(Value::as_u64) {
-                        if code == 0 {
-                            self.is_active.store(true, Ordering::SeqCst);
-                        }
-                    }
-                }
             }
             Command::Logout => {
                 debug!("Received logout response: {:?}", response);
             }
+            Command::Qos => {
+                debug!("Received QOS response: {:?}", response);
+            }
             Command::Unknown => {
                 debug!("Received unknown command response: {:?}", response);
             }
         }
+
+        let admin_response = response.admin_response()?;
+        match &admin_response {
+            AdminResponse::LoggedIn => self.is_active.store(true, Ordering::SeqCst),
+            AdminResponse::LoggedOut => {
+                self.is_active.store(false, Ordering::SeqCst);
+                self.logout_ack.notify_waiters();
+            }
+            AdminResponse::Error {
+                command,
+                code,
+                message,
+            } => warn!("ADMIN {} rejected with code {}: {}", command, code, message),
+            AdminResponse::DuplicateSession { message } => {
+                warn!(
+                    "LOGIN rejected, another session is already connected: {}",
+                    message
+                )
+            }
+            AdminResponse::QosChanged { .. } => {}
+        }
+        Some(StreamerMessage::Admin(admin_response))
     }
 }
 
@@ -287,26 +881,147 @@ impl SchwabStreamerInner {
 pub struct SchwabStreamer {
     inner: Arc<Mutex<SchwabStreamerInner>>,
     request_id: Arc<AtomicI64>,
-    streamer_info: Arc<Value>,
+    streamer_info: Arc<StreamerInfo>,
+    merge_updates: bool,
+    symbol_routes: SymbolRoutes,
 }
 
-#[derive(Debug, Clone, Default)]
+/// A handle to a `SchwabStreamer` that does not keep it alive, held by the listener task.
+struct WeakStreamer {
+    inner: Weak<Mutex<SchwabStreamerInner>>,
+    request_id: Arc<AtomicI64>,
+    streamer_info: Arc<StreamerInfo>,
+    merge_updates: bool,
+    symbol_routes: SymbolRoutes,
+}
+
+impl WeakStreamer {
+    /// Returns the streamer, or `None` once every handle to it has been dropped.
+    fn upgrade(&self) -> Option<SchwabStreamer> {
+        Some(SchwabStreamer {
+            inner: self.inner.upgrade()?,
+            request_id: self.request_id.clone(),
+            streamer_info: self.streamer_info.clone(),
+            merge_updates: self.merge_updates,
+            symbol_routes: self.symbol_routes.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct SchwabStreamerStartConfig {
     pub connect_timeout: Option<Duration>,
     pub login_send_timeout: Option<Duration>,
     pub login_ack_timeout: Option<Duration>,
+    /// If the LOGIN is rejected because another session is still connected, close the
+    /// connection, reconnect and log in once more instead of failing with
+    /// `SchwabError::DuplicateSession`. The earlier session cannot be logged out from a new
+    /// connection, so the second LOGIN only succeeds once Schwab has timed that session out.
+    pub force_relogin_on_duplicate_session: bool,
+    /// How long to wait for each SUBS or ADD to be acknowledged. An unacknowledged request is
+    /// sent once more, a SUBS as an ADD so later requests are kept, and if that is not
+    /// acknowledged either, a [`StreamerMessage::SubscriptionFailed`] is sent on the message
+    /// channel. `None` disables the tracking.
+    pub subscription_ack_timeout: Option<Duration>,
+    /// How often the server sends batched updates. Can be changed after login with
+    /// `SchwabStreamer::set_qos`.
+    pub qos_level: QosLevel,
+    /// How to reconnect when the connection is lost after a successful start. After
+    /// reconnecting, the streamer logs in again, replays every tracked subscription and sends a
+    /// [`StreamerMessage::Reconnected`] on the message channel. Updates sent while disconnected
+    /// are lost.
+    pub reconnect_policy: ReconnectPolicy,
+    /// How long the connection may go without receiving anything, heartbeats included, before
+    /// it is considered dead and reconnected. Catches half-open connections the OS has not torn
+    /// down yet. `None` disables the check. Default: 15 seconds.
+    pub stale_timeout: Option<Duration>,
+    /// The number of messages the channel returned by `start` can hold. Default: 100.
+    pub channel_capacity: usize,
+    /// What to do when the channel is full. Default: [`Backpressure::Block`].
+    pub backpressure: Backpressure,
+    /// The most keys sent in a single SUBS, ADD or UNSUBS frame. Larger requests passed to
+    /// `send` are split into several frames with the same fields; a SUBS is sent as a SUBS for
+    /// the first keys followed by ADDs for the rest, so it still replaces the subscription as a
+    /// whole. The same limit applies to every service. Default: 500.
+    pub max_keys_per_request: usize,
+}
+
+impl Default for SchwabStreamerStartConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: None,
+            login_send_timeout: None,
+            login_ack_timeout: None,
+            force_relogin_on_duplicate_session: false,
+            subscription_ack_timeout: None,
+            qos_level: QosLevel::default(),
+            reconnect_policy: ReconnectPolicy::default(),
+            stale_timeout: Some(DEFAULT_STALE_TIMEOUT),
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            backpressure: Backpressure::default(),
+            max_keys_per_request: DEFAULT_MAX_KEYS_PER_REQUEST,
+        }
+    }
+}
+
+/// Controls how `SchwabStreamer` reconnects after the WebSocket connection drops.
+///
+/// Delays grow exponentially from `base_delay`, capped at `max_delay`. The first attempt is
+/// made after `base_delay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// The maximum number of reconnection attempts per dropped connection. `0` disables
+    /// reconnecting, ending the message stream on the first drop.
+    pub max_attempts: u32,
+    /// The delay before the first attempt, doubled on every subsequent attempt.
+    pub base_delay: Duration,
+    /// The upper bound for any single delay.
+    pub max_delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Returns a `ReconnectPolicy` that never reconnects.
+    pub fn disabled() -> Self {
+        Self {
+            max_attempts: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Computes the delay before the given attempt, starting at `0`.
+    fn delay_for(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
 }
 
 impl SchwabStreamer {
-    pub async fn new(schwab_api: SchwabApi) -> anyhow::Result<Self> {
-        let user_preferences: UserPreferencesResponse = schwab_api.get_preferences().await?;
+    /// Creates a streamer from the connection details in the user preferences of `schwab_api`.
+    ///
+    /// The preferences are fetched through `SchwabApi::get_user_preferences`, so streamers created
+    /// from clones of the same client share a single preferences request.
+    pub async fn new(schwab_api: SchwabApi) -> Result<Self, SchwabError> {
+        let user_preferences: UserPreferencesResponse = schwab_api.get_user_preferences().await?;
 
         let streamer_info = user_preferences
             .streamer_info
             .first()
-            .ok_or_else(|| anyhow!("Streamer info not found in user preferences"))?;
-
-        let streamer_info_value = serde_json::to_value(streamer_info)?;
+            .ok_or_else(|| {
+                SchwabError::Streamer("Streamer info not found in user preferences".to_string())
+            })?
+            .clone();
+        streamer_info.validate()?;
 
         let inner_state = SchwabStreamerInner {
             schwab_api,
@@ -314,175 +1029,587 @@ impl SchwabStreamer {
             writer: None,
             listener_handle: None,
             is_active: Arc::new(AtomicBool::new(false)),
+            continuous_futures: HashMap::new(),
+            subscription_ack_timeout: None,
+            max_keys_per_request: DEFAULT_MAX_KEYS_PER_REQUEST,
+            pending_acks: HashMap::new(),
+            pending_views: HashMap::new(),
+            snapshots: HashMap::new(),
+            logout_ack: Arc::new(Notify::new()),
+            qos_level: QosLevel::default(),
+            last_message_at: Instant::now(),
         };
 
         Ok(Self {
             inner: Arc::new(Mutex::new(inner_state)),
             request_id: Arc::new(AtomicI64::new(0)),
-            streamer_info: Arc::new(streamer_info_value),
+            streamer_info: Arc::new(streamer_info),
+            merge_updates: false,
+            symbol_routes: SymbolRoutes::default(),
         })
     }
 
-    pub async fn default() -> anyhow::Result<Self> {
+    /// Sets whether Level 1 updates are merged into complete records before they are sent.
+    ///
+    /// Schwab only sends the fields that changed since the previous update, so by default most
+    /// fields of a Level 1 message are `None`. With merging enabled, the streamer keeps the last
+    /// known value of every field per service and symbol and fills in the unchanged fields, so
+    /// each message carries the full current record. Chart bars and account activity are always
+    /// complete and are passed through unchanged. Must be set before `start`.
+    ///
+    /// Default: `false`, sending the raw deltas.
+    pub fn with_merged_updates(mut self, merge_updates: bool) -> Self {
+        self.merge_updates = merge_updates;
+        self
+    }
+
+    /// Creates a streamer from a `SchwabApi` built with `SchwabApi::default`, then fetches the
+    /// streamer connection details from the user preferences.
+    ///
+    /// Fails with the same errors as `SchwabApi::default`, or with the error of the preferences
+    /// request.
+    pub async fn default() -> Result<Self, SchwabError> {
         let schwab_api = SchwabApi::default().await?;
         SchwabStreamer::new(schwab_api).await
     }
 
-    pub async fn start(&self) -> anyhow::Result<mpsc::Receiver<StreamerMessage>> {
+    /// Starts the streamer with the default [`SchwabStreamerStartConfig`], returning the channel
+    /// every [`StreamerMessage`] is sent on.
+    ///
+    /// There is no separate channel for connection events: `Connected`, `Disconnected`,
+    /// `Reconnecting`, `Reconnected` and the ADMIN responses arrive on this channel alongside
+    /// the market data. See [`SchwabStreamer::start_with_config`].
+    pub async fn start(&self) -> Result<mpsc::Receiver<StreamerMessage>, SchwabError> {
         self.start_with_config(SchwabStreamerStartConfig::default())
             .await
     }
 
+    /// Starts the streamer like `start`, returning its messages as a `futures::Stream`.
+    ///
+    /// Use [`MessageStream::equities`] and its siblings for a stream of a single message type.
+    pub async fn message_stream(&self) -> Result<MessageStream, SchwabError> {
+        self.start().await.map(MessageStream::from)
+    }
+
+    /// Returns a channel that receives only the updates for `symbol`.
+    ///
+    /// Every data message whose symbol (or, for account activity, subscription key) equals
+    /// `symbol` is copied to the channel in addition to being sent on the channel returned by
+    /// `start`, so one task can run per instrument. The symbol must still be subscribed
+    /// separately. Several channels may be registered for the same symbol, and they keep
+    /// receiving across reconnects.
+    ///
+    /// Dropping the receiver unregisters the channel. A channel that is full when an update
+    /// arrives misses that update rather than stalling the stream.
+    pub fn subscribe_symbol(&self, symbol: &str) -> mpsc::Receiver<StreamerMessage> {
+        let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_CAPACITY);
+        let mut routes = self
+            .symbol_routes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        routes.retain(|_, senders| {
+            senders.retain(|sender| !sender.is_closed());
+            !senders.is_empty()
+        });
+        routes.entry(symbol.to_string()).or_default().push(tx);
+        rx
+    }
+
+    /// Connects to the streamer, logs in and spawns the task that forwards incoming messages.
+    ///
+    /// If the LOGIN is rejected because a previous session for the same account is still
+    /// connected (for example after a crash without a clean LOGOUT), this returns
+    /// `SchwabError::DuplicateSession`. When `force_relogin_on_duplicate_session` is set in the
+    /// config, the streamer instead closes the connection, reconnects and logs in once more, only
+    /// returning the error if the second LOGIN is rejected as well. A LOGOUT cannot end the earlier
+    /// session from a new connection, so the retry only helps once Schwab has timed it out.
+    ///
+    /// Subscriptions that are already tracked, such as those restored with
+    /// `import_subscriptions` or left over from before a `stop`, are sent right after login.
+    ///
+    /// Once started, a dropped connection is re-established as described by
+    /// `config.reconnect_policy`, and the message channel is closed only when reconnecting fails.
     pub async fn start_with_config(
         &self,
         config: SchwabStreamerStartConfig,
-    ) -> anyhow::Result<mpsc::Receiver<StreamerMessage>> {
-        let inner_clone = self.inner.clone();
+    ) -> Result<mpsc::Receiver<StreamerMessage>, SchwabError> {
+        let (tx, rx) = mpsc::channel(config.channel_capacity.max(1));
+
+        {
+            let mut guard = self.inner.lock().await;
+            guard.subscription_ack_timeout = config.subscription_ack_timeout;
+            guard.max_keys_per_request = config.max_keys_per_request.max(1);
+            guard.qos_level = config.qos_level;
+            guard.pending_acks.clear();
+            guard.pending_views.clear();
+        }
+
+        let mut read = self.connect_and_login(&config, &tx).await?;
+        let mut login_response = self.wait_for_login_ack(&config, &mut read, &tx).await?;
+
+        if let Some(message) = duplicate_session_message(&login_response) {
+            if !config.force_relogin_on_duplicate_session {
+                return Err(SchwabError::DuplicateSession(message));
+            }
+
+            warn!(
+                "Streamer LOGIN rejected because another session is connected ({}). Reconnecting and retrying.",
+                message
+            );
+            self.close_connection().await;
+            read = self.connect_and_login(&config, &tx).await?;
+            login_response = self.wait_for_login_ack(&config, &mut read, &tx).await?;
 
-        let (tx, rx) = mpsc::channel(100);
+            if let Some(message) = duplicate_session_message(&login_response) {
+                return Err(SchwabError::DuplicateSession(message));
+            }
+        }
 
-        let mut read = {
+        {
             let mut guard = self.inner.lock().await;
+            let requests = guard.resubscribe_requests();
+            guard
+                .write_requests(&self.request_id, &self.streamer_info, requests)
+                .await?;
+        }
+
+        let streamer = self.downgrade();
+        let listener = tokio::spawn(Self::listen(streamer, read, tx, config));
+
+        self.inner.lock().await.listener_handle = Some(Arc::new(listener));
+        Ok(rx)
+    }
+
+    /// Returns a handle to this streamer that does not keep it alive.
+    fn downgrade(&self) -> WeakStreamer {
+        WeakStreamer {
+            inner: Arc::downgrade(&self.inner),
+            request_id: self.request_id.clone(),
+            streamer_info: self.streamer_info.clone(),
+            merge_updates: self.merge_updates,
+            symbol_routes: self.symbol_routes.clone(),
+        }
+    }
 
-            let token_info = guard.schwab_api.token_info().await;
-            let auth_header = token_info.access_token.as_str();
-
-            let (ws_stream, _response) = if let Some(connect_timeout) = config.connect_timeout {
-                timeout(connect_timeout, connect_async(SCHWAB_STREAMER_API_URL))
-                    .await
-                    .map_err(|_| anyhow!("Timed out connecting to stream API"))?
-                    .expect("Failed to connect to stream API")
-            } else {
-                connect_async(SCHWAB_STREAMER_API_URL)
-                    .await
-                    .expect("Failed to connect to stream API")
+    /// Forwards incoming messages until the receiver is dropped, reconnecting according to
+    /// `config.reconnect_policy` whenever the connection is lost.
+    ///
+    /// Runs on its own task, whose handle the streamer keeps, so it only holds a `WeakStreamer`
+    /// and ends once every handle to the streamer is dropped.
+    async fn listen(
+        streamer: WeakStreamer,
+        mut read: WsReader,
+        tx: mpsc::Sender<StreamerMessage>,
+        config: SchwabStreamerStartConfig,
+    ) {
+        loop {
+            let Some(reason) = Self::forward_messages(
+                &streamer,
+                &mut read,
+                &tx,
+                config.subscription_ack_timeout,
+                config.stale_timeout,
+                config.backpressure,
+            )
+            .await
+            else {
+                debug!("Stream receiver or streamer dropped. Closing listener task.");
+                return;
+            };
+            let Some(this) = streamer.upgrade() else {
+                debug!("Streamer dropped. Closing listener task.");
+                return;
             };
 
-            let (mut write, read) = ws_stream.split();
+            if !this.is_active().await {
+                debug!("Streamer logged out ({}). Closing listener task.", reason);
+                return;
+            }
 
-            let parameters = json!({
-                "qoslevel": "0",
-                "Authorization": auth_header,
-                "SchwabClientChannel": self.streamer_info.get("schwabClientChannel"),
-                "SchwabClientFunctionId": self.streamer_info.get("schwabClientFunctionId"),
-            });
+            warn!("Streamer connection lost: {}", reason);
+            {
+                let mut guard = this.inner.lock().await;
+                guard.writer = None;
+                guard.pending_acks.clear();
+                guard.pending_views.clear();
+                guard.is_active.store(false, Ordering::SeqCst);
+            }
+            if tx
+                .send(StreamerMessage::Disconnected { reason })
+                .await
+                .is_err()
+            {
+                debug!("Stream receiver dropped. Closing listener task.");
+                return;
+            }
 
-            let message = build_message(
-                self.request_id.clone(),
-                self.streamer_info.clone(),
-                Service::Admin,
-                Command::Login,
-                parameters,
-            )?;
-
-            debug!("[{:?}] Sending LOGIN request", Utc::now());
-            if let Some(login_send_timeout) = config.login_send_timeout {
-                timeout(
-                    login_send_timeout,
-                    write.send(Message::Text(message.to_string().into())),
-                )
+            let Some((new_read, attempts)) = this.reconnect(&config, &tx).await else {
+                warn!(
+                    "Streamer did not reconnect after {} attempts. Closing listener task.",
+                    config.reconnect_policy.max_attempts
+                );
+                return;
+            };
+            read = new_read;
+            if tx
+                .send(StreamerMessage::Reconnected { attempts })
                 .await
-                .map_err(|_| anyhow!("Timed out sending LOGIN request"))??;
-            } else {
-                write
-                    .send(Message::Text(message.to_string().into()))
-                    .await?;
+                .is_err()
+            {
+                debug!("Stream receiver dropped. Closing listener task.");
+                return;
             }
+        }
+    }
 
-            guard.writer = Some(write);
-            read
+    /// Copies `message` to the channels registered for its symbol, unregistering those whose
+    /// receiver was dropped.
+    fn route_to_symbol(&self, message: &StreamerMessage) {
+        let Some(symbol) = message.symbol() else {
+            return;
+        };
+        let mut routes = self
+            .symbol_routes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Some(senders) = routes.get_mut(symbol) else {
+            return;
         };
+        senders.retain(|sender| match sender.try_send(message.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                trace!("Symbol channel for {} is full, dropping update", symbol);
+                true
+            }
+            Err(TrySendError::Closed(_)) => false,
+        });
+        if senders.is_empty() {
+            routes.remove(symbol);
+        }
+    }
 
-        let wait_for_login_ack = async {
-            loop {
-                let message_result = read
-                    .next()
-                    .await
-                    .ok_or_else(|| anyhow!("WebSocket stream ended before LOGIN response"))?;
+    /// Forwards messages from `read` to `tx` until the connection ends, or until nothing has
+    /// been received for `stale_timeout`.
+    ///
+    /// Returns why the connection ended, or `None` if the receiver or every handle to the
+    /// streamer was dropped. The streamer is only upgraded while a message or check is handled.
+    async fn forward_messages(
+        streamer: &WeakStreamer,
+        read: &mut WsReader,
+        tx: &mpsc::Sender<StreamerMessage>,
+        ack_timeout: Option<Duration>,
+        stale_timeout: Option<Duration>,
+        backpressure: Backpressure,
+    ) -> Option<String> {
+        let mut dropped = 0;
+        let mut ack_check = interval(
+            ack_timeout
+                .map(|t| (t / 2).max(Duration::from_millis(100)))
+                .unwrap_or(Duration::from_secs(60)),
+        );
+        let mut stale_check = interval(
+            stale_timeout
+                .map(|t| (t / 3).max(Duration::from_millis(100)))
+                .unwrap_or(Duration::from_secs(60)),
+        );
+        streamer.upgrade()?.inner.lock().await.last_message_at = Instant::now();
+        loop {
+            let (this, message_result) = tokio::select! {
+                message_result = read.next() => match message_result {
+                    Some(message_result) => {
+                        let this = streamer.upgrade()?;
+                        this.inner.lock().await.last_message_at = Instant::now();
+                        (this, message_result)
+                    }
+                    None => return Some("connection closed by the server".to_string()),
+                },
+                _ = stale_check.tick(), if stale_timeout.is_some() => {
+                    let this = streamer.upgrade()?;
+                    let silent_for = this.inner.lock().await.last_message_at.elapsed();
+                    match stale_timeout {
+                        Some(stale_timeout) if silent_for >= stale_timeout => {
+                            return Some(format!("no message received for {:?}", silent_for));
+                        }
+                        _ => continue,
+                    }
+                }
+                _ = ack_check.tick(), if ack_timeout.is_some() => {
+                    let this = streamer.upgrade()?;
+                    let failures = this
+                        .inner
+                        .lock()
+                        .await
+                        .expire_pending_acks(&this.request_id, &this.streamer_info)
+                        .await;
+                    for msg in failures {
+                        tx.send(msg).await.ok()?;
+                    }
+                    continue;
+                }
+            };
+            trace!("READER RECEIVED: {:?}", message_result);
+            match message_result {
+                Ok(msg) => {
+                    if let Ok(text) = msg.into_text() {
+                        match serde_json::from_str::<TopLevelMessage>(&text) {
+                            Ok(message) => {
+                                for heartbeat in
+                                    message.notify.iter().filter_map(|n| n.heartbeat.as_ref())
+                                {
+                                    trace!("Streamer heartbeat: {}", heartbeat);
+                                }
 
-                match message_result {
-                    Ok(msg) => {
-                        if let Ok(text) = msg.into_text() {
-                            match serde_json::from_str::<TopLevelMessage>(&text) {
-                                Ok(message) => {
-                                    let mut saw_login_response = false;
-                                    if !message.response.is_empty() {
-                                        let mut guard = inner_clone.lock().await;
-                                        for r in &message.response {
-                                            if r.command == Command::Login {
-                                                saw_login_response = true;
-                                            }
-                                            guard.handle_command_response(r);
-                                        }
+                                let mut admin_messages = Vec::new();
+                                if !message.response.is_empty() {
+                                    let mut guard = this.inner.lock().await;
+                                    for r in &message.response {
+                                        admin_messages.extend(guard.handle_command_response(r));
                                     }
+                                }
 
-                                    if !message.data.is_empty() {
-                                        for streamer_data in message.data {
-                                            let messages: Vec<StreamerMessage> = streamer_data.into();
-                                            for msg in messages {
-                                                if tx.send(msg).await.is_err() {
-                                                    return Err(anyhow!(
-                                                        "Stream receiver dropped before LOGIN ack"
-                                                    ));
-                                                }
-                                            }
-                                        }
+                                for msg in admin_messages {
+                                    tx.send(msg).await.ok()?;
+                                }
+
+                                for streamer_data in message.data {
+                                    let mut messages: Vec<StreamerMessage> = streamer_data.into();
+                                    if this.merge_updates {
+                                        let mut guard = this.inner.lock().await;
+                                        messages = messages
+                                            .into_iter()
+                                            .map(|msg| guard.merge_update(msg))
+                                            .collect();
                                     }
 
-                                    if saw_login_response {
-                                        break Ok(());
+                                    for msg in messages {
+                                        let rolled = match &msg {
+                                            StreamerMessage::LevelOneFutures(response) => {
+                                                let mut guard = this.inner.lock().await;
+                                                guard
+                                                    .follow_roll(
+                                                        &this.request_id,
+                                                        &this.streamer_info,
+                                                        response,
+                                                    )
+                                                    .await
+                                                    .unwrap_or_else(|e| {
+                                                        warn!(
+                                                            "Failed to follow futures roll: {}",
+                                                            e
+                                                        );
+                                                        None
+                                                    })
+                                            }
+                                            _ => None,
+                                        };
+                                        this.route_to_symbol(&msg);
+                                        match backpressure {
+                                            Backpressure::Block => tx.send(msg).await.ok()?,
+                                            Backpressure::DropNewest => {
+                                                try_deliver(tx, msg, &mut dropped)?
+                                            }
+                                        }
+                                        // A roll changes the subscription, so it is never
+                                        // dropped, even with Backpressure::DropNewest.
+                                        if let Some(rolled) = rolled {
+                                            tx.send(rolled).await.ok()?;
+                                        }
                                     }
                                 }
-                                Err(e) => {
-                                    warn!("Failed to deserialize message: {}, error: {}", text, e);
-                                }
+                            }
+                            Err(e) => {
+                                warn!("Failed to deserialize message: {}, error: {}", text, e);
                             }
                         }
                     }
-                    Err(e) => {
-                        return Err(anyhow!("Error reading from WebSocket stream: {}", e));
-                    }
+                }
+                Err(e) => {
+                    return Some(format!("error reading from WebSocket stream: {}", e));
                 }
             }
-        };
+        }
+    }
 
-        if let Some(login_ack_timeout) = config.login_ack_timeout {
-            timeout(login_ack_timeout, wait_for_login_ack)
+    /// Reconnects, logs in and replays the tracked subscriptions, waiting between attempts as
+    /// `config.reconnect_policy` prescribes.
+    ///
+    /// Sends a `StreamerMessage::Reconnecting` before every attempt. Returns the new reader and
+    /// the number of attempts it took, or `None` once every attempt has failed or the receiver
+    /// was dropped.
+    async fn reconnect(
+        &self,
+        config: &SchwabStreamerStartConfig,
+        tx: &mpsc::Sender<StreamerMessage>,
+    ) -> Option<(WsReader, u32)> {
+        let policy = config.reconnect_policy;
+        for attempt in 1..=policy.max_attempts {
+            tx.send(StreamerMessage::Reconnecting { attempt })
+                .await
+                .ok()?;
+            sleep(policy.delay_for(attempt - 1)).await;
+            match self.try_reconnect(config, tx).await {
+                Ok(read) => return Some((read, attempt)),
+                Err(e) => warn!(
+                    "Streamer reconnection attempt {} of {} failed: {}",
+                    attempt, policy.max_attempts, e
+                ),
+            }
+        }
+        None
+    }
+
+    /// Makes a single reconnection attempt. See [`SchwabStreamer::reconnect`].
+    async fn try_reconnect(
+        &self,
+        config: &SchwabStreamerStartConfig,
+        tx: &mpsc::Sender<StreamerMessage>,
+    ) -> Result<WsReader, SchwabError> {
+        let mut read = self.connect_and_login(config, tx).await?;
+        let login_response = self.wait_for_login_ack(config, &mut read, tx).await?;
+        if login_response != Some(AdminResponse::LoggedIn) {
+            self.close_connection().await;
+            // Refresh the access token in case it expired while the connection was down.
+            let schwab_api = self.inner.lock().await.schwab_api.clone();
+            schwab_api.refresh_and_store_token().await?;
+            return Err(SchwabError::Streamer(format!(
+                "LOGIN was not accepted: {:?}",
+                login_response
+            )));
+        }
+
+        let mut guard = self.inner.lock().await;
+        let requests = guard.resubscribe_requests();
+        guard
+            .write_requests(&self.request_id, &self.streamer_info, requests)
+            .await?;
+        Ok(read)
+    }
+
+    /// Opens a new WebSocket connection and sends the LOGIN request, storing the write half.
+    ///
+    /// Sends `StreamerMessage::Connected` once the WebSocket is open and the LOGIN is sent.
+    async fn connect_and_login(
+        &self,
+        config: &SchwabStreamerStartConfig,
+        tx: &mpsc::Sender<StreamerMessage>,
+    ) -> Result<WsReader, SchwabError> {
+        let mut guard = self.inner.lock().await;
+
+        let token_info = guard.schwab_api.token_info().await;
+        let auth_header = token_info.access_token.as_str();
+        let streamer_url = guard.schwab_api.endpoints().streamer.clone();
+
+        let (ws_stream, _response) = if let Some(connect_timeout) = config.connect_timeout {
+            timeout(connect_timeout, connect_async(streamer_url.as_str()))
                 .await
-                .map_err(|_| anyhow!("Timed out waiting for LOGIN response"))??;
+                .map_err(|_| {
+                    SchwabError::Streamer("Timed out connecting to stream API".to_string())
+                })??
         } else {
-            wait_for_login_ack.await?;
+            connect_async(streamer_url.as_str()).await?
+        };
+
+        let (mut write, read) = ws_stream.split();
+
+        let parameters = json!({
+            "qoslevel": guard.qos_level.to_string(),
+            "Authorization": auth_header,
+            "SchwabClientChannel": self.streamer_info.schwab_client_channel,
+            "SchwabClientFunctionId": self.streamer_info.schwab_client_function_id,
+        });
+
+        let message = build_message(
+            self.request_id.fetch_add(1, Ordering::Relaxed),
+            &self.streamer_info,
+            Service::Admin,
+            Command::Login,
+            parameters,
+        )?;
+
+        debug!("[{:?}] Sending LOGIN request", Utc::now());
+        if let Some(login_send_timeout) = config.login_send_timeout {
+            timeout(
+                login_send_timeout,
+                write.send(Message::Text(message.to_string().into())),
+            )
+            .await
+            .map_err(|_| SchwabError::Streamer("Timed out sending LOGIN request".to_string()))??;
+        } else {
+            write
+                .send(Message::Text(message.to_string().into()))
+                .await?;
         }
 
-        let listener = tokio::spawn(async move {
-            while let Some(message_result) = read.next().await {
-                trace!("READER RECEIVED: {:?}", message_result);
+        guard.writer = Some(write);
+        drop(guard);
+
+        // Sent without holding the lock, so a full channel cannot stall other callers. A dropped
+        // receiver is noticed while waiting for the LOGIN response.
+        let _ = tx.send(StreamerMessage::Connected).await;
+        Ok(read)
+    }
+
+    /// Reads messages until the LOGIN response arrives, forwarding everything received in the
+    /// meantime, and returns the typed LOGIN response.
+    async fn wait_for_login_ack(
+        &self,
+        config: &SchwabStreamerStartConfig,
+        read: &mut WsReader,
+        tx: &mpsc::Sender<StreamerMessage>,
+    ) -> Result<Option<AdminResponse>, SchwabError> {
+        let wait_for_login_ack = async {
+            loop {
+                let message_result = read.next().await.ok_or_else(|| {
+                    SchwabError::Streamer(
+                        "WebSocket stream ended before LOGIN response".to_string(),
+                    )
+                })?;
+
                 match message_result {
                     Ok(msg) => {
                         if let Ok(text) = msg.into_text() {
                             match serde_json::from_str::<TopLevelMessage>(&text) {
                                 Ok(message) => {
+                                    let mut login_response = None;
+                                    let mut saw_login_response = false;
+                                    let mut admin_messages = Vec::new();
                                     if !message.response.is_empty() {
-                                        let mut guard = inner_clone.lock().await;
+                                        let mut guard = self.inner.lock().await;
                                         for r in &message.response {
-                                            guard.handle_command_response(r);
+                                            if r.command == Command::Login {
+                                                saw_login_response = true;
+                                                login_response = r.admin_response();
+                                            }
+                                            admin_messages.extend(guard.handle_command_response(r));
+                                        }
+                                    }
+
+                                    for msg in admin_messages {
+                                        if tx.send(msg).await.is_err() {
+                                            return Err(SchwabError::Streamer(
+                                                "Stream receiver dropped before LOGIN ack"
+                                                    .to_string(),
+                                            ));
                                         }
                                     }
 
                                     if !message.data.is_empty() {
                                         for streamer_data in message.data {
                                             let messages: Vec<StreamerMessage> = streamer_data.into();
-
                                             for msg in messages {
                                                 if tx.send(msg).await.is_err() {
-                                                    debug!(
-                                                        "Stream receiver dropped. Closing listener task."
-                                                    );
-                                                    return;
+                                                    return Err(SchwabError::Streamer(
+                                                        "Stream receiver dropped before LOGIN ack"
+                                                            .to_string(),
+                                                    ));
                                                 }
                                             }
                                         }
                                     }
+
+                                    if saw_login_response {
+                                        break Ok(login_response);
+                                    }
                                 }
                                 Err(e) => {
                                     warn!("Failed to deserialize message: {}, error: {}", text, e);
@@ -491,60 +1618,216 @@ impl SchwabStreamer {
                         }
                     }
                     Err(e) => {
-                        warn!("Error reading from WebSocket stream: {}", e);
-                        break;
+                        return Err(e.into());
                     }
                 }
             }
-        });
+        };
 
-        self.inner.lock().await.listener_handle = Some(Arc::new(listener));
-        Ok(rx)
+        if let Some(login_ack_timeout) = config.login_ack_timeout {
+            timeout(login_ack_timeout, wait_for_login_ack)
+                .await
+                .map_err(|_| {
+                    SchwabError::Streamer("Timed out waiting for LOGIN response".to_string())
+                })?
+        } else {
+            wait_for_login_ack.await
+        }
     }
 
-    pub async fn send(&self, requests: Vec<StreamRequest>) -> anyhow::Result<()> {
+    /// Closes the current connection without logging out.
+    ///
+    /// Used after a rejected LOGIN. The rejected connection has no session to log out of, and a
+    /// LOGOUT sent on it cannot end the session it collided with; only Schwab timing that session
+    /// out frees it.
+    async fn close_connection(&self) {
         let mut guard = self.inner.lock().await;
-        if let Some(mut writer) = guard.writer.take() {
-            for request in requests {
-                guard.record_request(&request);
-
-                let parameters = json!({
-                    "keys": request.keys.join(","),
-                    "fields": request.fields.join(","),
-                });
-
-                let message = build_message(
-                    self.request_id.clone(),
-                    self.streamer_info.clone(),
-                    request.service,
-                    request.command,
-                    parameters,
-                )?;
-
-                debug!("Sending request: {:?}", message);
-                writer
-                    .send(Message::Text(message.to_string().into()))
-                    .await?;
-            }
-            // Put the writer back after the loop
-            guard.writer = Some(writer);
-        } else {
-            return Err(anyhow!("Streamer is not connected. Call start() first."));
+        if let Some(mut writer) = guard.writer.take()
+            && let Err(e) = writer.close().await
+        {
+            // The server may already have dropped the connection after rejecting the LOGIN.
+            debug!("Error closing WebSocket after a rejected LOGIN: {}", e);
+        }
+        guard.is_active.store(false, Ordering::SeqCst);
+    }
+
+    pub async fn send(&self, requests: Vec<StreamRequest>) -> Result<(), SchwabError> {
+        let mut guard = self.inner.lock().await;
+        guard
+            .write_requests(&self.request_id, &self.streamer_info, requests)
+            .await
+    }
+
+    /// Sends every tracked subscription again on the open connection.
+    ///
+    /// Reconnects already do this automatically. Call it after reconnecting manually, or when a
+    /// subscription seems to have been dropped by the server. Keys of a service that share the
+    /// same fields are sent together, with a SUBS for the first group of each service and an
+    /// ADD for the rest.
+    pub async fn resubscribe(&self) -> Result<(), SchwabError> {
+        let mut guard = self.inner.lock().await;
+        let requests = guard.resubscribe_requests();
+        guard
+            .write_requests(&self.request_id, &self.streamer_info, requests)
+            .await
+    }
+
+    /// Changes the fields streamed for every subscribed key of `service`, without subscribing
+    /// again.
+    ///
+    /// The field indexes are those of the service's field enum, e.g. `"3"` for
+    /// `LevelOneEquitiesField::LastPrice`. The key field `0` is always included. The tracked
+    /// subscriptions pick up the new fields once the server accepts the VIEW; a rejection is
+    /// reported as a [`StreamerMessage::SubscriptionFailed`] and leaves them unchanged.
+    pub async fn view(&self, service: Service, fields: Vec<String>) -> Result<(), SchwabError> {
+        if fields.is_empty() {
+            return Err(SchwabError::InvalidInput(
+                "A VIEW needs at least one field".to_string(),
+            ));
+        }
+
+        let mut guard = self.inner.lock().await;
+        let fields_as_strings = field_list(&fields, 0..=0);
+        let request = StreamRequest::new(service, Command::View, vec![], fields_as_strings);
+        guard
+            .write_request(&self.request_id, &self.streamer_info, request, false)
+            .await
+    }
+
+    /// Returns a copy of the tracked subscriptions: the subscribed keys of every service, each
+    /// with the field indexes requested for it.
+    ///
+    /// ADD, SUBS and UNSUBS requests are reflected as soon as they are sent, and VIEW requests
+    /// once Schwab accepts them. Useful to check why a symbol stopped updating.
+    pub async fn current_subscriptions(&self) -> HashMap<Service, HashMap<String, Vec<String>>> {
+        self.inner.lock().await.subscriptions.clone()
+    }
+
+    /// Returns a copy of every tracked subscription, to be saved and later restored with
+    /// [`SchwabStreamer::import_subscriptions`].
+    pub async fn export_subscriptions(&self) -> SubscriptionSnapshot {
+        SubscriptionSnapshot {
+            subscriptions: self.inner.lock().await.subscriptions.clone(),
         }
+    }
+
+    /// Replaces the tracked subscriptions with those in `snapshot`, skipping unknown services.
+    ///
+    /// Nothing is sent right away. The subscriptions are sent by the next `start`, or by
+    /// [`SchwabStreamer::resubscribe`] if the streamer is already connected.
+    pub async fn import_subscriptions(&self, snapshot: SubscriptionSnapshot) {
+        let mut guard = self.inner.lock().await;
+        guard.subscriptions = snapshot
+            .subscriptions
+            .into_iter()
+            .filter(|(service, _)| *service != Service::Unknown)
+            .collect();
+        guard.snapshots.clear();
+    }
+
+    /// Changes the QoS level of the logged in session with an ADMIN QOS command.
+    ///
+    /// The server acknowledges the change with an [`AdminResponse::QosChanged`] message. The new
+    /// level is also used when the streamer logs in again after a reconnect.
+    pub async fn set_qos(&self, qos_level: QosLevel) -> Result<(), SchwabError> {
+        let mut guard = self.inner.lock().await;
+        let Some(writer) = guard.writer.as_mut() else {
+            return Err(SchwabError::Streamer(
+                "Streamer is not connected. Call start() first.".to_string(),
+            ));
+        };
+
+        let message = build_message(
+            self.request_id.fetch_add(1, Ordering::Relaxed),
+            &self.streamer_info,
+            Service::Admin,
+            Command::Qos,
+            json!({ "qoslevel": qos_level.to_string() }),
+        )?;
+
+        debug!("Sending QOS request: {:?}", message);
+        writer
+            .send(Message::Text(message.to_string().into()))
+            .await?;
+        guard.qos_level = qos_level;
         Ok(())
     }
 
+    /// Returns the QoS level of the session: the level last passed to `set_qos`, or the one
+    /// from the start configuration if it was never changed.
+    ///
+    /// The level is recorded once the QOS command is sent, before Schwab acknowledges it.
+    pub async fn qos_level(&self) -> QosLevel {
+        self.inner.lock().await.qos_level
+    }
+
+    /// Subscribes to the front-month contract of a futures root such as `/ES`.
+    ///
+    /// The active contract is resolved through the `futureActiveSymbol` reference field of a REST
+    /// quote for `root`, and is subscribed with `ADD` so existing futures subscriptions are kept.
+    /// Returns the subscribed contract symbol, e.g. `/ESZ24`.
+    ///
+    /// With `follow_roll` set, every update for the subscribed contract is checked for a new
+    /// `future_active_symbol` (field 34). When Schwab reports a different active contract, the
+    /// streamer unsubscribes the old contract, adds the new one with the same fields and sends a
+    /// [`StreamerMessage::FutureRolled`] on the message channel. Field 34 is added to `fields`
+    /// automatically so rolls can be detected. Without `follow_roll`, the subscription stays on
+    /// the resolved contract until it expires.
+    pub async fn subscribe_front_month_future(
+        &self,
+        root: &str,
+        mut fields: Vec<LevelOneFuturesField>,
+        follow_roll: bool,
+    ) -> Result<String, SchwabError> {
+        let schwab_api = self.inner.lock().await.schwab_api.clone();
+        let quotes = schwab_api
+            .get_quotes(
+                vec![root.to_string()],
+                Some(vec![QuoteFields::Reference]),
+                None,
+            )
+            .await?;
+        let active_symbol = quotes
+            .get(root)
+            .and_then(|quote| quote.reference.as_ref())
+            .and_then(|reference| reference.future_active_symbol.clone())
+            .filter(|symbol| !symbol.is_empty())
+            .ok_or_else(|| {
+                SchwabError::InvalidInput(format!(
+                    "No active futures contract found for `{}`",
+                    root
+                ))
+            })?;
+
+        if follow_roll
+            && !fields.is_empty()
+            && !fields
+                .iter()
+                .any(|f| matches!(f, LevelOneFuturesField::FutureActiveSymbol))
+        {
+            fields.push(LevelOneFuturesField::FutureActiveSymbol);
+        }
+        let request = self.level_one_futures(vec![active_symbol.clone()], fields, Command::Add);
+
+        let mut guard = self.inner.lock().await;
+        guard
+            .write_requests(&self.request_id, &self.streamer_info, vec![request])
+            .await?;
+        if follow_roll {
+            guard
+                .continuous_futures
+                .insert(root.to_string(), active_symbol.clone());
+        }
+        Ok(active_symbol)
+    }
+
     pub fn level_one_equities(
         &self,
         keys: Vec<String>,
         fields: Vec<streamer::LevelOneEquitiesField>,
         command: Command,
     ) -> StreamRequest {
-        let fields_as_strings: Vec<String> = if fields.is_empty() {
-            (0..=51).map(|f| f.to_string()).collect()
-        } else {
-            fields.iter().map(|f| f.to_string()).collect()
-        };
+        let fields_as_strings = field_list(&fields, 0..=51);
 
         StreamRequest::new(Service::LevelOneEquities, command, keys, fields_as_strings)
     }
@@ -555,11 +1838,7 @@ impl SchwabStreamer {
         fields: Vec<LevelOneOptionsField>,
         command: Command,
     ) -> StreamRequest {
-        let fields_as_strings: Vec<String> = if fields.is_empty() {
-            (0..=55).map(|v| v.to_string()).collect()
-        } else {
-            fields.iter().map(|f| f.to_string()).collect()
-        };
+        let fields_as_strings = field_list(&fields, 0..=55);
 
         StreamRequest::new(Service::LevelOneOptions, command, keys, fields_as_strings)
     }
@@ -570,11 +1849,7 @@ impl SchwabStreamer {
         fields: Vec<LevelOneFuturesField>,
         command: Command,
     ) -> StreamRequest {
-        let fields_as_strings: Vec<String> = if fields.is_empty() {
-            (0..=40).map(|v| v.to_string()).collect()
-        } else {
-            fields.iter().map(|f| f.to_string()).collect()
-        };
+        let fields_as_strings = field_list(&fields, 0..=40);
 
         StreamRequest::new(Service::LevelOneFutures, command, keys, fields_as_strings)
     }
@@ -585,11 +1860,7 @@ impl SchwabStreamer {
         fields: Vec<LevelOneFuturesOptionsField>,
         command: Command,
     ) -> StreamRequest {
-        let fields_as_strings: Vec<String> = if fields.is_empty() {
-            (0..=31).map(|v| v.to_string()).collect()
-        } else {
-            fields.iter().map(|f| f.to_string()).collect()
-        };
+        let fields_as_strings = field_list(&fields, 0..=31);
 
         StreamRequest::new(Service::LevelOneFuturesOptions, command, keys, fields_as_strings)
     }
@@ -600,56 +1871,779 @@ impl SchwabStreamer {
         fields: Vec<LevelOneForexField>,
         command: Command,
     ) -> StreamRequest {
-        let fields_as_strings: Vec<String> = if fields.is_empty() {
-            (0..=29).map(|v| v.to_string()).collect()
-        } else {
-            fields.iter().map(|f| f.to_string()).collect()
-        };
+        let fields_as_strings = field_list(&fields, 0..=29);
 
         StreamRequest::new(Service::LevelOneForex, command, keys, fields_as_strings)
     }
 
-    pub async fn stop(&self) -> anyhow::Result<()> {
-        let mut guard = self.inner.lock().await;
-        if let Some(writer) = guard.writer.as_mut() {
-            writer.close().await?;
+    /// Builds a request for one-minute OHLCV bars of the equities in `keys`.
+    ///
+    /// An empty `fields` requests every field.
+    pub fn chart_equity(
+        &self,
+        keys: Vec<String>,
+        fields: Vec<ChartEquityField>,
+        command: Command,
+    ) -> StreamRequest {
+        let fields_as_strings = field_list(&fields, 0..=8);
+
+        StreamRequest::new(Service::ChartEquity, command, keys, fields_as_strings)
+    }
+
+    /// Builds a request for the NASDAQ level two order book of the equities in `keys`.
+    ///
+    /// An empty `fields` requests every field.
+    pub fn nasdaq_book(
+        &self,
+        keys: Vec<String>,
+        fields: Vec<BookField>,
+        command: Command,
+    ) -> StreamRequest {
+        let fields_as_strings = field_list(&fields, 0..=3);
+
+        StreamRequest::new(Service::NasdaqBook, command, keys, fields_as_strings)
+    }
+
+    /// Builds a request for the NYSE level two order book of the equities in `keys`.
+    ///
+    /// An empty `fields` requests every field.
+    pub fn nyse_book(
+        &self,
+        keys: Vec<String>,
+        fields: Vec<BookField>,
+        command: Command,
+    ) -> StreamRequest {
+        let fields_as_strings = field_list(&fields, 0..=3);
+
+        StreamRequest::new(Service::NyseBook, command, keys, fields_as_strings)
+    }
+
+    /// Builds a request for the level two order book of the option contracts in `keys`.
+    ///
+    /// An empty `fields` requests every field.
+    pub fn options_book(
+        &self,
+        keys: Vec<String>,
+        fields: Vec<BookField>,
+        command: Command,
+    ) -> StreamRequest {
+        let fields_as_strings = field_list(&fields, 0..=3);
+
+        StreamRequest::new(Service::OptionsBook, command, keys, fields_as_strings)
+    }
+
+    /// Builds a request for order and fill notifications on every account linked to the session.
+    ///
+    /// The subscription key is the `schwabClientCorrelId` from the user preferences the streamer
+    /// logged in with, and every field is requested. Notifications arrive as
+    /// [`StreamerMessage::AccountActivity`].
+    pub fn account_activity(&self, command: Command) -> StreamRequest {
+        StreamRequest::new(
+            Service::AcctActivity,
+            command,
+            vec![self.streamer_info.schwab_client_correl_id.clone()],
+            (0..=3).map(|v| v.to_string()).collect(),
+        )
+    }
+
+    /// Logs out, closes the connection and stops the listener task.
+    ///
+    /// A LOGOUT is sent first so Schwab releases the session instead of counting it against the
+    /// account's connection limit until it times out. The connection is closed once the LOGOUT is
+    /// acknowledged, or after two seconds without an acknowledgment.
+    pub async fn stop(&self) -> Result<(), SchwabError> {
+        let logout_ack = self.inner.lock().await.logout_ack.clone();
+        let acknowledged = logout_ack.notified();
+        tokio::pin!(acknowledged);
+        acknowledged.as_mut().enable();
+
+        let logout_sent = {
+            let mut guard = self.inner.lock().await;
+            match guard.writer.as_mut() {
+                Some(writer) => {
+                    let message = build_message(
+                        self.request_id.fetch_add(1, Ordering::Relaxed),
+                        &self.streamer_info,
+                        Service::Admin,
+                        Command::Logout,
+                        json!({}),
+                    )?;
+                    debug!("Sending LOGOUT request: {:?}", message);
+                    match writer.send(Message::Text(message.to_string().into())).await {
+                        Ok(()) => true,
+                        Err(e) => {
+                            debug!("Error sending LOGOUT: {}", e);
+                            false
+                        }
+                    }
+                }
+                None => false,
+            }
+        };
+        if logout_sent && timeout(LOGOUT_ACK_TIMEOUT, acknowledged).await.is_err() {
+            warn!(
+                "LOGOUT was not acknowledged within {:?}. Closing the connection anyway.",
+                LOGOUT_ACK_TIMEOUT
+            );
         }
+
+        let mut guard = self.inner.lock().await;
         if let Some(handle) = guard.listener_handle.take() {
             handle.abort();
         }
+        if let Some(mut writer) = guard.writer.take()
+            && let Err(e) = writer.close().await
+        {
+            // The server usually closes the connection itself after the LOGOUT.
+            debug!("Error closing WebSocket after LOGOUT: {}", e);
+        }
+        guard.is_active.store(false, Ordering::SeqCst);
+        guard.pending_acks.clear();
+        guard.pending_views.clear();
+        guard.snapshots.clear();
         Ok(())
     }
 
+    /// Returns the client ids this streamer logs in and sends requests with.
+    pub fn streamer_info(&self) -> &StreamerInfo {
+        &self.streamer_info
+    }
+
     pub async fn is_active(&self) -> bool {
         let inner = self.inner.lock().await;
         inner.is_active.load(Ordering::SeqCst)
     }
 }
 
+/// Sends `message` without waiting, counting it in `dropped` if the channel is full. A pending
+/// count is reported with a [`StreamerMessage::Lagged`] ahead of the next message that fits.
+///
+/// Returns `None` if the receiver was dropped.
+fn try_deliver(
+    tx: &mpsc::Sender<StreamerMessage>,
+    message: StreamerMessage,
+    dropped: &mut u64,
+) -> Option<()> {
+    if *dropped > 0 {
+        match tx.try_send(StreamerMessage::Lagged { dropped: *dropped }) {
+            Ok(()) => *dropped = 0,
+            Err(TrySendError::Full(_)) => {
+                *dropped += 1;
+                return Some(());
+            }
+            Err(TrySendError::Closed(_)) => return None,
+        }
+    }
+    match tx.try_send(message) {
+        Ok(()) => Some(()),
+        Err(TrySendError::Full(_)) => {
+            *dropped += 1;
+            Some(())
+        }
+        Err(TrySendError::Closed(_)) => None,
+    }
+}
+
+/// Splits `request` into requests of at most `max_keys` keys each, with the same service and
+/// fields.
+///
+/// A SUBS becomes a SUBS for the first keys and ADDs for the rest, since each SUBS would replace
+/// the keys subscribed by the one before it. VIEW requests carry no keys and are kept whole.
+fn split_request(request: StreamRequest, max_keys: usize) -> Vec<StreamRequest> {
+    if request.keys.len() <= max_keys {
+        return vec![request];
+    }
+
+    request
+        .keys
+        .chunks(max_keys)
+        .enumerate()
+        .map(|(i, keys)| {
+            let command = match request.command {
+                Command::Subs if i > 0 => Command::Add,
+                ref command => command.clone(),
+            };
+            StreamRequest::new(
+                request.service.clone(),
+                command,
+                keys.to_vec(),
+                request.fields.clone(),
+            )
+        })
+        .collect()
+}
+
+/// Builds the message reporting that `request` did not result in a subscription.
+fn subscription_failed(request: &StreamRequest, reason: String) -> StreamerMessage {
+    StreamerMessage::SubscriptionFailed {
+        service: request.service.to_string(),
+        command: request.command.to_string(),
+        keys: request.keys.clone(),
+        reason,
+    }
+}
+
+/// Converts the fields of a subscription to their indexes, requesting every index in `all` when
+/// `fields` is empty.
+///
+/// Duplicates are requested once, and the key field `0` is always included because Schwab
+/// requires it.
+fn field_list<F: ToString>(fields: &[F], all: RangeInclusive<u32>) -> Vec<String> {
+    if fields.is_empty() {
+        return all.map(|v| v.to_string()).collect();
+    }
+    dedup_ordered(
+        iter::once("0".to_string())
+            .chain(fields.iter().map(|f| f.to_string()))
+            .collect(),
+    )
+}
+
+/// Builds a streamer request frame.
+///
+/// The customer and correlation ids are always taken from the same `StreamerInfo` the session
+/// logged in with, so they stay consistent for every request and across reconnects.
 fn build_message(
-    request_id: Arc<AtomicI64>,
-    streamer_info: Arc<Value>,
+    request_id_num: i64,
+    streamer_info: &StreamerInfo,
     service: Service,
     command: Command,
     parameters: Value,
-) -> anyhow::Result<Value> {
-    let request_id_num = request_id.fetch_add(1, Ordering::Relaxed);
-    let schwab_client_customer_id = streamer_info
-        .get("schwabClientCustomerId")
-        .ok_or_else(|| anyhow!("Unable to read schwabClientCustomerId from streamer info"))?;
-    let schwab_client_correl_id = streamer_info
-        .get("schwabClientCorrelId")
-        .ok_or_else(|| anyhow!("Unable to read schwabClientCorrelId from streamer info"))?;
-
+) -> Result<Value, SchwabError> {
     let message = json!({
         "requests": [{
             "service": service.to_string(),
             "command": command.to_string(),
             "requestid": request_id_num,
             "parameters": parameters,
-            "SchwabClientCustomerId": schwab_client_customer_id,
-            "SchwabClientCorrelId": schwab_client_correl_id,
+            "SchwabClientCustomerId": streamer_info.schwab_client_customer_id,
+            "SchwabClientCorrelId": streamer_info.schwab_client_correl_id,
         }]
     });
     Ok(message)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schwab::test_support::{MockStreamer, StreamerEvent};
+
+    fn equities(command: Command, keys: &[&str], fields: &[&str]) -> StreamRequest {
+        StreamRequest::new(
+            Service::LevelOneEquities,
+            command,
+            keys.iter().map(|k| k.to_string()).collect(),
+            fields.iter().map(|f| f.to_string()).collect(),
+        )
+    }
+
+    /// Starts `streamer` against `server` and consumes the LOGIN.
+    async fn start(
+        server: &mut MockStreamer,
+        streamer: &SchwabStreamer,
+        config: SchwabStreamerStartConfig,
+    ) -> mpsc::Receiver<StreamerMessage> {
+        let rx = streamer.start_with_config(config).await.expect("login");
+        assert_eq!(server.next_request().await["command"], "LOGIN");
+        rx
+    }
+
+    async fn tracked_equities(streamer: &SchwabStreamer) -> HashMap<String, Vec<String>> {
+        streamer
+            .current_subscriptions()
+            .await
+            .remove(&Service::LevelOneEquities)
+            .unwrap_or_default()
+    }
+
+    /// The typed admin responses of a `response` frame.
+    fn admin_responses(frame: Value) -> Vec<Option<AdminResponse>> {
+        let message: TopLevelMessage = serde_json::from_value(frame).unwrap();
+        message
+            .response
+            .iter()
+            .map(StreamerResponse::admin_response)
+            .collect()
+    }
+
+    #[test]
+    fn qos_acknowledgement_is_parsed_as_qos_changed() {
+        let responses = admin_responses(json!({
+            "response": [{
+                "service": "ADMIN",
+                "command": "QOS",
+                "requestid": "3",
+                "SchwabClientCorrelId": "correl-id",
+                "timestamp": 1705438800000i64,
+                "content": { "code": 0, "msg": "QoS command succeeded. Set qoslevel=4" }
+            }]
+        }));
+
+        assert_eq!(
+            responses,
+            [Some(AdminResponse::QosChanged {
+                message: "QoS command succeeded. Set qoslevel=4".to_string()
+            })]
+        );
+    }
+
+    #[test]
+    fn non_zero_admin_codes_are_parsed_as_errors() {
+        let responses = admin_responses(json!({
+            "response": [
+                {
+                    "service": "ADMIN",
+                    "command": "QOS",
+                    "requestid": "4",
+                    "SchwabClientCorrelId": "correl-id",
+                    "timestamp": 1705438800000i64,
+                    "content": { "code": 22, "msg": "Invalid qoslevel value" }
+                },
+                {
+                    "service": "ADMIN",
+                    "command": "LOGIN",
+                    "requestid": "0",
+                    "SchwabClientCorrelId": "correl-id",
+                    "timestamp": 1705438800000i64,
+                    "content": { "code": 3, "msg": "Login denied" }
+                }
+            ]
+        }));
+
+        assert_eq!(
+            responses,
+            [
+                Some(AdminResponse::Error {
+                    command: "QOS".to_string(),
+                    code: 22,
+                    message: "Invalid qoslevel value".to_string(),
+                }),
+                Some(AdminResponse::Error {
+                    command: "LOGIN".to_string(),
+                    code: 3,
+                    message: "Login denied".to_string(),
+                }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn add_merges_fields_into_tracked_keys() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let _rx = start(&mut server, &streamer, Default::default()).await;
+
+        streamer
+            .send(vec![equities(Command::Add, &["AAPL"], &["0", "1"])])
+            .await
+            .unwrap();
+        streamer
+            .send(vec![equities(Command::Add, &["AAPL", "MSFT"], &["0", "2"])])
+            .await
+            .unwrap();
+
+        let tracked = tracked_equities(&streamer).await;
+        assert_eq!(tracked["AAPL"], ["0", "1", "2"]);
+        assert_eq!(tracked["MSFT"], ["0", "2"]);
+    }
+
+    #[tokio::test]
+    async fn subs_replaces_the_tracked_keys_of_the_service() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let _rx = start(&mut server, &streamer, Default::default()).await;
+
+        streamer
+            .send(vec![equities(Command::Add, &["AAPL", "MSFT"], &["0", "1"])])
+            .await
+            .unwrap();
+        streamer
+            .send(vec![equities(Command::Subs, &["TSLA"], &["0", "3"])])
+            .await
+            .unwrap();
+
+        let tracked = tracked_equities(&streamer).await;
+        assert_eq!(tracked.len(), 1);
+        assert_eq!(tracked["TSLA"], ["0", "3"]);
+    }
+
+    #[tokio::test]
+    async fn unsubs_forgets_its_keys() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let _rx = start(&mut server, &streamer, Default::default()).await;
+
+        streamer
+            .send(vec![
+                equities(Command::Subs, &["AAPL", "MSFT"], &["0", "1"]),
+                equities(Command::Unsubs, &["AAPL"], &[]),
+            ])
+            .await
+            .unwrap();
+
+        let tracked = tracked_equities(&streamer).await;
+        assert_eq!(tracked.keys().collect::<Vec<_>>(), ["MSFT"]);
+    }
+
+    #[tokio::test]
+    async fn large_subs_is_split_into_subs_and_adds() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let _rx = start(&mut server, &streamer, Default::default()).await;
+
+        let keys: Vec<String> = (0..1200).map(|i| format!("SYM{}", i)).collect();
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        streamer
+            .send(vec![equities(Command::Subs, &keys, &["0", "1"])])
+            .await
+            .unwrap();
+
+        let mut frames = Vec::new();
+        for _ in 0..3 {
+            let request = server.next_request().await;
+            let key_count = request["parameters"]["keys"]
+                .as_str()
+                .unwrap()
+                .split(',')
+                .count();
+            frames.push((request["command"].as_str().unwrap().to_string(), key_count));
+        }
+        assert_eq!(
+            frames,
+            [
+                ("SUBS".to_string(), 500),
+                ("ADD".to_string(), 500),
+                ("ADD".to_string(), 200),
+            ]
+        );
+        assert_eq!(tracked_equities(&streamer).await.len(), 1200);
+
+        // Nothing else was written before the next request.
+        streamer
+            .send(vec![equities(Command::Unsubs, &["SYM0"], &[])])
+            .await
+            .unwrap();
+        assert_eq!(server.next_request().await["command"], "UNSUBS");
+    }
+
+    #[tokio::test]
+    async fn login_and_requests_use_the_ids_from_preferences() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let _rx = streamer
+            .start_with_config(Default::default())
+            .await
+            .unwrap();
+
+        let login = server.next_request().await;
+        streamer
+            .send(vec![equities(Command::Add, &["AAPL"], &["0"])])
+            .await
+            .unwrap();
+        let add = server.next_request().await;
+
+        assert_eq!(login["command"], "LOGIN");
+        assert_eq!(login["parameters"]["SchwabClientChannel"], "N9");
+        assert_eq!(login["parameters"]["SchwabClientFunctionId"], "APIAPP");
+        for request in [login, add] {
+            assert_eq!(request["SchwabClientCustomerId"], "customer-id");
+            assert_eq!(request["SchwabClientCorrelId"], "correl-id");
+        }
+    }
+
+    /// Asserts that the next events of `server` are a LOGIN, the connection closing and a second
+    /// LOGIN.
+    async fn assert_relogin(server: &mut MockStreamer) {
+        assert_eq!(server.next_request().await["command"], "LOGIN");
+        assert!(matches!(server.next_event().await, StreamerEvent::Closed));
+        assert_eq!(server.next_request().await["command"], "LOGIN");
+    }
+
+    #[tokio::test]
+    async fn duplicate_session_login_is_an_error_by_default() {
+        let mut server = MockStreamer::start_rejecting_logins(1).await;
+        let streamer = server.streamer().await;
+
+        let result = streamer.start_with_config(Default::default()).await;
+
+        assert!(matches!(
+            result,
+            Err(SchwabError::DuplicateSession(message)) if message == "Max connections reached"
+        ));
+        assert_eq!(server.next_request().await["command"], "LOGIN");
+    }
+
+    #[tokio::test]
+    async fn duplicate_session_login_is_retried_once_when_forced() {
+        let config = || SchwabStreamerStartConfig {
+            force_relogin_on_duplicate_session: true,
+            ..Default::default()
+        };
+
+        let mut server = MockStreamer::start_rejecting_logins(1).await;
+        let streamer = server.streamer().await;
+        let started = streamer.start_with_config(config()).await;
+        assert!(started.is_ok());
+        assert_relogin(&mut server).await;
+
+        let mut server = MockStreamer::start_rejecting_logins(2).await;
+        let streamer = server.streamer().await;
+        let rejected = streamer.start_with_config(config()).await;
+        assert!(matches!(rejected, Err(SchwabError::DuplicateSession(_))));
+        assert_relogin(&mut server).await;
+    }
+
+    #[tokio::test]
+    async fn set_qos_sends_an_admin_qos_request() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let _rx = start(&mut server, &streamer, Default::default()).await;
+
+        streamer.set_qos(QosLevel::Slow).await.unwrap();
+
+        assert_eq!(
+            server.next_request().await,
+            json!({
+                "service": "ADMIN",
+                "command": "QOS",
+                "requestid": 1,
+                "parameters": { "qoslevel": "4" },
+                "SchwabClientCustomerId": "customer-id",
+                "SchwabClientCorrelId": "correl-id",
+            })
+        );
+        assert_eq!(streamer.qos_level().await, QosLevel::Slow);
+    }
+
+    #[tokio::test]
+    async fn resubscribe_replays_tracked_subscriptions() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let _rx = start(&mut server, &streamer, Default::default()).await;
+
+        streamer
+            .send(vec![
+                equities(Command::Add, &["AAPL"], &["0", "1"]),
+                equities(Command::Add, &["MSFT"], &["0", "1"]),
+                equities(Command::Add, &["TSLA"], &["0", "2"]),
+            ])
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            server.next_request().await;
+        }
+
+        streamer.resubscribe().await.unwrap();
+
+        let mut frames = Vec::new();
+        for _ in 0..2 {
+            let request = server.next_request().await;
+            let mut keys: Vec<String> = request["parameters"]["keys"]
+                .as_str()
+                .unwrap()
+                .split(',')
+                .map(str::to_string)
+                .collect();
+            keys.sort();
+            frames.push((
+                request["command"].as_str().unwrap().to_string(),
+                request["parameters"]["fields"]
+                    .as_str()
+                    .unwrap()
+                    .to_string(),
+                keys,
+            ));
+        }
+        // One group per distinct field list; whichever comes first is the SUBS.
+        assert_eq!(frames[0].0, "SUBS");
+        assert_eq!(frames[1].0, "ADD");
+        frames.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(frames[0].1, "0,1");
+        assert_eq!(frames[0].2, ["AAPL", "MSFT"]);
+        assert_eq!(frames[1].1, "0,2");
+        assert_eq!(frames[1].2, ["TSLA"]);
+    }
+
+    #[tokio::test]
+    async fn connected_is_sent_without_holding_the_lock() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let (tx, mut rx) = mpsc::channel(1);
+        tx.send(StreamerMessage::Connected).await.unwrap();
+
+        let connecting = streamer.clone();
+        let handle = tokio::spawn(async move {
+            connecting
+                .connect_and_login(&SchwabStreamerStartConfig::default(), &tx)
+                .await
+                .map(|_| ())
+        });
+        assert_eq!(server.next_request().await["command"], "LOGIN");
+
+        // The channel is full, so Connected is still pending, yet the streamer stays usable.
+        timeout(Duration::from_secs(1), streamer.current_subscriptions())
+            .await
+            .expect("lock released before sending Connected");
+        assert!(matches!(rx.recv().await, Some(StreamerMessage::Connected)));
+        assert!(matches!(rx.recv().await, Some(StreamerMessage::Connected)));
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn drop_newest_reports_dropped_updates_as_lagged() {
+        let (tx, mut rx) = mpsc::channel(2);
+        let mut dropped = 0;
+
+        for _ in 0..4 {
+            try_deliver(&tx, StreamerMessage::Connected, &mut dropped).unwrap();
+        }
+        assert_eq!(dropped, 2);
+
+        rx.recv().await.unwrap();
+        rx.recv().await.unwrap();
+        try_deliver(&tx, StreamerMessage::Connected, &mut dropped).unwrap();
+        assert_eq!(dropped, 0);
+        assert!(matches!(
+            rx.recv().await,
+            Some(StreamerMessage::Lagged { dropped: 2 })
+        ));
+        assert!(matches!(rx.recv().await, Some(StreamerMessage::Connected)));
+    }
+
+    #[tokio::test]
+    async fn stop_forgets_merged_snapshots() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await.with_merged_updates(true);
+        let _rx = start(&mut server, &streamer, Default::default()).await;
+        let update =
+            |frame: Value| StreamerMessage::LevelOneEquity(serde_json::from_value(frame).unwrap());
+
+        let mut guard = streamer.inner.lock().await;
+        guard.merge_update(update(json!({ "key": "AAPL", "1": 189.5 })));
+        let StreamerMessage::LevelOneEquity(merged) =
+            guard.merge_update(update(json!({ "key": "AAPL", "2": 189.7 })))
+        else {
+            panic!("expected an equity update");
+        };
+        assert_eq!(merged.bid_price, Some(189.5));
+        drop(guard);
+
+        streamer.stop().await.unwrap();
+        assert!(streamer.inner.lock().await.snapshots.is_empty());
+    }
+
+    #[tokio::test]
+    async fn dropping_the_streamer_ends_the_listener() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let mut rx = start(&mut server, &streamer, Default::default()).await;
+
+        drop(streamer);
+
+        assert!(matches!(server.next_event().await, StreamerEvent::Closed));
+        timeout(Duration::from_secs(5), async {
+            while rx.recv().await.is_some() {}
+        })
+        .await
+        .expect("message channel closed");
+    }
+
+    #[tokio::test]
+    async fn stop_logs_out_before_closing() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let _rx = start(&mut server, &streamer, Default::default()).await;
+
+        let started = Instant::now();
+        streamer.stop().await.unwrap();
+
+        assert_eq!(server.next_request().await["command"], "LOGOUT");
+        assert!(matches!(server.next_event().await, StreamerEvent::Closed));
+        // The LOGOUT was acknowledged, so stop did not wait out the timeout.
+        assert!(started.elapsed() < LOGOUT_ACK_TIMEOUT);
+        assert!(!streamer.is_active().await);
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_add_is_retried_once_then_reported() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let config = SchwabStreamerStartConfig {
+            subscription_ack_timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        let mut rx = start(&mut server, &streamer, config).await;
+
+        streamer
+            .send(vec![equities(Command::Add, &["AAPL"], &["0", "1"])])
+            .await
+            .unwrap();
+        assert_eq!(server.next_request().await["command"], "ADD");
+        assert_eq!(server.next_request().await["command"], "ADD");
+
+        let failure = timeout(Duration::from_secs(5), async {
+            loop {
+                match rx.recv().await.expect("channel open") {
+                    failure @ StreamerMessage::SubscriptionFailed { .. } => break failure,
+                    _ => continue,
+                }
+            }
+        })
+        .await
+        .expect("SubscriptionFailed within five seconds");
+        let StreamerMessage::SubscriptionFailed {
+            service,
+            command,
+            keys,
+            ..
+        } = failure
+        else {
+            unreachable!();
+        };
+        assert_eq!(service, "LEVELONE_EQUITIES");
+        assert_eq!(command, "ADD");
+        assert_eq!(keys, ["AAPL"]);
+
+        // No third attempt.
+        streamer
+            .send(vec![equities(Command::Unsubs, &["AAPL"], &[])])
+            .await
+            .unwrap();
+        assert_eq!(server.next_request().await["command"], "UNSUBS");
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_subs_is_retried_as_add() {
+        let mut server = MockStreamer::start().await;
+        let streamer = server.streamer().await;
+        let config = SchwabStreamerStartConfig {
+            subscription_ack_timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        let _rx = start(&mut server, &streamer, config).await;
+
+        streamer
+            .send(vec![
+                equities(Command::Subs, &["AAPL"], &["0", "1"]),
+                equities(Command::Add, &["MSFT"], &["0", "1"]),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(server.next_request().await["command"], "SUBS");
+        assert_eq!(server.next_request().await["command"], "ADD");
+
+        let retries = [server.next_request().await, server.next_request().await];
+        for retry in &retries {
+            assert_eq!(retry["command"], "ADD");
+        }
+        let mut keys: Vec<_> = retries
+            .iter()
+            .map(|retry| retry["parameters"]["keys"].as_str().unwrap())
+            .collect();
+        keys.sort();
+        assert_eq!(keys, ["AAPL", "MSFT"]);
+
+        let tracked = tracked_equities(&streamer).await;
+        assert_eq!(tracked.len(), 2);
+    }
+}
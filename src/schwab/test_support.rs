@@ -0,0 +1,168 @@
+//! Fixtures shared by the unit tests.
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use futures_util::{SinkExt, StreamExt};
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use tokio::{net::TcpListener, sync::mpsc, time::timeout};
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+use crate::schwab::{
+    endpoints::SchwabEndpoints, retry::RetryConfig, schwab_api::SchwabApi,
+    schwab_auth::StoredTokenInfo, schwab_streamer::SchwabStreamer, token_store::InMemoryTokenStore,
+    transport::MockTransport,
+};
+
+/// Tokens that were just issued, with the trading scope.
+pub(crate) fn tokens() -> StoredTokenInfo {
+    StoredTokenInfo {
+        access_token: "access-token".to_string(),
+        expires_in: 1800,
+        id_token: "id-token".to_string(),
+        refresh_token: "refresh-token".to_string(),
+        scope: "api".to_string(),
+        token_type: "Bearer".to_string(),
+        obtained_at: Utc::now(),
+    }
+}
+
+/// Retries that wait a millisecond, so tests of the retry path stay fast.
+pub(crate) fn fast_retries() -> RetryConfig {
+    RetryConfig {
+        max_retries: 2,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(2),
+    }
+}
+
+/// A client that sends every request through `transport`.
+pub(crate) async fn api(transport: &Arc<MockTransport>) -> SchwabApi {
+    SchwabApi::builder()
+        .app_key("app-key")
+        .app_secret("app-secret")
+        .token_store(Arc::new(InMemoryTokenStore::with_tokens(tokens())))
+        .transport(transport.clone())
+        .retry_config(fast_retries())
+        .build()
+        .await
+        .expect("client with in-memory tokens builds")
+}
+
+/// What the mock streamer received from the client.
+#[derive(Debug)]
+pub(crate) enum StreamerEvent {
+    /// One entry of the `requests` array of a frame.
+    Request(Value),
+    /// The client closed the connection or dropped it.
+    Closed,
+}
+
+/// A WebSocket server standing in for the Schwab streamer.
+///
+/// It acknowledges every ADMIN request with code `0`, unless started with
+/// [`MockStreamer::start_rejecting_logins`], leaves subscription requests unacknowledged, and
+/// reports everything it receives through `events`.
+pub(crate) struct MockStreamer {
+    pub(crate) url: String,
+    pub(crate) events: mpsc::UnboundedReceiver<StreamerEvent>,
+}
+
+impl MockStreamer {
+    /// Starts the server on a free local port.
+    pub(crate) async fn start() -> Self {
+        Self::start_rejecting_logins(0).await
+    }
+
+    /// Starts the server, answering the first `rejected_logins` LOGIN requests with code 12, as
+    /// Schwab does while another session for the account is still connected.
+    pub(crate) async fn start_rejecting_logins(mut rejected_logins: usize) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("local port is free");
+        let url = format!("ws://{}", listener.local_addr().expect("bound address"));
+        let (events_tx, events) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let Ok(mut socket) = accept_async(stream).await else {
+                    continue;
+                };
+                while let Some(Ok(message)) = socket.next().await {
+                    let frame: Value = match message {
+                        Message::Text(text) => serde_json::from_str(&text).expect("JSON frame"),
+                        Message::Close(_) => break,
+                        _ => continue,
+                    };
+                    for request in frame["requests"].as_array().cloned().unwrap_or_default() {
+                        if request["service"] == "ADMIN" {
+                            let content = if request["command"] == "LOGIN" && rejected_logins > 0 {
+                                rejected_logins -= 1;
+                                json!({ "code": 12, "msg": "Max connections reached" })
+                            } else {
+                                json!({ "code": 0, "msg": "ok" })
+                            };
+                            let ack = json!({
+                                "response": [{
+                                    "service": "ADMIN",
+                                    "command": request["command"],
+                                    "requestid": request["requestid"].to_string(),
+                                    "content": content,
+                                }]
+                            });
+                            let _ = socket.send(Message::Text(ack.to_string().into())).await;
+                        }
+                        let _ = events_tx.send(StreamerEvent::Request(request));
+                    }
+                }
+                let _ = events_tx.send(StreamerEvent::Closed);
+            }
+        });
+
+        Self { url, events }
+    }
+
+    /// Returns the next request the client sent.
+    pub(crate) async fn next_request(&mut self) -> Value {
+        match self.next_event().await {
+            StreamerEvent::Request(request) => request,
+            StreamerEvent::Closed => panic!("connection closed while waiting for a request"),
+        }
+    }
+
+    /// Returns the next event, failing the test if none arrives within five seconds.
+    pub(crate) async fn next_event(&mut self) -> StreamerEvent {
+        timeout(Duration::from_secs(5), self.events.recv())
+            .await
+            .expect("streamer event within five seconds")
+            .expect("mock streamer is running")
+    }
+
+    /// A streamer whose user preferences point at this server.
+    pub(crate) async fn streamer(&self) -> SchwabStreamer {
+        let transport = Arc::new(MockTransport::new());
+        transport.respond(
+            StatusCode::OK,
+            json!({
+                "accounts": [],
+                "offers": [],
+                "streamerInfo": [{
+                    "streamerSocketUrl": self.url,
+                    "schwabClientCustomerId": "customer-id",
+                    "schwabClientCorrelId": "correl-id",
+                    "schwabClientChannel": "N9",
+                    "schwabClientFunctionId": "APIAPP",
+                }],
+            })
+            .to_string(),
+        );
+        let schwab_api = api(&transport).await.with_endpoints(SchwabEndpoints {
+            streamer: self.url.clone(),
+            ..SchwabEndpoints::default()
+        });
+        SchwabStreamer::new(schwab_api)
+            .await
+            .expect("streamer info from the canned preferences")
+    }
+}
@@ -0,0 +1,254 @@
+use std::{fmt, path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::schwab::{error::SchwabError, schwab_auth::StoredTokenInfo};
+
+/// A pluggable storage backend for Schwab OAuth tokens.
+///
+/// `SchwabAuth` writes freshly authorized tokens to a store, and `SchwabApi` reads tokens from
+/// it on construction and writes rotated tokens back to it after every refresh. Sharing a single
+/// store between the two keeps them in sync without going through the file system.
+#[async_trait]
+pub trait TokenStore: fmt::Debug + Send + Sync {
+    /// Loads the stored tokens, returning `None` if no tokens have been saved yet.
+    async fn load(&self) -> Result<Option<StoredTokenInfo>, SchwabError>;
+
+    /// Saves the given tokens, replacing any previously stored tokens.
+    ///
+    /// Custom implementations can report backend-specific failures as `SchwabError::TokenStore`.
+    async fn save(&self, token_info: &StoredTokenInfo) -> Result<(), SchwabError>;
+}
+
+/// A `TokenStore` that persists tokens as pretty-printed JSON in a local file.
+///
+/// A file that cannot be parsed is reported as `SchwabError::CorruptTokenFile`. With
+/// [`FileTokenStore::with_corrupt_backup`] enabled, a corrupt file is moved aside to
+/// `<path>.corrupt-<timestamp>` before new tokens are written over it.
+///
+/// This is the default store, and matches the `tokens.json` layout used by earlier versions.
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    path: PathBuf,
+    backup_corrupt: bool,
+}
+
+impl FileTokenStore {
+    /// Creates a new `FileTokenStore` backed by the file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            backup_corrupt: false,
+        }
+    }
+
+    /// Keeps a copy of a corrupt token file instead of silently overwriting it on the next save.
+    pub fn with_corrupt_backup(mut self, enabled: bool) -> Self {
+        self.backup_corrupt = enabled;
+        self
+    }
+
+    /// Moves the current file aside if it exists and does not contain valid tokens.
+    async fn backup_if_corrupt(&self) -> Result<(), SchwabError> {
+        let json_string = match tokio::fs::read_to_string(&self.path).await {
+            Ok(json_string) => json_string,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if serde_json::from_str::<StoredTokenInfo>(&json_string).is_ok() {
+            return Ok(());
+        }
+
+        let mut backup_path = self.path.clone().into_os_string();
+        backup_path.push(format!(".corrupt-{}", Utc::now().format("%Y%m%dT%H%M%S")));
+        warn!(
+            "Token file {} is corrupt; moving it to {:?}",
+            self.path.display(),
+            backup_path
+        );
+        tokio::fs::rename(&self.path, backup_path).await?;
+        Ok(())
+    }
+
+    /// Returns the path of the backing file.
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl TokenStore for FileTokenStore {
+    async fn load(&self) -> Result<Option<StoredTokenInfo>, SchwabError> {
+        let json_string = match tokio::fs::read_to_string(&self.path).await {
+            Ok(json_string) => json_string,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let token_info: StoredTokenInfo =
+            serde_json::from_str(&json_string).map_err(|source| SchwabError::CorruptTokenFile {
+                path: self.path.clone(),
+                source,
+            })?;
+        Ok(Some(token_info))
+    }
+
+    async fn save(&self, token_info: &StoredTokenInfo) -> Result<(), SchwabError> {
+        if self.backup_corrupt {
+            self.backup_if_corrupt().await?;
+        }
+        let json_string = serde_json::to_string_pretty(token_info)?;
+        tokio::fs::write(&self.path, json_string).await?;
+        Ok(())
+    }
+}
+
+/// A `TokenStore` that keeps tokens in memory only.
+///
+/// Useful for tests and short-lived processes where writing `tokens.json` to disk is undesirable.
+/// Clones share the same underlying storage, so a single store can be handed to both a
+/// `SchwabAuth` and a `SchwabApi`.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryTokenStore {
+    tokens: Arc<Mutex<Option<StoredTokenInfo>>>,
+}
+
+impl InMemoryTokenStore {
+    /// Creates a new, empty `InMemoryTokenStore`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `InMemoryTokenStore` pre-populated with the given tokens.
+    pub fn with_tokens(token_info: StoredTokenInfo) -> Self {
+        Self {
+            tokens: Arc::new(Mutex::new(Some(token_info))),
+        }
+    }
+}
+
+#[async_trait]
+impl TokenStore for InMemoryTokenStore {
+    async fn load(&self) -> Result<Option<StoredTokenInfo>, SchwabError> {
+        Ok(self.tokens.lock().await.clone())
+    }
+
+    async fn save(&self, token_info: &StoredTokenInfo) -> Result<(), SchwabError> {
+        *self.tokens.lock().await = Some(token_info.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::schwab::{schwab_api::SchwabApi, test_support};
+
+    /// A fresh, empty directory under the system temp dir, unique to this process and `name`.
+    async fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("brokerage-api-{}-{}", std::process::id(), name));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        dir
+    }
+
+    async fn file_names(dir: &Path) -> Vec<String> {
+        let mut entries = tokio::fs::read_dir(dir).await.unwrap();
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn invalid_json_is_reported_as_a_corrupt_token_file() {
+        let dir = temp_dir("corrupt-load").await;
+        let path = dir.join("tokens.json");
+        tokio::fs::write(&path, r#"{"access_token": "abc", "refresh_"#)
+            .await
+            .unwrap();
+
+        let error = FileTokenStore::new(&path).load().await.unwrap_err();
+
+        match &error {
+            SchwabError::CorruptTokenFile { path: reported, .. } => assert_eq!(reported, &path),
+            other => panic!("expected CorruptTokenFile, got {:?}", other),
+        }
+        assert!(
+            error.to_string().contains("re-run SchwabAuth::authorize"),
+            "{}",
+            error
+        );
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn missing_token_file_loads_as_none() {
+        let dir = temp_dir("missing-load").await;
+
+        let loaded = FileTokenStore::new(dir.join("tokens.json")).load().await;
+
+        assert!(loaded.unwrap().is_none());
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn saving_over_a_corrupt_file_backs_it_up_when_enabled() {
+        let dir = temp_dir("corrupt-backup").await;
+        let path = dir.join("tokens.json");
+        tokio::fs::write(&path, "not json").await.unwrap();
+        let store = FileTokenStore::new(&path).with_corrupt_backup(true);
+
+        store.save(&test_support::tokens()).await.unwrap();
+
+        let names = file_names(&dir).await;
+        assert_eq!(names.len(), 2, "{:?}", names);
+        assert!(names[1].starts_with("tokens.json.corrupt-"), "{:?}", names);
+        let backup = tokio::fs::read_to_string(dir.join(&names[1]))
+            .await
+            .unwrap();
+        assert_eq!(backup, "not json");
+        let loaded = store.load().await.unwrap().unwrap();
+        assert_eq!(loaded.refresh_token, "refresh-token");
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn saving_over_a_corrupt_file_replaces_it_by_default() {
+        let dir = temp_dir("corrupt-overwrite").await;
+        let path = dir.join("tokens.json");
+        tokio::fs::write(&path, "not json").await.unwrap();
+
+        FileTokenStore::new(&path)
+            .save(&test_support::tokens())
+            .await
+            .unwrap();
+
+        assert_eq!(file_names(&dir).await, ["tokens.json"]);
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn client_construction_fails_with_a_corrupt_token_file() {
+        let dir = temp_dir("corrupt-client").await;
+        let path = dir.join("tokens.json");
+        tokio::fs::write(&path, "").await.unwrap();
+
+        let result = SchwabApi::builder()
+            .app_key("app-key")
+            .app_secret("app-secret")
+            .token_store(Arc::new(FileTokenStore::new(&path)))
+            .build()
+            .await;
+
+        assert!(matches!(result, Err(SchwabError::CorruptTokenFile { .. })));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+
+use async_trait::async_trait;
+use reqwest::{Client, Request, Response};
+
+use crate::schwab::error::SchwabError;
+
+#[cfg(any(test, feature = "test-util"))]
+pub use mock::{MockTransport, RecordedRequest};
+
+/// Sends HTTP requests on behalf of `SchwabApi` and `SchwabAuth`.
+///
+/// Requests are still built with the configured `reqwest::Client`; the transport only executes
+/// them. `reqwest::Client` is the default implementation. With the `test-util` feature,
+/// `MockTransport` answers with canned responses so that URL construction, retries and error
+/// mapping can be tested without a network or a mock server.
+#[async_trait]
+pub trait HttpTransport: fmt::Debug + Send + Sync {
+    /// Sends `request` and returns the response, whatever its status.
+    async fn execute(&self, request: Request) -> Result<Response, SchwabError>;
+}
+
+#[async_trait]
+impl HttpTransport for Client {
+    async fn execute(&self, request: Request) -> Result<Response, SchwabError> {
+        Ok(Client::execute(self, request).await?)
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+mod mock {
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    use async_trait::async_trait;
+    use reqwest::{Method, Request, Response, StatusCode, Url, header::HeaderMap};
+
+    use super::HttpTransport;
+    use crate::schwab::error::SchwabError;
+
+    /// A request seen by a [`MockTransport`].
+    #[derive(Debug, Clone)]
+    pub struct RecordedRequest {
+        pub method: Method,
+        pub url: Url,
+        pub headers: HeaderMap,
+        /// The request body, if it was sent from memory rather than streamed.
+        pub body: Option<Vec<u8>>,
+    }
+
+    #[derive(Debug)]
+    struct CannedResponse {
+        status: StatusCode,
+        headers: HeaderMap,
+        body: String,
+    }
+
+    /// An `HttpTransport` that answers requests with queued responses, in order, and records every
+    /// request it receives.
+    ///
+    /// A request that arrives after the queue is exhausted fails with `SchwabError::InvalidInput`.
+    /// Clones share the same queue and request log.
+    #[derive(Debug, Clone, Default)]
+    pub struct MockTransport {
+        responses: Arc<Mutex<VecDeque<CannedResponse>>>,
+        requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    }
+
+    impl MockTransport {
+        /// Creates a transport with no queued responses.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Queues a response with the given status and body.
+        pub fn respond(&self, status: StatusCode, body: impl Into<String>) -> &Self {
+            self.respond_with_headers(status, HeaderMap::new(), body)
+        }
+
+        /// Queues a response with the given status, headers and body, e.g. a 429 with `Retry-After`.
+        pub fn respond_with_headers(
+            &self,
+            status: StatusCode,
+            headers: HeaderMap,
+            body: impl Into<String>,
+        ) -> &Self {
+            lock(&self.responses).push_back(CannedResponse {
+                status,
+                headers,
+                body: body.into(),
+            });
+            self
+        }
+
+        /// Returns every request received so far, oldest first.
+        pub fn requests(&self) -> Vec<RecordedRequest> {
+            lock(&self.requests).clone()
+        }
+
+        /// Returns the number of queued responses that have not been used yet.
+        pub fn remaining(&self) -> usize {
+            lock(&self.responses).len()
+        }
+    }
+
+    #[async_trait]
+    impl HttpTransport for MockTransport {
+        async fn execute(&self, request: Request) -> Result<Response, SchwabError> {
+            lock(&self.requests).push(RecordedRequest {
+                method: request.method().clone(),
+                url: request.url().clone(),
+                headers: request.headers().clone(),
+                body: request
+                    .body()
+                    .and_then(|body| body.as_bytes())
+                    .map(<[u8]>::to_vec),
+            });
+
+            let canned = lock(&self.responses).pop_front().ok_or_else(|| {
+                SchwabError::InvalidInput(format!(
+                    "MockTransport has no response queued for {} {}",
+                    request.method(),
+                    request.url()
+                ))
+            })?;
+
+            let mut response = http::Response::new(canned.body);
+            *response.status_mut() = canned.status;
+            *response.headers_mut() = canned.headers;
+            Ok(Response::from(response))
+        }
+    }
+
+    /// Locks `mutex`, recovering the data if a panicking test poisoned it.
+    fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+        mutex
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
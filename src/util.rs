@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeDelta, Utc, Weekday};
+
+use crate::schwab::models::market_data::{Candle, PutCall};
 
 /// Removes duplicate elements from a vector while preserving the original order.
 ///
@@ -41,7 +43,7 @@ where
 /// # Arguments
 ///
 /// * `params` - A `Vec` of `(&str, Option<T>)` tuples, where the first element is the parameter key
-///              and the second is an `Option` containing the parameter value.
+///   and the second is an `Option` containing the parameter value.
 ///
 /// # Returns
 ///
@@ -53,6 +55,19 @@ pub(crate) fn parse_params<T: ToString>(params: Vec<(&str, Option<T>)>) -> Vec<(
         .collect()
 }
 
+/// Converts a timestamp in milliseconds since the epoch, the format of Schwab's `*InLong` and most
+/// other time fields, to a `DateTime<Utc>`.
+///
+/// Returns `None` for values outside the range of `DateTime`.
+pub fn epoch_ms_to_utc(ms: i64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(ms)
+}
+
+/// Like [`epoch_ms_to_utc`], for optional fields.
+pub fn epoch_ms_to_utc_opt(ms: Option<i64>) -> Option<DateTime<Utc>> {
+    ms.and_then(epoch_ms_to_utc)
+}
+
 /// Converts a `DateTime<Utc>` or `String` to an epoch timestamp in milliseconds.
 pub(crate) fn time_to_epoch_ms(date: Option<DateTime<Utc>>) -> Option<String> {
     date.map(|d| d.timestamp_millis().to_string())
@@ -63,6 +78,143 @@ pub(crate) fn time_to_yyyymmdd(date: Option<DateTime<Utc>>) -> Option<String> {
     date.map(|d| d.format("%Y-%m-%d").to_string())
 }
 
+/// Converts a `DateTime<Utc>` to an ISO-8601 timestamp with millisecond precision, as expected by
+/// the Trader API, e.g. "2025-09-19T13:30:00.000Z".
+pub(crate) fn time_to_iso8601(date: Option<DateTime<Utc>>) -> Option<String> {
+    date.map(|d| d.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+}
+
+/// The day-count convention used to turn a time interval into a fraction of a year.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DayCount {
+    /// Actual elapsed time divided by 365 days. The usual convention for option pricing.
+    #[default]
+    Act365,
+    /// Actual elapsed time divided by 360 days.
+    Act360,
+    /// Actual elapsed time divided by 365.25 days, averaging out leap years.
+    Act365_25,
+}
+
+impl DayCount {
+    /// Returns the number of days in a year under this convention.
+    pub fn days_per_year(&self) -> f64 {
+        match self {
+            DayCount::Act365 => 365.0,
+            DayCount::Act360 => 360.0,
+            DayCount::Act365_25 => 365.25,
+        }
+    }
+}
+
+/// Returns the time from `now` until `expiration` as a fraction of a year.
+///
+/// The interval is measured to the millisecond, so the result keeps shrinking during the
+/// expiration day itself. An `expiration` in the past yields `0.0`.
+pub fn year_fraction(now: DateTime<Utc>, expiration: DateTime<Utc>, day_count: DayCount) -> f64 {
+    let millis = (expiration - now).num_milliseconds().max(0) as f64;
+    millis / (86_400_000.0 * day_count.days_per_year())
+}
+
+/// Parses an option expiration date as returned by Schwab.
+///
+/// Option chains report a full timestamp such as `2025-09-19T20:00:00.000+00:00`. A bare
+/// `YYYY-MM-DD` date is taken to expire at 20:00 UTC, the 4pm Eastern close during daylight
+/// saving time.
+pub fn parse_expiration_date(expiration_date: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(expiration_date) {
+        return Some(date_time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(expiration_date, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(20, 0, 0))
+        .map(|date_time| date_time.and_utc())
+}
+
+/// Returns the UTC times at which `date` begins and ends in New York, where the US exchanges
+/// keep their hours.
+///
+/// Follows the US daylight saving rules in force since 2007: UTC-4 from 2:00 local time on the
+/// second Sunday of March until 2:00 on the first Sunday of November, and UTC-5 otherwise. The
+/// days the clocks change are 23 and 25 hours long.
+pub fn new_york_day_bounds(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let next_day = date
+        .succ_opt()
+        .expect("date before the end of the calendar");
+    (new_york_midnight(date), new_york_midnight(next_day))
+}
+
+/// Returns the UTC time of midnight at the start of `date` in New York.
+fn new_york_midnight(date: NaiveDate) -> DateTime<Utc> {
+    let sunday = |month, n| {
+        NaiveDate::from_weekday_of_month_opt(date.year(), month, Weekday::Sun, n)
+            .expect("every month has a first and second Sunday")
+    };
+    // The clocks change at 2:00, so midnight on the day of a change still has the old offset.
+    let daylight_saving = date > sunday(3, 2) && date <= sunday(11, 1);
+    let offset_hours = if daylight_saving { 4 } else { 5 };
+    date.and_time(NaiveTime::MIN).and_utc() + TimeDelta::hours(offset_hours)
+}
+
+/// Computes the volume-weighted average price of `candles`, using each candle's typical price
+/// `(high + low + close) / 3`.
+///
+/// Candles without volume carry no weight and are skipped. Returns `None` if `candles` is empty or
+/// no candle has any volume.
+pub fn vwap(candles: &[Candle]) -> Option<f64> {
+    let mut running = RunningVwap::new();
+    for candle in candles {
+        running.add_candle(candle);
+    }
+    running.value()
+}
+
+/// A VWAP maintained incrementally from live trades or candles, e.g. from the streamer's
+/// `last_price` and `last_size` fields.
+///
+/// Call [`RunningVwap::reset`] at the start of each session to anchor the average there.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RunningVwap {
+    price_volume: f64,
+    volume: f64,
+}
+
+impl RunningVwap {
+    /// Creates an empty `RunningVwap`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a trade of `size` shares at `price`. Trades without a positive size are ignored.
+    pub fn add_trade(&mut self, price: f64, size: f64) {
+        if size > 0.0 && price.is_finite() {
+            self.price_volume += price * size;
+            self.volume += size;
+        }
+    }
+
+    /// Adds a candle at its typical price, weighted by its volume.
+    pub fn add_candle(&mut self, candle: &Candle) {
+        let typical_price = (candle.high + candle.low + candle.close) / 3.0;
+        self.add_trade(typical_price, candle.volume as f64);
+    }
+
+    /// Returns the current VWAP, or `None` until some volume has been added.
+    pub fn value(&self) -> Option<f64> {
+        (self.volume > 0.0).then(|| self.price_volume / self.volume)
+    }
+
+    /// Returns the total volume added so far.
+    pub fn volume(&self) -> f64 {
+        self.volume
+    }
+
+    /// Clears all trades, e.g. at the start of a new session.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
 /// Formats an option contract into the Schwab-standard symbol format.
 /// e.g., format_option_symbol("AAPL", "250919", 'C', 232.5) -> "AAPL  250919C00232500"
 #[allow(dead_code)]
@@ -85,3 +237,276 @@ pub fn format_option_symbol(
         padded_ticker, yymmdd, side, formatted_strike
     )
 }
+
+/// The components of an OSI option symbol, as returned by [`parse_option_symbol`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionSymbol {
+    /// The option root, usually the underlying's ticker, without padding.
+    pub underlying: String,
+    pub expiration: NaiveDate,
+    pub put_call: PutCall,
+    pub strike: f64,
+}
+
+/// Builds the 21-character OSI symbol Schwab uses for an option contract.
+///
+/// The root is padded with spaces to six characters, followed by the expiration as `YYMMDD`,
+/// `C` or `P`, and the strike in thousandths padded to eight digits, e.g.
+/// `build_option_symbol("AAPL", 2024-01-19, PutCall::Call, 190.0)` gives
+/// `AAPL  240119C00190000`. Roots longer than six characters make the symbol longer than 21
+/// characters, and Schwab will not recognize it.
+pub fn build_option_symbol(
+    underlying: &str,
+    expiration: NaiveDate,
+    put_call: PutCall,
+    strike: f64,
+) -> String {
+    let side = match put_call {
+        PutCall::Call => 'C',
+        PutCall::Put => 'P',
+    };
+    format_option_symbol(
+        underlying,
+        &expiration.format("%y%m%d").to_string(),
+        side,
+        strike,
+    )
+}
+
+/// Splits a 21-character OSI option symbol into its components, the inverse of
+/// [`build_option_symbol`].
+///
+/// Returns `None` if `symbol` is not exactly 21 characters or any component is malformed.
+pub fn parse_option_symbol(symbol: &str) -> Option<OptionSymbol> {
+    if symbol.len() != 21 || !symbol.is_ascii() {
+        return None;
+    }
+
+    let underlying = symbol[..6].trim_end();
+    if underlying.is_empty() {
+        return None;
+    }
+    let expiration = NaiveDate::parse_from_str(&symbol[6..12], "%y%m%d").ok()?;
+    let put_call = match &symbol[12..13] {
+        "C" => PutCall::Call,
+        "P" => PutCall::Put,
+        _ => return None,
+    };
+    let strike_digits = &symbol[13..];
+    if !strike_digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let strike = strike_digits.parse::<u32>().ok()? as f64 / 1000.0;
+
+    Some(OptionSymbol {
+        underlying: underlying.to_string(),
+        expiration,
+        put_call,
+        strike,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_york_day_bounds_follow_daylight_saving_time() {
+        let bounds = |date: &str| {
+            let (start, end) = new_york_day_bounds(date.parse().unwrap());
+            (start.to_rfc3339(), end.to_rfc3339())
+        };
+
+        assert_eq!(
+            bounds("2024-01-15"),
+            (
+                "2024-01-15T05:00:00+00:00".to_string(),
+                "2024-01-16T05:00:00+00:00".to_string()
+            )
+        );
+        assert_eq!(
+            bounds("2024-07-01"),
+            (
+                "2024-07-01T04:00:00+00:00".to_string(),
+                "2024-07-02T04:00:00+00:00".to_string()
+            )
+        );
+        assert_eq!(
+            bounds("2024-03-10"),
+            (
+                "2024-03-10T05:00:00+00:00".to_string(),
+                "2024-03-11T04:00:00+00:00".to_string()
+            )
+        );
+        assert_eq!(
+            bounds("2024-11-03"),
+            (
+                "2024-11-03T04:00:00+00:00".to_string(),
+                "2024-11-04T05:00:00+00:00".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn epoch_ms_converts_in_range_values_only() {
+        assert_eq!(
+            epoch_ms_to_utc(1_700_000_000_123),
+            DateTime::parse_from_rfc3339("2023-11-14T22:13:20.123Z")
+                .ok()
+                .map(|time| time.to_utc())
+        );
+        assert_eq!(epoch_ms_to_utc(i64::MAX), None);
+        assert_eq!(epoch_ms_to_utc_opt(Some(i64::MIN)), None);
+        assert_eq!(epoch_ms_to_utc_opt(None), None);
+    }
+
+    #[test]
+    fn option_symbol_encodes_fractional_strikes_in_thousandths() {
+        let expiration = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+
+        let symbol = build_option_symbol("SIRI", expiration, PutCall::Put, 12.5);
+
+        assert_eq!(symbol, "SIRI  240315P00012500");
+        assert_eq!(parse_option_symbol(&symbol).unwrap().strike, 12.5);
+    }
+
+    #[test]
+    fn option_symbol_pads_short_roots_to_six_characters() {
+        let expiration = NaiveDate::from_ymd_opt(2024, 1, 19).unwrap();
+
+        let symbol = build_option_symbol("F", expiration, PutCall::Call, 12.0);
+
+        assert_eq!(symbol, "F     240119C00012000");
+        assert_eq!(symbol.len(), 21);
+        assert_eq!(parse_option_symbol(&symbol).unwrap().underlying, "F");
+    }
+
+    #[test]
+    fn option_symbol_round_trips_through_build_and_parse() {
+        let cases = [
+            ("AAPL", (2024, 1, 19), PutCall::Call, 190.0),
+            ("SPY", (2025, 12, 19), PutCall::Put, 612.5),
+            ("BRKB", (2024, 6, 21), PutCall::Call, 407.125),
+            ("GOOGL", (2026, 1, 16), PutCall::Put, 0.5),
+        ];
+
+        for (underlying, (year, month, day), put_call, strike) in cases {
+            let expected = OptionSymbol {
+                underlying: underlying.to_string(),
+                expiration: NaiveDate::from_ymd_opt(year, month, day).unwrap(),
+                put_call,
+                strike,
+            };
+            let symbol = build_option_symbol(
+                &expected.underlying,
+                expected.expiration,
+                expected.put_call.clone(),
+                expected.strike,
+            );
+
+            assert_eq!(parse_option_symbol(&symbol), Some(expected), "{}", symbol);
+        }
+    }
+
+    #[test]
+    fn option_symbol_parsing_rejects_malformed_symbols() {
+        assert_eq!(parse_option_symbol("AAPL 240119C00190000"), None);
+        assert_eq!(parse_option_symbol("      240119C00190000"), None);
+        assert_eq!(parse_option_symbol("AAPL  241319C00190000"), None);
+        assert_eq!(parse_option_symbol("AAPL  240119X00190000"), None);
+        assert_eq!(parse_option_symbol("AAPL  240119C0019000A"), None);
+    }
+
+    fn candle(high: f64, low: f64, close: f64, volume: i64) -> Candle {
+        Candle {
+            open: close,
+            high,
+            low,
+            close,
+            volume,
+            datetime: 0,
+        }
+    }
+
+    #[test]
+    fn vwap_weights_typical_prices_by_volume() {
+        // Typical prices 10, 12 and 15; the last candle has no volume and carries no weight:
+        // (10 * 100 + 12 * 300) / 400 = 11.5.
+        let candles = [
+            candle(11.0, 9.0, 10.0, 100),
+            candle(12.5, 11.5, 12.0, 300),
+            candle(20.0, 10.0, 15.0, 0),
+        ];
+
+        assert_eq!(vwap(&candles), Some(11.5));
+        assert_eq!(vwap(&candles[2..]), None);
+        assert_eq!(vwap(&[]), None);
+    }
+
+    #[test]
+    fn running_vwap_accumulates_trades_until_reset() {
+        let mut running = RunningVwap::new();
+        assert_eq!(running.value(), None);
+
+        running.add_trade(100.0, 200.0);
+        running.add_trade(101.0, 100.0);
+        running.add_trade(99.5, 0.0);
+        running.add_trade(f64::NAN, 50.0);
+
+        // (100 * 200 + 101 * 100) / 300 = 100.333...
+        let value = running.value().unwrap();
+        assert!((value - 30_100.0 / 300.0).abs() < 1e-9, "{}", value);
+        assert_eq!(running.volume(), 300.0);
+
+        running.add_candle(&candle(11.0, 9.0, 10.0, 300));
+        // (30100 + 10 * 300) / 600 = 55.1666...
+        let value = running.value().unwrap();
+        assert!((value - 33_100.0 / 600.0).abs() < 1e-9, "{}", value);
+
+        running.reset();
+        assert_eq!(running.value(), None);
+        assert_eq!(running.volume(), 0.0);
+    }
+
+    #[test]
+    fn bare_expiration_dates_expire_at_the_close() {
+        assert_eq!(
+            parse_expiration_date("2025-09-19"),
+            DateTime::parse_from_rfc3339("2025-09-19T20:00:00Z")
+                .ok()
+                .map(|time| time.to_utc())
+        );
+        assert_eq!(
+            parse_expiration_date("2025-09-19T20:00:00.000+00:00"),
+            parse_expiration_date("2025-09-19")
+        );
+        assert_eq!(parse_expiration_date("09/19/2025"), None);
+    }
+
+    #[test]
+    fn year_fraction_counts_milliseconds_and_stops_at_zero() {
+        let expiration = parse_expiration_date("2025-09-19").unwrap();
+
+        let one_day = year_fraction(
+            expiration - chrono::Duration::days(1),
+            expiration,
+            DayCount::Act365,
+        );
+        let half_hour = year_fraction(
+            expiration - chrono::Duration::minutes(30),
+            expiration,
+            DayCount::Act360,
+        );
+
+        assert_eq!(one_day, 1.0 / 365.0);
+        assert_eq!(half_hour, 0.5 / (24.0 * 360.0));
+        assert_eq!(
+            year_fraction(
+                expiration,
+                expiration - chrono::Duration::days(1),
+                DayCount::Act365
+            ),
+            0.0
+        );
+    }
+}